@@ -0,0 +1,88 @@
+use crate::Playlist;
+use icu_normalizer::ComposingNormalizerBorrowed;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Summary figures for one playlist, computed once and shared by the renderers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistStats {
+    pub unique_artists: usize,
+    /// Artists ordered by number of tracks, most frequent first. Ties keep the
+    /// order in which the artists first appear in the playlist.
+    pub top_artists: Vec<(String, usize)>,
+    pub first_added: Option<String>,
+    pub last_added: Option<String>,
+}
+
+impl PlaylistStats {
+    pub fn most_frequent_artist(&self) -> Option<&(String, usize)> {
+        self.top_artists.first()
+    }
+}
+
+/// Key under which spellings of the same artist are counted together:
+/// Unicode-normalized (NFKC) and lowercased, so "BEYONCÉ" and "Beyoncé" match
+pub fn artist_key(name: &str) -> String {
+    ComposingNormalizerBorrowed::new_nfkc()
+        .normalize(name.trim())
+        .to_lowercase()
+}
+
+pub fn analyze(playlist: &Playlist) -> PlaylistStats {
+    // Keyed by `artist_key`; the first spelling seen is the one displayed
+    let mut counts: HashMap<String, (&str, usize, usize)> = HashMap::new();
+
+    for (idx, item) in playlist.items.iter().enumerate() {
+        let artist = item.track.artist_name.trim();
+        if artist.is_empty() {
+            continue;
+        }
+        counts
+            .entry(artist_key(artist))
+            .or_insert((artist, 0, idx))
+            .1 += 1;
+    }
+
+    let mut top_artists: Vec<(&str, usize, usize)> = counts.into_values().collect();
+    top_artists.sort_by(|(_, count_a, first_a), (_, count_b, first_b)| {
+        count_b.cmp(count_a).then(first_a.cmp(first_b))
+    });
+
+    let added_dates = playlist
+        .items
+        .iter()
+        .map(|item| item.added_date.as_str())
+        .filter(|date| !date.is_empty());
+
+    PlaylistStats {
+        unique_artists: top_artists.len(),
+        top_artists: top_artists
+            .into_iter()
+            .map(|(artist, count, _)| (artist.to_string(), count))
+            .collect(),
+        first_added: added_dates.clone().min().map(str::to_string),
+        last_added: added_dates.max().map(str::to_string),
+    }
+}
+
+/// Summary figures of every playlist, for the JSON stats report
+pub fn generate_stats_json(playlists: &[Playlist], filenames: &[String]) -> Value {
+    json!({
+        "playlists": playlists.iter().zip(filenames).map(|(playlist, filename)| {
+            let stats = analyze(playlist);
+            json!({
+                "name": playlist.name,
+                "file": filename,
+                "tracks": playlist.items.len(),
+                "followers": playlist.number_of_followers,
+                "unique_artists": stats.unique_artists,
+                "first_added": stats.first_added,
+                "last_added": stats.last_added,
+                "top_artists": stats.top_artists.iter().map(|(artist, count)| json!({
+                    "artist_name": artist,
+                    "tracks": count,
+                })).collect::<Vec<_>>(),
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
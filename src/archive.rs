@@ -0,0 +1,254 @@
+use crate::error::ParseError;
+use crate::export_check::{self, FieldIssue, ParseMode};
+use crate::{Item, Playlist, Root, Track};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// Marks a JSON document as an archive rather than a Spotify export
+pub const ARCHIVE_FORMAT: &str = "spotify_converter.archive";
+
+/// Schema version written; it only goes up with changes older readers would
+/// misread, while new optional fields keep the version
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// File the archive is written to, in the output directory
+pub const ARCHIVE_FILENAME: &str = "archive.json";
+
+/// The crate's own normalized form of a playlist collection. Importers for
+/// other services only need to produce this to be renderable, and it is
+/// accepted as input wherever a Spotify export is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveDocument {
+    /// Always `spotify_converter.archive`
+    #[schemars(extend("const" = ARCHIVE_FORMAT))]
+    pub format: String,
+    /// Schema version the document follows
+    #[schemars(range(min = 1, max = ARCHIVE_VERSION))]
+    pub version: u32,
+    /// Service the playlists came from, such as `spotify`
+    pub source: String,
+    pub playlists: Vec<ArchivePlaylist>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ArchivePlaylist {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<ArchiveUser>,
+    /// Listener a service-generated playlist was made for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub made_for: Option<ArchiveUser>,
+    #[serde(default)]
+    pub followers: i64,
+    /// `YYYY-MM-DD`, or empty when unknown
+    #[serde(default)]
+    pub last_modified: String,
+    #[serde(default)]
+    pub tracks: Vec<ArchiveTrack>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveUser {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ArchiveTrack {
+    pub name: String,
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub album: String,
+    /// Empty for tracks without a service URI, such as local files
+    #[serde(default)]
+    pub uri: String,
+    /// `YYYY-MM-DD`, or empty when unknown
+    #[serde(default)]
+    pub added: String,
+}
+
+/// Owner and listener fields of an export are a bare name or a user object
+fn user_from_value(value: &Value) -> Option<ArchiveUser> {
+    let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+    let user = match value {
+        Value::String(name) => ArchiveUser {
+            id: None,
+            name: Some(name.clone()),
+        },
+        Value::Object(_) => ArchiveUser {
+            id: text("id"),
+            name: text("display_name").or_else(|| text("displayName")),
+        },
+        _ => return None,
+    };
+    (user.id.is_some() || user.name.is_some()).then_some(user)
+}
+
+fn user_to_value(user: Option<&ArchiveUser>) -> Value {
+    let Some(user) = user else {
+        return Value::Null;
+    };
+    let mut object = Map::new();
+    if let Some(id) = &user.id {
+        object.insert("id".to_string(), json!(id));
+    }
+    if let Some(name) = &user.name {
+        object.insert("display_name".to_string(), json!(name));
+    }
+    Value::Object(object)
+}
+
+impl ArchiveDocument {
+    pub fn from_root(root: &Root) -> Self {
+        let playlists = root
+            .playlists
+            .iter()
+            .map(|playlist| ArchivePlaylist {
+                name: playlist.name.clone(),
+                description: playlist
+                    .description
+                    .clone()
+                    .filter(|description| !description.is_empty()),
+                uri: playlist.uri.clone(),
+                owner: user_from_value(&playlist.owner),
+                made_for: user_from_value(&playlist.made_for),
+                followers: playlist.number_of_followers,
+                last_modified: playlist.last_modified_date.clone(),
+                tracks: playlist
+                    .items
+                    .iter()
+                    .map(|item| ArchiveTrack {
+                        name: item.title().to_string(),
+                        artist: item.creator().to_string(),
+                        album: item.album().into_owned(),
+                        uri: item.uri().to_string(),
+                        added: item.added_date.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            source: "spotify".to_string(),
+            playlists,
+        }
+    }
+
+    pub fn into_root(self) -> Root {
+        let playlists = self
+            .playlists
+            .into_iter()
+            .map(|playlist| Playlist {
+                name: playlist.name,
+                last_modified_date: playlist.last_modified,
+                collaborators: Vec::new(),
+                items: playlist
+                    .tracks
+                    .into_iter()
+                    .map(|track| Item {
+                        track: Track {
+                            track_name: track.name,
+                            artist_name: track.artist,
+                            album_name: track.album,
+                            track_uri: track.uri,
+                        },
+                        added_date: track.added,
+                        ..Item::default()
+                    })
+                    .collect(),
+                description: playlist.description,
+                number_of_followers: playlist.followers,
+                uri: playlist.uri,
+                owner: user_to_value(playlist.owner.as_ref()),
+                made_for: user_to_value(playlist.made_for.as_ref()),
+            })
+            .collect();
+        Root { playlists }
+    }
+}
+
+/// Parses input JSON as either an archive, recognized by its `format`
+/// field, or a Spotify export, held to the expected format as `mode` says.
+/// The fields of an export that didn't match are returned; an archive is
+/// written by this crate and always matches.
+pub fn parse_input(text: &str, mode: ParseMode) -> Result<(Root, Vec<FieldIssue>), ParseError> {
+    let mut value: Value = serde_json::from_str(text).map_err(|e| ParseError::from_json(&e))?;
+    if value.get("format").and_then(Value::as_str) != Some(ARCHIVE_FORMAT) {
+        let issues = export_check::check_export(&mut value, mode)?;
+        let root = serde_json::from_value(value)
+            .map_err(|e| ParseError::new(format!("not a Spotify playlist export: {}", e)))?;
+        return Ok((root, issues));
+    }
+
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version == 0 || version > u64::from(ARCHIVE_VERSION) {
+        return Err(ParseError::new(format!(
+            "archive version {} is not supported; this version reads up to {}",
+            version, ARCHIVE_VERSION
+        )));
+    }
+    let document: ArchiveDocument = serde_json::from_value(value)
+        .map_err(|e| ParseError::new(format!("invalid archive: {}", e)))?;
+    Ok((document.into_root(), Vec::new()))
+}
+
+pub fn generate_archive(root: &Root) -> String {
+    serde_json::to_string_pretty(&ArchiveDocument::from_root(root)).unwrap_or_default()
+}
+
+/// Input shapes a JSON Schema can be generated for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemaShape {
+    /// A Spotify playlist export
+    Export,
+    /// The crate's archive format
+    Archive,
+}
+
+pub fn parse_schema_shape(value: &str) -> Result<SchemaShape, String> {
+    match value {
+        "export" => Ok(SchemaShape::Export),
+        "archive" => Ok(SchemaShape::Archive),
+        _ => Err(format!(
+            "schema must be either 'export' or 'archive' (got '{}')",
+            value
+        )),
+    }
+}
+
+/// A JSON Schema (draft 2020-12) for one input shape, or without one for
+/// any input this crate accepts: a Spotify export or an archive
+pub fn input_schema(shape: Option<SchemaShape>) -> Value {
+    let generator = schemars::SchemaGenerator::default();
+    match shape {
+        Some(SchemaShape::Export) => generator.into_root_schema_for::<Root>().to_value(),
+        Some(SchemaShape::Archive) => generator
+            .into_root_schema_for::<ArchiveDocument>()
+            .to_value(),
+        None => {
+            let mut generator = generator;
+            let export = generator.subschema_for::<Root>();
+            let archive = generator.subschema_for::<ArchiveDocument>();
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "title": "spotify_converter input",
+                "description": format!(
+                    "A Spotify playlist export, or an archive of format {} up to version {}",
+                    ARCHIVE_FORMAT, ARCHIVE_VERSION
+                ),
+                "anyOf": [export, archive],
+                "$defs": generator.take_definitions(true),
+            })
+        }
+    }
+}
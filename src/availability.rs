@@ -0,0 +1,550 @@
+use crate::journal::Journal;
+use crate::spotify::{self, ApiTrack};
+use crate::{
+    Playlist, bidi_html, escape_html, escape_markdown, get_common_styles, markdown_link_target,
+    track_json, uri,
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// A track of the export that can no longer be played on Spotify
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnavailableTrack {
+    pub playlist: usize,
+    pub item: usize,
+    pub reason: String,
+}
+
+/// Where a suggested replacement URI came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplacementSource {
+    /// Spotify relinked the track to another release of it
+    Relinked,
+    /// A catalogue search by title and artist found a playable match
+    Search,
+}
+
+impl ReplacementSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReplacementSource::Relinked => "relinked by Spotify",
+            ReplacementSource::Search => "search match",
+        }
+    }
+}
+
+/// A suggested new URI for a track whose exported URI is dead or moved
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replacement {
+    pub playlist: usize,
+    pub item: usize,
+    pub uri: String,
+    pub source: ReplacementSource,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Availability {
+    pub unavailable: Vec<UnavailableTrack>,
+    /// Tracks Spotify answered with a different, playable release
+    pub relinked: Vec<Replacement>,
+}
+
+/// What a lookup found out about one track id, as kept in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum TrackOutcome {
+    Available,
+    Unavailable(String),
+    Relinked(String),
+}
+
+/// Why Spotify reports a track as unplayable, or `None` if it plays fine
+fn unavailable_reason(track: Option<&ApiTrack>) -> Option<String> {
+    let Some(track) = track else {
+        return Some("removed from Spotify".to_string());
+    };
+    if let Some(restrictions) = &track.restrictions {
+        return Some(format!("restricted ({})", restrictions.reason));
+    }
+    if track.is_playable == Some(false) {
+        return Some("not playable in this market".to_string());
+    }
+    if track
+        .available_markets
+        .as_ref()
+        .is_some_and(|markets| markets.is_empty())
+    {
+        return Some("not available in any market".to_string());
+    }
+    None
+}
+
+/// Looks up every distinct track of the export through the API and returns
+/// the unavailable and relinked ones, in export order. Local files and other
+/// non-track items are skipped. Relinking only happens when a market is given.
+/// Ids already in the journal are not looked up again.
+pub fn check_availability(
+    client: &spotify::Client,
+    playlists: &[Playlist],
+    market: Option<&str>,
+    journal: &mut Journal,
+) -> Result<Availability, String> {
+    let track_key = |id: &str| format!("track:{}", id);
+
+    let mut ids: Vec<&str> = playlists
+        .iter()
+        .flat_map(|playlist| &playlist.items)
+        .filter_map(|item| uri::track_id(&item.track.track_uri))
+        .filter(|id| journal.get::<TrackOutcome>(&track_key(id)).is_none())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    for batch in ids.chunks(spotify::TRACKS_PER_REQUEST) {
+        let tracks = client.tracks(batch, market)?;
+        for (id, track) in batch.iter().zip(tracks.iter()) {
+            let outcome = if let Some(reason) = unavailable_reason(track.as_ref()) {
+                TrackOutcome::Unavailable(reason)
+            } else if let Some(track) = track
+                && track.linked_from.is_some()
+            {
+                TrackOutcome::Relinked(track.uri.clone())
+            } else {
+                TrackOutcome::Available
+            };
+            journal
+                .record(&track_key(id), &outcome)
+                .map_err(|e| format!("could not write journal: {}", e))?;
+        }
+    }
+
+    let mut availability = Availability::default();
+    for (playlist_idx, playlist) in playlists.iter().enumerate() {
+        for (item_idx, item) in playlist.items.iter().enumerate() {
+            let Some(id) = uri::track_id(&item.track.track_uri) else {
+                continue;
+            };
+            match journal.get(&track_key(id)) {
+                Some(TrackOutcome::Unavailable(reason)) => {
+                    availability.unavailable.push(UnavailableTrack {
+                        playlist: playlist_idx,
+                        item: item_idx,
+                        reason,
+                    });
+                }
+                Some(TrackOutcome::Relinked(uri)) => {
+                    availability.relinked.push(Replacement {
+                        playlist: playlist_idx,
+                        item: item_idx,
+                        uri,
+                        source: ReplacementSource::Relinked,
+                    });
+                }
+                Some(TrackOutcome::Available) | None => {}
+            }
+        }
+    }
+
+    Ok(availability)
+}
+
+/// Normalizes names for comparing search results with exported metadata
+fn match_key(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Searches the catalogue for a playable track with the same title and
+/// artist as each unavailable one. Returns the matches together with the
+/// tracks Spotify relinked, in export order. Searches already in the journal
+/// are not repeated.
+pub fn find_replacements(
+    client: &spotify::Client,
+    playlists: &[Playlist],
+    availability: &Availability,
+    market: Option<&str>,
+    journal: &mut Journal,
+) -> Result<Vec<Replacement>, String> {
+    let mut found: HashMap<&str, Option<String>> = HashMap::new();
+    let mut replacements = availability.relinked.clone();
+
+    for unavailable in &availability.unavailable {
+        let track = &playlists[unavailable.playlist].items[unavailable.item].track;
+        let search_key = format!("search:{}", track.track_uri);
+        if let Some(candidate) = journal.get::<Option<String>>(&search_key) {
+            found.insert(&track.track_uri, candidate);
+        }
+        if !found.contains_key(track.track_uri.as_str()) {
+            let title = match_key(&track.track_name);
+            let artist = match_key(&track.artist_name);
+            let candidate = client
+                .search_tracks(&track.track_name, &track.artist_name, market)?
+                .into_iter()
+                .filter(|candidate| candidate.uri != track.track_uri)
+                .filter(|candidate| unavailable_reason(Some(candidate)).is_none())
+                .find(|candidate| {
+                    match_key(&candidate.name) == title
+                        && candidate
+                            .artists
+                            .iter()
+                            .any(|candidate_artist| match_key(&candidate_artist.name) == artist)
+                })
+                .map(|candidate| candidate.uri);
+            journal
+                .record(&search_key, &candidate)
+                .map_err(|e| format!("could not write journal: {}", e))?;
+            found.insert(&track.track_uri, candidate);
+        }
+
+        if let Some(Some(uri)) = found.get(track.track_uri.as_str()) {
+            replacements.push(Replacement {
+                playlist: unavailable.playlist,
+                item: unavailable.item,
+                uri: uri.clone(),
+                source: ReplacementSource::Search,
+            });
+        }
+    }
+
+    replacements.sort_by_key(|replacement| (replacement.playlist, replacement.item));
+    Ok(replacements)
+}
+
+/// Groups report entries by playlist, keeping export order
+fn by_playlist<T>(entries: &[T], playlist_of: impl Fn(&T) -> usize) -> Vec<(usize, Vec<&T>)> {
+    let mut groups: Vec<(usize, Vec<&T>)> = Vec::new();
+    for entry in entries {
+        let playlist = playlist_of(entry);
+        match groups.last_mut() {
+            Some((current, members)) if *current == playlist => members.push(entry),
+            _ => groups.push((playlist, vec![entry])),
+        }
+    }
+    groups
+}
+
+pub fn generate_availability_markdown(
+    unavailable: &[UnavailableTrack],
+    playlists: &[Playlist],
+    filenames: &[String],
+    market: Option<&str>,
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Unavailable Tracks\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+
+    let groups = by_playlist(unavailable, |track| track.playlist);
+    md.push_str(&format!(
+        "- **Unavailable Tracks:** {}\n",
+        unavailable.len()
+    ));
+    md.push_str(&format!("- **Affected Playlists:** {}\n", groups.len()));
+    if let Some(market) = market {
+        md.push_str(&format!("- **Market:** {}\n", market));
+    }
+    md.push('\n');
+
+    for (playlist_idx, tracks) in &groups {
+        let playlist = &playlists[*playlist_idx];
+        md.push_str(&format!(
+            "## [{}]({}) ({} of {})\n\n",
+            escape_markdown(&playlist.name),
+            markdown_link_target(&filenames[*playlist_idx]),
+            tracks.len(),
+            playlist.items.len()
+        ));
+        for unavailable_track in tracks {
+            let track = &playlist.items[unavailable_track.item].track;
+            md.push_str(&format!(
+                "- {}. {} - {} ({})\n",
+                unavailable_track.item + 1,
+                escape_markdown(&track.track_name),
+                escape_markdown(&track.artist_name),
+                unavailable_track.reason
+            ));
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+pub fn generate_availability_html(
+    unavailable: &[UnavailableTrack],
+    playlists: &[Playlist],
+    filenames: &[String],
+    market: Option<&str>,
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Unavailable Tracks</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        .reason {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Unavailable Tracks</h1>\n");
+
+    let groups = by_playlist(unavailable, |track| track.playlist);
+    html.push_str("        <ul>\n");
+    html.push_str(&format!(
+        "            <li><strong>Unavailable Tracks:</strong> {}</li>\n",
+        unavailable.len()
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Affected Playlists:</strong> {}</li>\n",
+        groups.len()
+    ));
+    if let Some(market) = market {
+        html.push_str(&format!(
+            "            <li><strong>Market:</strong> {}</li>\n",
+            escape_html(market)
+        ));
+    }
+    html.push_str("        </ul>\n");
+
+    for (playlist_idx, tracks) in &groups {
+        let playlist = &playlists[*playlist_idx];
+        html.push_str(&format!(
+            "        <h2><a href=\"{}\">{}</a> ({} of {})</h2>\n",
+            escape_html(&filenames[*playlist_idx]),
+            bidi_html(&playlist.name),
+            tracks.len(),
+            playlist.items.len()
+        ));
+        html.push_str("        <ul>\n");
+        for unavailable_track in tracks {
+            let track = &playlist.items[unavailable_track.item].track;
+            html.push_str(&format!(
+                "            <li>{}. {} - {} <span class=\"reason\">({})</span></li>\n",
+                unavailable_track.item + 1,
+                bidi_html(&track.track_name),
+                bidi_html(&track.artist_name),
+                escape_html(&unavailable_track.reason)
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_remediation_markdown(
+    replacements: &[Replacement],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Suggested Replacements\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+
+    let groups = by_playlist(replacements, |replacement| replacement.playlist);
+    md.push_str(&format!(
+        "- **Replaceable Tracks:** {}\n",
+        replacements.len()
+    ));
+    md.push_str(&format!("- **Affected Playlists:** {}\n\n", groups.len()));
+
+    for (playlist_idx, members) in &groups {
+        let playlist = &playlists[*playlist_idx];
+        md.push_str(&format!(
+            "## [{}]({})\n\n",
+            escape_markdown(&playlist.name),
+            markdown_link_target(&filenames[*playlist_idx])
+        ));
+        md.push_str("| # | Track | Artist | Exported URI | Suggested URI | Source |\n");
+        md.push_str("|---|-------|--------|--------------|---------------|--------|\n");
+        for replacement in members {
+            let track = &playlist.items[replacement.item].track;
+            md.push_str(&format!(
+                "| {} | {} | {} | `{}` | `{}` | {} |\n",
+                replacement.item + 1,
+                escape_markdown(&track.track_name),
+                escape_markdown(&track.artist_name),
+                track.track_uri,
+                replacement.uri,
+                replacement.source.label()
+            ));
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+pub fn generate_remediation_html(
+    replacements: &[Replacement],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Suggested Replacements</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        table {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            border-collapse: collapse;\n");
+    html.push_str("        }\n");
+    html.push_str("        th {\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            text-align: start;\n");
+    html.push_str("        }\n");
+    html.push_str("        td {\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            border-bottom: 1px solid #ddd;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Suggested Replacements</h1>\n");
+
+    let groups = by_playlist(replacements, |replacement| replacement.playlist);
+    html.push_str("        <ul>\n");
+    html.push_str(&format!(
+        "            <li><strong>Replaceable Tracks:</strong> {}</li>\n",
+        replacements.len()
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Affected Playlists:</strong> {}</li>\n",
+        groups.len()
+    ));
+    html.push_str("        </ul>\n");
+
+    for (playlist_idx, members) in &groups {
+        let playlist = &playlists[*playlist_idx];
+        html.push_str(&format!(
+            "        <h2><a href=\"{}\">{}</a></h2>\n",
+            escape_html(&filenames[*playlist_idx]),
+            bidi_html(&playlist.name)
+        ));
+        html.push_str("        <table>\n");
+        html.push_str("            <thead>\n");
+        html.push_str("                <tr>\n");
+        for heading in [
+            "#",
+            "Track",
+            "Artist",
+            "Exported URI",
+            "Suggested URI",
+            "Source",
+        ] {
+            html.push_str(&format!("                    <th>{}</th>\n", heading));
+        }
+        html.push_str("                </tr>\n");
+        html.push_str("            </thead>\n");
+        html.push_str("            <tbody>\n");
+        for replacement in members {
+            let track = &playlist.items[replacement.item].track;
+            html.push_str("                <tr>\n");
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                replacement.item + 1
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                bidi_html(&track.track_name)
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                bidi_html(&track.artist_name)
+            ));
+            html.push_str(&format!(
+                "                    <td><code>{}</code></td>\n",
+                escape_html(&track.track_uri)
+            ));
+            html.push_str(&format!(
+                "                    <td><a href=\"{}\"><code>{}</code></a></td>\n",
+                escape_html(&replacement.uri),
+                escape_html(&replacement.uri)
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                replacement.source.label()
+            ));
+            html.push_str("                </tr>\n");
+        }
+        html.push_str("            </tbody>\n");
+        html.push_str("        </table>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_availability_json(
+    unavailable: &[UnavailableTrack],
+    playlists: &[Playlist],
+    filenames: &[String],
+    market: Option<&str>,
+) -> Value {
+    json!({
+        "market": market,
+        "playlists": by_playlist(unavailable, |track| track.playlist)
+            .iter()
+            .map(|(playlist_idx, tracks)| {
+                let playlist = &playlists[*playlist_idx];
+                json!({
+                    "name": playlist.name,
+                    "file": filenames[*playlist_idx],
+                    "tracks": tracks.iter().map(|unavailable_track| {
+                        let mut track = track_json(playlist, unavailable_track.item);
+                        track["reason"] = json!(unavailable_track.reason);
+                        track
+                    }).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+pub fn generate_remediation_json(
+    replacements: &[Replacement],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> Value {
+    json!({
+        "playlists": by_playlist(replacements, |replacement| replacement.playlist)
+            .iter()
+            .map(|(playlist_idx, members)| {
+                let playlist = &playlists[*playlist_idx];
+                json!({
+                    "name": playlist.name,
+                    "file": filenames[*playlist_idx],
+                    "tracks": members.iter().map(|replacement| {
+                        let mut track = track_json(playlist, replacement.item);
+                        track["suggested_uri"] = json!(replacement.uri);
+                        track["source"] = json!(replacement.source.label());
+                        track
+                    }).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
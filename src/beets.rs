@@ -0,0 +1,62 @@
+use crate::Playlist;
+use std::collections::HashSet;
+
+/// File the beets configuration is written to, in the output directory
+pub const BEETS_FILENAME: &str = "beets-smartplaylist.yaml";
+
+/// Quotes a value as one word for beets' shell-style query parsing
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Single-quoted YAML scalar
+fn yaml_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// A configuration section for the beets `smartplaylist` plugin with one
+/// playlist per Spotify playlist, each matching its tracks by artist and
+/// title. Merged into a beets config, `beet splupdate` writes the matching
+/// library items to `<name>.m3u` files. Playlists without any matchable
+/// track are left out.
+pub fn generate_beets_config(playlists: &[Playlist], basenames: &[String]) -> String {
+    let mut yaml = String::new();
+    yaml.push_str(
+        "# Smart playlists mirroring a Spotify export, for the beets smartplaylist plugin.\n",
+    );
+    yaml.push_str("# Merge into your beets config.yaml, then run `beet splupdate`.\n");
+    yaml.push_str("smartplaylist:\n");
+    yaml.push_str("    playlists:\n");
+
+    for (playlist, basename) in playlists.iter().zip(basenames) {
+        let mut queries: Vec<String> = playlist
+            .items
+            .iter()
+            .map(|item| &item.track)
+            .filter(|track| !track.track_name.is_empty() && !track.artist_name.is_empty())
+            .map(|track| {
+                format!(
+                    "{} {}",
+                    shell_quote(&format!("artist:{}", track.artist_name)),
+                    shell_quote(&format!("title:{}", track.track_name))
+                )
+            })
+            .collect();
+        let mut seen = HashSet::new();
+        queries.retain(|query| seen.insert(query.clone()));
+        if queries.is_empty() {
+            continue;
+        }
+
+        yaml.push_str(&format!(
+            "        - name: {}\n",
+            yaml_quote(&format!("{}.m3u", basename))
+        ));
+        yaml.push_str("          query:\n");
+        for query in queries {
+            yaml.push_str(&format!("              - {}\n", yaml_quote(&query)));
+        }
+    }
+
+    yaml
+}
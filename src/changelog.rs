@@ -0,0 +1,317 @@
+use crate::diff::ExportDiff;
+use crate::{
+    Item, Playlist, bidi_html, escape_html, escape_markdown, get_common_styles,
+    markdown_link_target, output,
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// History of the runs with `--changelog` into an output directory, kept
+/// there across runs
+pub const HISTORY_FILENAME: &str = ".changelog-history.json";
+
+/// Tracks added to and removed from one playlist, as "Title – Artist"
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistTracks {
+    pub playlist: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// What one regeneration changed since the one before it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Generation {
+    /// Day of the run, UTC
+    pub date: String,
+    /// Playlists the run converted
+    pub playlists: usize,
+    /// The first run recorded, with nothing earlier to compare to
+    #[serde(default)]
+    pub first: bool,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Old and new names
+    pub renamed: Vec<(String, String)>,
+    pub changed: Vec<PlaylistTracks>,
+}
+
+fn describe(item: &Item) -> String {
+    format!("{} – {}", item.title(), item.creator())
+}
+
+impl Generation {
+    /// The first generation of an output directory
+    pub fn first(date: String, playlists: usize) -> Self {
+        Generation {
+            date,
+            playlists,
+            first: true,
+            ..Generation::default()
+        }
+    }
+
+    pub fn from_diff(date: String, diff: &ExportDiff, old: &[Playlist], new: &[Playlist]) -> Self {
+        Generation {
+            date,
+            playlists: new.len(),
+            first: false,
+            added: diff
+                .added
+                .iter()
+                .map(|&idx| new[idx].name.clone())
+                .collect(),
+            removed: diff
+                .removed
+                .iter()
+                .map(|&idx| old[idx].name.clone())
+                .collect(),
+            renamed: diff
+                .renamed(old, new)
+                .map(|change| (old[change.old].name.clone(), new[change.new].name.clone()))
+                .collect(),
+            changed: diff
+                .modified()
+                .map(|change| PlaylistTracks {
+                    playlist: new[change.new].name.clone(),
+                    added: change
+                        .added_tracks
+                        .iter()
+                        .map(|&idx| describe(&new[change.new].items[idx]))
+                        .collect(),
+                    removed: change
+                        .removed_tracks
+                        .iter()
+                        .map(|&idx| describe(&old[change.old].items[idx]))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether the run changed nothing, and so isn't worth an entry
+    pub fn is_empty(&self) -> bool {
+        !self.first
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+/// The generations recorded in `output_dir`, oldest first; none if no run
+/// has written any
+pub fn load_history(output_dir: &Path) -> Vec<Generation> {
+    fs::read_to_string(output_dir.join(HISTORY_FILENAME))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_history(output_dir: &Path, history: &[Generation]) -> io::Result<()> {
+    fs::write(
+        output::output_file(output_dir, HISTORY_FILENAME).map_err(io::Error::other)?,
+        serde_json::to_string_pretty(history)?,
+    )
+}
+
+/// The file of the current playlist named `name`, if there still is one
+fn playlist_file<'a>(
+    name: &str,
+    playlists: &[Playlist],
+    filenames: &'a [String],
+) -> Option<&'a str> {
+    playlists
+        .iter()
+        .position(|playlist| playlist.name == name)
+        .and_then(|idx| filenames.get(idx))
+        .map(String::as_str)
+}
+
+pub fn generate_changelog_markdown(
+    history: &[Generation],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut md = String::new();
+    let link = |name: &str| match playlist_file(name, playlists, filenames) {
+        Some(file) => format!(
+            "[{}]({})",
+            escape_markdown(name),
+            markdown_link_target(file)
+        ),
+        None => escape_markdown(name),
+    };
+
+    md.push_str("# Changelog\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+
+    for generation in history.iter().rev() {
+        md.push_str(&format!("## {}\n\n", generation.date));
+        if generation.first {
+            md.push_str(&format!(
+                "First generation, with {} playlists.\n\n",
+                generation.playlists
+            ));
+            continue;
+        }
+        for name in &generation.added {
+            md.push_str(&format!("- **Added:** {}\n", link(name)));
+        }
+        for name in &generation.removed {
+            md.push_str(&format!("- **Removed:** {}\n", escape_markdown(name)));
+        }
+        for (old, new) in &generation.renamed {
+            md.push_str(&format!(
+                "- **Renamed:** {} → {}\n",
+                escape_markdown(old),
+                link(new)
+            ));
+        }
+        for change in &generation.changed {
+            md.push_str(&format!(
+                "- **Changed:** {} (+{} −{})\n",
+                link(&change.playlist),
+                change.added.len(),
+                change.removed.len()
+            ));
+            for track in &change.added {
+                md.push_str(&format!("  - + {}\n", escape_markdown(track)));
+            }
+            for track in &change.removed {
+                md.push_str(&format!("  - ~~{}~~\n", escape_markdown(track)));
+            }
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+pub fn generate_changelog_html(
+    history: &[Generation],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut html = String::new();
+    let link = |name: &str| match playlist_file(name, playlists, filenames) {
+        Some(file) => format!("<a href=\"{}\">{}</a>", escape_html(file), bidi_html(name)),
+        None => bidi_html(name),
+    };
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Changelog</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        .tracks {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("        }\n");
+    html.push_str("        .tracks del {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Changelog</h1>\n");
+
+    for generation in history.iter().rev() {
+        html.push_str(&format!(
+            "        <h2>{}</h2>\n",
+            escape_html(&generation.date)
+        ));
+        if generation.first {
+            html.push_str(&format!(
+                "        <p>First generation, with {} playlists.</p>\n",
+                generation.playlists
+            ));
+            continue;
+        }
+        html.push_str("        <ul>\n");
+        for name in &generation.added {
+            html.push_str(&format!(
+                "            <li><strong>Added:</strong> {}</li>\n",
+                link(name)
+            ));
+        }
+        for name in &generation.removed {
+            html.push_str(&format!(
+                "            <li><strong>Removed:</strong> {}</li>\n",
+                bidi_html(name)
+            ));
+        }
+        for (old, new) in &generation.renamed {
+            html.push_str(&format!(
+                "            <li><strong>Renamed:</strong> {} → {}</li>\n",
+                bidi_html(old),
+                link(new)
+            ));
+        }
+        for change in &generation.changed {
+            html.push_str(&format!(
+                "            <li><strong>Changed:</strong> {} (+{} −{})\n",
+                link(&change.playlist),
+                change.added.len(),
+                change.removed.len()
+            ));
+            html.push_str("                <ul class=\"tracks\">\n");
+            for track in &change.added {
+                html.push_str(&format!(
+                    "                    <li>+ {}</li>\n",
+                    bidi_html(track)
+                ));
+            }
+            for track in &change.removed {
+                html.push_str(&format!(
+                    "                    <li><del>{}</del></li>\n",
+                    bidi_html(track)
+                ));
+            }
+            html.push_str("                </ul>\n");
+            html.push_str("            </li>\n");
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_changelog_json(
+    history: &[Generation],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> Value {
+    json!({
+        "generations": history.iter().rev().map(|generation| {
+            json!({
+                "date": generation.date,
+                "playlists": generation.playlists,
+                "first": generation.first,
+                "added": generation.added.iter().map(|name| json!({
+                    "name": name,
+                    "file": playlist_file(name, playlists, filenames),
+                })).collect::<Vec<_>>(),
+                "removed": generation.removed,
+                "renamed": generation.renamed.iter().map(|(old, new)| json!({
+                    "old_name": old,
+                    "new_name": new,
+                })).collect::<Vec<_>>(),
+                "changed": generation.changed.iter().map(|change| json!({
+                    "name": change.playlist,
+                    "file": playlist_file(&change.playlist, playlists, filenames),
+                    "added_tracks": change.added,
+                    "removed_tracks": change.removed,
+                })).collect::<Vec<_>>(),
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,43 @@
+use icu_collator::CollatorBorrowed;
+use icu_collator::options::CollatorOptions;
+use icu_locale_core::Locale;
+use std::cmp::Ordering;
+
+/// Alphabetical ordering following the Unicode Collation Algorithm, tailored
+/// to a locale when one is given, so "Éowyn" sorts with the E's and "Ö"
+/// lands where Swedish or German readers expect it
+#[derive(Debug, Clone, Default)]
+pub struct Collation {
+    locale: Option<Locale>,
+}
+
+/// Parses a BCP 47 locale such as `sv`, `de-AT` or `es-u-co-trad`
+pub fn parse_locale(value: &str) -> Result<Locale, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid locale, e.g. en, sv or de-AT", value))
+}
+
+impl Collation {
+    pub fn new(locale: Option<Locale>) -> Self {
+        Self { locale }
+    }
+
+    fn collator(&self) -> Option<CollatorBorrowed<'static>> {
+        let prefs = match &self.locale {
+            Some(locale) => locale.into(),
+            None => Default::default(),
+        };
+        CollatorBorrowed::try_new(prefs, CollatorOptions::default()).ok()
+    }
+
+    /// Comparison function for sorting. Locales without collation data fall
+    /// back to plain code point order.
+    pub fn comparator(&self) -> impl Fn(&str, &str) -> Ordering {
+        let collator = self.collator();
+        move |a, b| match &collator {
+            Some(collator) => collator.compare(a, b),
+            None => a.cmp(b),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// This tool's directory in the platform's config directory
+pub fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    };
+    Some(base?.join("spotify_converter"))
+}
+
+/// Where `auth login` keeps credentials: `credentials.json`, or
+/// `profiles/<name>.credentials.json` for a profile
+pub fn credentials_path(profile: Option<&str>) -> Option<PathBuf> {
+    let dir = config_dir()?;
+    Some(match profile {
+        Some(name) => dir
+            .join("profiles")
+            .join(format!("{}.credentials.json", name)),
+        None => dir.join("credentials.json"),
+    })
+}
+
+/// Settings file of a profile: `profiles/<name>.json`
+pub fn profile_path(name: &str) -> Option<PathBuf> {
+    Some(
+        config_dir()?
+            .join("profiles")
+            .join(format!("{}.json", name)),
+    )
+}
+
+/// Checks a profile name is usable as part of a filename
+pub fn parse_profile_name(value: &str) -> Result<String, String> {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "profile names may only contain letters, digits, '-' and '_' (got '{}')",
+            value
+        ))
+    }
+}
+
+/// The options a profile sets, as command-line arguments grouped by long
+/// option name. The profile file is a JSON object from long option names to
+/// values, e.g. `{"output": "/srv/archive/alex", "format": "html", "tags": true}`:
+/// `true` passes a flag, `false` and `null` leave it out, and arrays repeat
+/// the option.
+pub fn profile_args(name: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let path = profile_path(name).ok_or("no home or config directory to read profiles from")?;
+    let text = fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "could not read profile '{}' at {}: {}",
+            name,
+            path.display(),
+            e
+        )
+    })?;
+    let settings: serde_json::Map<String, Value> = serde_json::from_str(&text)
+        .map_err(|e| format!("profile {} is not a JSON object: {}", path.display(), e))?;
+
+    let mut args = Vec::new();
+    for (key, value) in settings {
+        let long = key.trim_start_matches('-').replace('_', "-");
+        let option = format!("--{}", long);
+        if option == "--profile" {
+            return Err(format!(
+                "profile {} cannot select a profile",
+                path.display()
+            ));
+        }
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        let mut option_args = Vec::new();
+        for value in values {
+            match value {
+                Value::Bool(true) => option_args.push(option.clone()),
+                Value::Bool(false) | Value::Null => {}
+                Value::String(text) => option_args.extend([option.clone(), text]),
+                Value::Number(number) => option_args.extend([option.clone(), number.to_string()]),
+                _ => {
+                    return Err(format!(
+                        "profile {}: '{}' must be a string, number, boolean or list of them",
+                        path.display(),
+                        key
+                    ));
+                }
+            }
+        }
+        args.push((long, option_args));
+    }
+    Ok(args)
+}
@@ -0,0 +1,63 @@
+use anstyle::{AnsiColor, Style};
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+const SUCCESS: Style = AnsiColor::Green.on_default();
+const WARNING: Style = AnsiColor::Yellow.on_default();
+const FAILURE: Style = AnsiColor::Red.on_default();
+const ERROR: Style = AnsiColor::Red.on_default().bold();
+
+/// Turns color off for the rest of the run, for `--no-color`. Without it
+/// color is still left out when the output isn't a terminal, or when
+/// NO_COLOR is set.
+pub fn disable_color() {
+    anstream::ColorChoice::Never.write_global();
+}
+
+/// Whether the status symbols can be shown: only a locale explicitly set to
+/// another encoding than UTF-8 gets ASCII ones instead
+fn unicode_symbols() -> bool {
+    static UNICODE: OnceLock<bool> = OnceLock::new();
+    *UNICODE.get_or_init(|| {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .is_none_or(|locale| {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            })
+    })
+}
+
+/// The symbol, or its ASCII stand-in. `⚠` is followed by the text
+/// presentation selector, without which some terminals draw it as a
+/// two-column emoji and push the message out of line.
+fn symbol(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if unicode_symbols() { unicode } else { ascii }
+}
+
+/// `  ✓ message` on stdout, for something done
+pub fn success(message: impl Display) {
+    anstream::println!("  {SUCCESS}{}{SUCCESS:#} {}", symbol("✓", "+"), message);
+}
+
+/// `  ⚠ message` on stderr, for something that looks wrong but didn't stop
+/// the run
+pub fn warning(message: impl Display) {
+    anstream::eprintln!(
+        "  {WARNING}{}{WARNING:#} {}",
+        symbol("⚠\u{fe0e}", "!"),
+        message
+    );
+}
+
+/// `  ✗ message` on stderr, for one item of many that failed
+pub fn failure(message: impl Display) {
+    anstream::eprintln!("  {FAILURE}{}{FAILURE:#} {}", symbol("✗", "x"), message);
+}
+
+/// `Error: message` on stderr, for what stops the run
+pub fn error(message: impl Display) {
+    anstream::eprintln!("{ERROR}Error:{ERROR:#} {}", message);
+}
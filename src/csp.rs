@@ -0,0 +1,138 @@
+use crate::output;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Directory, relative to the output directory, holding the extracted assets
+pub const ASSETS_DIR: &str = "assets";
+
+/// Policy the externalized pages satisfy: everything from the archive's own
+/// origin, nothing inline, no plugins or framing of foreign content.
+pub const POLICY: &str = "default-src 'none'; style-src 'self'; script-src 'self'; img-src 'self' data:; media-src 'self'; base-uri 'none'; form-action 'none'";
+
+/// FNV-1a, used to give assets content-derived filenames so browsers can
+/// cache them indefinitely and identical assets are only written once.
+pub fn content_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Collects the inline `<style>` and `<script>` blocks of generated pages as
+/// hashed external files, for hosts that enforce a strict Content Security Policy.
+/// Preview clips are kept apart, in [`crate::previews::previews_dir`]: they
+/// are named by track id, so later runs find them without downloading again.
+#[derive(Debug, Default)]
+pub struct AssetStore {
+    assets: BTreeMap<String, String>,
+}
+
+impl AssetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(&mut self, kind: &str, extension: &str, content: &str) -> String {
+        let filename = format!(
+            "{}/{}-{}.{}",
+            ASSETS_DIR,
+            kind,
+            content_hash(content.as_bytes()),
+            extension
+        );
+        self.assets
+            .entry(filename.clone())
+            .or_insert_with(|| content.to_string());
+        filename
+    }
+
+    /// Rewrites a page so it has no inline styles or scripts, replacing them
+    /// with references to hashed asset files and adding a CSP meta tag.
+    /// JSON data blocks (`<script type="application/json">`) are not executed
+    /// and stay inline. `root_path` leads from the page up to the output
+    /// directory, e.g. `../` for a page in a subdirectory.
+    pub fn externalize(&mut self, html: &str, root_path: &str) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+
+        loop {
+            let style = rest.find("<style>");
+            let script = rest.find("<script>");
+            let (start, open, close, kind) = match (style, script) {
+                (Some(s), Some(j)) if s < j => (s, "<style>", "</style>", "style"),
+                (Some(s), None) => (s, "<style>", "</style>", "style"),
+                (_, Some(j)) => (j, "<script>", "</script>", "script"),
+                (None, None) => break,
+            };
+
+            let body_start = start + open.len();
+            let Some(body_len) = rest[body_start..].find(close) else {
+                break;
+            };
+            let body = &rest[body_start..body_start + body_len];
+
+            out.push_str(&rest[..start]);
+            if kind == "style" {
+                let href = self.add("style", "css", &dedent(body));
+                out.push_str(&format!(
+                    "<link rel=\"stylesheet\" href=\"{}{}\">",
+                    root_path, href
+                ));
+            } else {
+                let src = self.add("script", "js", &dedent(body));
+                out.push_str(&format!("<script src=\"{}{}\"></script>", root_path, src));
+            }
+            rest = &rest[body_start + body_len + close.len()..];
+        }
+        out.push_str(rest);
+
+        out.replacen(
+            "<meta charset=\"UTF-8\">\n",
+            &format!(
+                "<meta charset=\"UTF-8\">\n    <meta http-equiv=\"Content-Security-Policy\" content=\"{}\">\n",
+                POLICY
+            ),
+            1,
+        )
+    }
+
+    pub fn asset_count(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Writes the collected assets plus `content-security-policy.txt`, a
+    /// snippet for configuring the same policy as a real response header.
+    pub fn write_to(&self, output_dir: &Path) -> io::Result<()> {
+        let path =
+            |filename: &str| output::output_file(output_dir, filename).map_err(io::Error::other);
+        fs::create_dir_all(path(ASSETS_DIR)?)?;
+        for (filename, content) in &self.assets {
+            fs::write(path(filename)?, content)?;
+        }
+        fs::write(
+            path("content-security-policy.txt")?,
+            format!("Content-Security-Policy: {}\n", POLICY),
+        )
+    }
+}
+
+/// Strips the indentation the blocks had inside the page
+fn dedent(block: &str) -> String {
+    let indent = block
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    for line in block.lines().filter(|line| !line.trim().is_empty()) {
+        out.push_str(line.get(indent..).unwrap_or(line.trim_start()));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,180 @@
+use crate::Playlist;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteStyle {
+    /// Quote only fields containing the delimiter, quotes or line breaks
+    Necessary,
+    /// Quote every field
+    Always,
+    /// Never quote, fields are written verbatim
+    Never,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub quote_style: QuoteStyle,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            quote_style: QuoteStyle::Necessary,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// File extension matching the delimiter, so tab separated output ends up as `.tsv`
+    pub fn extension(&self) -> &'static str {
+        if self.delimiter == '\t' { "tsv" } else { "csv" }
+    }
+}
+
+pub fn parse_delimiter(value: &str) -> Result<char, String> {
+    match value.to_lowercase().as_str() {
+        "tab" | "\\t" | "tsv" => return Ok('\t'),
+        "comma" => return Ok(','),
+        "semicolon" => return Ok(';'),
+        "pipe" => return Ok('|'),
+        _ => {}
+    }
+
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '"' && c != '\n' && c != '\r' => Ok(c),
+        _ => Err(format!(
+            "delimiter must be a single character or one of 'tab', 'comma', 'semicolon', 'pipe' (got '{}')",
+            value
+        )),
+    }
+}
+
+pub fn parse_quote_style(value: &str) -> Result<QuoteStyle, String> {
+    match value.to_lowercase().as_str() {
+        "necessary" => Ok(QuoteStyle::Necessary),
+        "always" => Ok(QuoteStyle::Always),
+        "never" => Ok(QuoteStyle::Never),
+        _ => Err(format!(
+            "quote style must be one of 'necessary', 'always' or 'never' (got '{}')",
+            value
+        )),
+    }
+}
+
+fn escape_field(field: &str, options: &CsvOptions) -> String {
+    let quote = match options.quote_style {
+        QuoteStyle::Always => true,
+        QuoteStyle::Never => false,
+        QuoteStyle::Necessary => field
+            .chars()
+            .any(|c| c == options.delimiter || c == '"' || c == '\n' || c == '\r'),
+    };
+
+    if quote {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn push_row(out: &mut String, fields: &[&str], options: &CsvOptions) {
+    let row = fields
+        .iter()
+        .map(|field| escape_field(field, options))
+        .collect::<Vec<_>>()
+        .join(&options.delimiter.to_string());
+    out.push_str(&row);
+    out.push_str("\r\n");
+}
+
+pub fn generate_csv(playlist: &Playlist, options: &CsvOptions) -> String {
+    let mut csv = String::new();
+
+    push_row(
+        &mut csv,
+        &["Track Name", "Artist", "Album", "URI", "Added Date"],
+        options,
+    );
+
+    for item in &playlist.items {
+        push_row(
+            &mut csv,
+            &[
+                &item.title(),
+                &item.creator(),
+                &item.album(),
+                item.uri(),
+                &item.added_date,
+            ],
+            options,
+        );
+    }
+
+    csv
+}
+
+/// Single denormalized table of every track across all playlists, with plain
+/// lowercase column names so it can be queried directly without joins.
+pub fn generate_flat_csv(playlists: &[Playlist], options: &CsvOptions) -> String {
+    let mut csv = String::new();
+
+    push_row(
+        &mut csv,
+        &[
+            "playlist", "position", "track", "artist", "album", "uri", "added",
+        ],
+        options,
+    );
+
+    for playlist in playlists {
+        for (idx, item) in playlist.items.iter().enumerate() {
+            push_row(
+                &mut csv,
+                &[
+                    &playlist.name,
+                    &(idx + 1).to_string(),
+                    &item.title(),
+                    &item.creator(),
+                    &item.album(),
+                    item.uri(),
+                    &item.added_date,
+                ],
+                options,
+            );
+        }
+    }
+
+    csv
+}
+
+pub fn generate_index_csv(
+    playlists: &[Playlist],
+    filenames: &[String],
+    options: &CsvOptions,
+) -> String {
+    let mut csv = String::new();
+
+    push_row(
+        &mut csv,
+        &["Playlist", "File", "Tracks", "Followers", "Last Modified"],
+        options,
+    );
+
+    for (playlist, filename) in playlists.iter().zip(filenames.iter()) {
+        push_row(
+            &mut csv,
+            &[
+                &playlist.name,
+                filename,
+                &playlist.items.len().to_string(),
+                &playlist.number_of_followers.to_string(),
+                &playlist.last_modified_date,
+            ],
+            options,
+        );
+    }
+
+    csv
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date, as the export writes them (`2023-06-01`, optionally
+/// followed by a time)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// Parses the leading `YYYY-MM-DD` of a date or timestamp
+    pub fn parse(text: &str) -> Option<Date> {
+        let text = text.trim();
+        let year = text.get(0..4)?.parse().ok()?;
+        let month = text.get(5..7)?.parse().ok()?;
+        let day = text.get(8..10)?.parse().ok()?;
+        let separators_ok = text.get(4..5) == Some("-") && text.get(7..8) == Some("-");
+        let valid = separators_ok
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month);
+        valid.then_some(Date { year, month, day })
+    }
+
+    /// The current date in UTC
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        Date::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Days since 1970-01-01, after Howard Hinnant's `days_from_civil`
+    pub fn days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The date `days` after 1970-01-01, after Howard Hinnant's `civil_from_days`
+    pub fn from_days(days: i64) -> Date {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Date { year, month, day }
+    }
+
+    /// Whole days from `self` to `later`, negative if `later` is earlier
+    pub fn days_until(self, later: Date) -> i64 {
+        later.days() - self.days()
+    }
+
+    /// The same day `span` earlier; month ends are clamped, so a month before
+    /// March 31 is the last day of February
+    pub fn minus(self, span: Span) -> Date {
+        match span {
+            Span::Days(days) => Date::from_days(self.days() - i64::from(days)),
+            Span::Months(months) => {
+                let index =
+                    i64::from(self.year) * 12 + i64::from(self.month) - 1 - i64::from(months);
+                let year = index.div_euclid(12) as i32;
+                let month = index.rem_euclid(12) as u32 + 1;
+                Date {
+                    year,
+                    month,
+                    day: self.day.min(days_in_month(year, month)),
+                }
+            }
+            Span::Years(years) => self.minus(Span::Months(years * 12)),
+        }
+    }
+}
+
+/// A calendar length of time, as given on the command line (`3y`, `18m`, `90d`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Span {
+    Days(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Span::Days(n) => write!(f, "{} day{}", n, if *n == 1 { "" } else { "s" }),
+            Span::Months(n) => write!(f, "{} month{}", n, if *n == 1 { "" } else { "s" }),
+            Span::Years(n) => write!(f, "{} year{}", n, if *n == 1 { "" } else { "s" }),
+        }
+    }
+}
+
+pub fn parse_span(value: &str) -> Result<Span, String> {
+    let value = value.trim();
+    let error = || {
+        format!(
+            "time span must be a number followed by y, m or d, e.g. 3y (got '{}')",
+            value
+        )
+    };
+    let unit = value.chars().last().ok_or_else(error)?;
+    let count: u32 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| error())?;
+    match unit.to_ascii_lowercase() {
+        'y' => Ok(Span::Years(count)),
+        'm' => Ok(Span::Months(count)),
+        'd' => Ok(Span::Days(count)),
+        _ => Err(error()),
+    }
+}
+
+/// Rough human-readable length of a number of days: "12 days", "7 months",
+/// "3 years"
+pub fn describe_days(days: i64) -> String {
+    let (count, unit) = match days {
+        ..31 => (days.max(0), "day"),
+        31..365 => (days / 30, "month"),
+        _ => (days / 365, "year"),
+    };
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+const MONTH_NAMES: &[&str] = &[
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// English name of a month number (1-12)
+pub fn month_name(month: u32) -> &'static str {
+    MONTH_NAMES
+        .get(month.wrapping_sub(1) as usize)
+        .copied()
+        .unwrap_or("")
+}
+
+/// Parses a `YYYY-MM` month into year and month number
+pub fn parse_month(value: &str) -> Result<(i32, u32), String> {
+    let error = || format!("month must look like 2024-03 (got '{}')", value);
+    let (year, month) = value.trim().split_once('-').ok_or_else(error)?;
+    let year: i32 = year.parse().map_err(|_| error())?;
+    let month: u32 = month.parse().map_err(|_| error())?;
+    if year.to_string().len() != 4 || !(1..=12).contains(&month) {
+        return Err(error());
+    }
+    Ok((year, month))
+}
@@ -0,0 +1,419 @@
+use crate::{
+    Item, Playlist, TrackChanges, bidi_html, escape_html, escape_markdown, get_common_styles,
+    markdown_link_target, track_json,
+};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+
+/// Share of tracks two playlists must have in common (Jaccard index) for a
+/// disappeared and a new playlist to be treated as one renamed playlist
+const RENAME_SIMILARITY: f64 = 0.9;
+
+/// The playlists a run with `--annotate-changes` or `--changelog` rendered, in the archive
+/// format, kept in the output directory so the next run can mark what
+/// changed since
+pub const SNAPSHOT_FILENAME: &str = ".annotate-snapshot.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistChange {
+    pub old: usize,
+    pub new: usize,
+    pub added_tracks: Vec<usize>,
+    pub removed_tracks: Vec<usize>,
+}
+
+/// Differences between an earlier and a current export. Indices point into the
+/// old and new playlist slices respectively.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportDiff {
+    pub added: Vec<usize>,
+    pub removed: Vec<usize>,
+    /// Playlists matched across exports, by name or as renames
+    pub matched: Vec<PlaylistChange>,
+}
+
+impl ExportDiff {
+    pub fn renamed<'a>(
+        &'a self,
+        old: &'a [Playlist],
+        new: &'a [Playlist],
+    ) -> impl Iterator<Item = &'a PlaylistChange> {
+        self.matched
+            .iter()
+            .filter(move |change| old[change.old].name != new[change.new].name)
+    }
+
+    pub fn modified(&self) -> impl Iterator<Item = &PlaylistChange> {
+        self.matched
+            .iter()
+            .filter(|change| !change.added_tracks.is_empty() || !change.removed_tracks.is_empty())
+    }
+}
+
+/// Identifies an item across exports: its URI, or title and creator for
+/// items without one (scrubbed exports)
+fn track_key(item: &Item) -> String {
+    if item.uri().is_empty() {
+        format!("{}\u{1f}{}", item.title(), item.creator())
+    } else {
+        item.uri().to_string()
+    }
+}
+
+fn track_keys(playlist: &Playlist) -> HashSet<String> {
+    playlist.items.iter().map(track_key).collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn compare_tracks(
+    old: &Playlist,
+    new: &Playlist,
+    old_idx: usize,
+    new_idx: usize,
+) -> PlaylistChange {
+    let old_keys = track_keys(old);
+    let new_keys = track_keys(new);
+
+    PlaylistChange {
+        old: old_idx,
+        new: new_idx,
+        added_tracks: new
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !old_keys.contains(&track_key(item)))
+            .map(|(idx, _)| idx)
+            .collect(),
+        removed_tracks: old
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !new_keys.contains(&track_key(item)))
+            .map(|(idx, _)| idx)
+            .collect(),
+    }
+}
+
+/// Matches playlists by name first, then pairs up the remaining ones whose
+/// track sets are (nearly) identical as renames.
+pub fn diff_exports(old: &[Playlist], new: &[Playlist]) -> ExportDiff {
+    let mut old_matched = vec![false; old.len()];
+    let mut new_matched = vec![false; new.len()];
+    let mut matched = Vec::new();
+
+    for (new_idx, playlist) in new.iter().enumerate() {
+        if let Some(old_idx) =
+            (0..old.len()).find(|&idx| !old_matched[idx] && old[idx].name == playlist.name)
+        {
+            old_matched[old_idx] = true;
+            new_matched[new_idx] = true;
+            matched.push(compare_tracks(&old[old_idx], playlist, old_idx, new_idx));
+        }
+    }
+
+    for (new_idx, playlist) in new.iter().enumerate() {
+        if new_matched[new_idx] {
+            continue;
+        }
+        let new_keys = track_keys(playlist);
+        let best = (0..old.len())
+            .filter(|&idx| !old_matched[idx])
+            .map(|idx| (idx, similarity(&track_keys(&old[idx]), &new_keys)))
+            .filter(|&(_, score)| score >= RENAME_SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((old_idx, _)) = best {
+            old_matched[old_idx] = true;
+            new_matched[new_idx] = true;
+            matched.push(compare_tracks(&old[old_idx], playlist, old_idx, new_idx));
+        }
+    }
+
+    ExportDiff {
+        added: (0..new.len()).filter(|&idx| !new_matched[idx]).collect(),
+        removed: (0..old.len()).filter(|&idx| !old_matched[idx]).collect(),
+        matched,
+    }
+}
+
+/// The changes to mark on each of the `new` playlists' pages, by index;
+/// playlists that are new themselves have none
+pub fn track_changes(old: &[Playlist], new: &[Playlist]) -> Vec<Option<TrackChanges>> {
+    let mut changes = vec![None; new.len()];
+    for change in diff_exports(old, new).matched {
+        changes[change.new] = Some(TrackChanges {
+            added: change.added_tracks.into_iter().collect(),
+            removed: change
+                .removed_tracks
+                .iter()
+                .map(|&idx| old[change.old].items[idx].clone())
+                .collect(),
+        });
+    }
+    changes
+}
+
+pub fn generate_diff_markdown(
+    diff: &ExportDiff,
+    old: &[Playlist],
+    new: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Changes Since Previous Export\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+
+    let renamed: Vec<&PlaylistChange> = diff.renamed(old, new).collect();
+    let modified: Vec<&PlaylistChange> = diff.modified().collect();
+
+    md.push_str(&format!("- **Added Playlists:** {}\n", diff.added.len()));
+    md.push_str(&format!(
+        "- **Removed Playlists:** {}\n",
+        diff.removed.len()
+    ));
+    md.push_str(&format!("- **Renamed Playlists:** {}\n", renamed.len()));
+    md.push_str(&format!("- **Changed Playlists:** {}\n\n", modified.len()));
+
+    if !renamed.is_empty() {
+        md.push_str("## Renamed\n\n");
+        for change in &renamed {
+            md.push_str(&format!(
+                "- {} → [**{}**]({})\n",
+                escape_markdown(&old[change.old].name),
+                escape_markdown(&new[change.new].name),
+                markdown_link_target(&filenames[change.new])
+            ));
+        }
+        md.push('\n');
+    }
+
+    if !diff.added.is_empty() {
+        md.push_str("## Added\n\n");
+        for &idx in &diff.added {
+            md.push_str(&format!(
+                "- [**{}**]({}) - {} tracks\n",
+                escape_markdown(&new[idx].name),
+                markdown_link_target(&filenames[idx]),
+                new[idx].items.len()
+            ));
+        }
+        md.push('\n');
+    }
+
+    if !diff.removed.is_empty() {
+        md.push_str("## Removed\n\n");
+        for &idx in &diff.removed {
+            md.push_str(&format!(
+                "- ~~{}~~ - {} tracks\n",
+                escape_markdown(&old[idx].name),
+                old[idx].items.len()
+            ));
+        }
+        md.push('\n');
+    }
+
+    if !modified.is_empty() {
+        md.push_str("## Changed\n\n");
+        for change in &modified {
+            let playlist = &new[change.new];
+            md.push_str(&format!(
+                "### [{}]({}) (+{} / -{})\n\n",
+                escape_markdown(&playlist.name),
+                markdown_link_target(&filenames[change.new]),
+                change.added_tracks.len(),
+                change.removed_tracks.len()
+            ));
+            for &idx in &change.added_tracks {
+                let item = &playlist.items[idx];
+                md.push_str(&format!(
+                    "- (+) {} - {}\n",
+                    escape_markdown(&item.title()),
+                    escape_markdown(&item.creator())
+                ));
+            }
+            for &idx in &change.removed_tracks {
+                let item = &old[change.old].items[idx];
+                md.push_str(&format!(
+                    "- (−) ~~{} - {}~~\n",
+                    escape_markdown(&item.title()),
+                    escape_markdown(&item.creator())
+                ));
+            }
+            md.push('\n');
+        }
+    }
+
+    md
+}
+
+pub fn generate_diff_html(
+    diff: &ExportDiff,
+    old: &[Playlist],
+    new: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Changes Since Previous Export</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        .added {\n");
+    html.push_str("            color: #1db954;\n");
+    html.push_str("        }\n");
+    html.push_str("        .removed {\n");
+    html.push_str("            color: #c0392b;\n");
+    html.push_str("            text-decoration: line-through;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Changes Since Previous Export</h1>\n");
+
+    let renamed: Vec<&PlaylistChange> = diff.renamed(old, new).collect();
+    let modified: Vec<&PlaylistChange> = diff.modified().collect();
+
+    html.push_str("        <ul>\n");
+    html.push_str(&format!(
+        "            <li><strong>Added Playlists:</strong> {}</li>\n",
+        diff.added.len()
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Removed Playlists:</strong> {}</li>\n",
+        diff.removed.len()
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Renamed Playlists:</strong> {}</li>\n",
+        renamed.len()
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Changed Playlists:</strong> {}</li>\n",
+        modified.len()
+    ));
+    html.push_str("        </ul>\n");
+
+    if !renamed.is_empty() {
+        html.push_str("        <h2>Renamed</h2>\n");
+        html.push_str("        <ul>\n");
+        for change in &renamed {
+            html.push_str(&format!(
+                "            <li>{} → <a href=\"{}\">{}</a></li>\n",
+                escape_html(&old[change.old].name),
+                escape_html(&filenames[change.new]),
+                escape_html(&new[change.new].name)
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    if !diff.added.is_empty() {
+        html.push_str("        <h2>Added</h2>\n");
+        html.push_str("        <ul>\n");
+        for &idx in &diff.added {
+            html.push_str(&format!(
+                "            <li><a href=\"{}\">{}</a> - {} tracks</li>\n",
+                escape_html(&filenames[idx]),
+                escape_html(&new[idx].name),
+                new[idx].items.len()
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    if !diff.removed.is_empty() {
+        html.push_str("        <h2>Removed</h2>\n");
+        html.push_str("        <ul>\n");
+        for &idx in &diff.removed {
+            html.push_str(&format!(
+                "            <li><span class=\"removed\">{}</span> - {} tracks</li>\n",
+                escape_html(&old[idx].name),
+                old[idx].items.len()
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    if !modified.is_empty() {
+        html.push_str("        <h2>Changed</h2>\n");
+        for change in &modified {
+            let playlist = &new[change.new];
+            html.push_str(&format!(
+                "        <h3><a href=\"{}\">{}</a> (+{} / -{})</h3>\n",
+                escape_html(&filenames[change.new]),
+                bidi_html(&playlist.name),
+                change.added_tracks.len(),
+                change.removed_tracks.len()
+            ));
+            html.push_str("        <ul>\n");
+            for &idx in &change.added_tracks {
+                let item = &playlist.items[idx];
+                html.push_str(&format!(
+                    "            <li class=\"added\">+ {} - {}</li>\n",
+                    bidi_html(&item.title()),
+                    bidi_html(&item.creator())
+                ));
+            }
+            for &idx in &change.removed_tracks {
+                let item = &old[change.old].items[idx];
+                html.push_str(&format!(
+                    "            <li class=\"removed\">{} - {}</li>\n",
+                    bidi_html(&item.title()),
+                    bidi_html(&item.creator())
+                ));
+            }
+            html.push_str("        </ul>\n");
+        }
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_diff_json(
+    diff: &ExportDiff,
+    old: &[Playlist],
+    new: &[Playlist],
+    filenames: &[String],
+) -> Value {
+    json!({
+        "added": diff.added.iter().map(|&idx| json!({
+            "name": new[idx].name,
+            "file": filenames[idx],
+            "tracks": new[idx].items.len(),
+        })).collect::<Vec<_>>(),
+        "removed": diff.removed.iter().map(|&idx| json!({
+            "name": old[idx].name,
+            "tracks": old[idx].items.len(),
+        })).collect::<Vec<_>>(),
+        "renamed": diff.renamed(old, new).map(|change| json!({
+            "old_name": old[change.old].name,
+            "name": new[change.new].name,
+            "file": filenames[change.new],
+        })).collect::<Vec<_>>(),
+        "changed": diff.modified().map(|change| json!({
+            "name": new[change.new].name,
+            "file": filenames[change.new],
+            "added_tracks": change.added_tracks.iter()
+                .map(|&idx| track_json(&new[change.new], idx))
+                .collect::<Vec<_>>(),
+            "removed_tracks": change.removed_tracks.iter()
+                .map(|&idx| track_json(&old[change.old], idx))
+                .collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,141 @@
+use crate::dates::Date;
+use crate::l10n::Localizer;
+use crate::{Playlist, bidi_html, escape_html, escape_markdown, get_common_styles};
+
+/// Tracks added to one playlist during the digest month, by item index
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestSection {
+    pub playlist: usize,
+    pub items: Vec<usize>,
+}
+
+/// Everything added across all playlists in the given month, per playlist
+/// in export order and by added date within each playlist
+pub fn collect_additions(playlists: &[Playlist], year: i32, month: u32) -> Vec<DigestSection> {
+    playlists
+        .iter()
+        .enumerate()
+        .filter_map(|(playlist_idx, playlist)| {
+            let mut items: Vec<(Date, usize)> = playlist
+                .items
+                .iter()
+                .enumerate()
+                .filter_map(|(item_idx, item)| {
+                    Date::parse(&item.added_date)
+                        .filter(|added| added.year == year && added.month == month)
+                        .map(|added| (added, item_idx))
+                })
+                .collect();
+            if items.is_empty() {
+                return None;
+            }
+            items.sort();
+            Some(DigestSection {
+                playlist: playlist_idx,
+                items: items.into_iter().map(|(_, idx)| idx).collect(),
+            })
+        })
+        .collect()
+}
+
+pub fn digest_title(year: i32, month: u32, localizer: &Localizer) -> String {
+    format!("What I Added in {}", localizer.month_year(year, month))
+}
+
+/// Standalone Markdown post; it does not link into a generated archive
+pub fn generate_digest_markdown(
+    sections: &[DigestSection],
+    playlists: &[Playlist],
+    year: i32,
+    month: u32,
+    localizer: &Localizer,
+) -> String {
+    let mut md = String::new();
+    let total: usize = sections.iter().map(|section| section.items.len()).sum();
+
+    md.push_str(&format!("# {}\n\n", digest_title(year, month, localizer)));
+    md.push_str(&format!(
+        "{} tracks added across {} playlists.\n\n",
+        localizer.number(total),
+        localizer.number(sections.len())
+    ));
+
+    for section in sections {
+        let playlist = &playlists[section.playlist];
+        md.push_str(&format!(
+            "## {} ({})\n\n",
+            playlist.name,
+            localizer.number(section.items.len())
+        ));
+        for &idx in &section.items {
+            let item = &playlist.items[idx];
+            md.push_str(&format!(
+                "- **{}** - {} ({})\n",
+                escape_markdown(&item.title()),
+                escape_markdown(&item.creator()),
+                escape_markdown(&item.album())
+            ));
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+pub fn generate_digest_html(
+    sections: &[DigestSection],
+    playlists: &[Playlist],
+    year: i32,
+    month: u32,
+    localizer: &Localizer,
+) -> String {
+    let mut html = String::new();
+    let total: usize = sections.iter().map(|section| section.items.len()).sum();
+    let title = digest_title(year, month, localizer);
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str(&format!("    <title>{}</title>\n", escape_html(&title)));
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        .album {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str(&format!("        <h1>{}</h1>\n", escape_html(&title)));
+    html.push_str(&format!(
+        "        <p>{} tracks added across {} playlists.</p>\n",
+        localizer.number(total),
+        localizer.number(sections.len())
+    ));
+
+    for section in sections {
+        let playlist = &playlists[section.playlist];
+        html.push_str(&format!(
+            "        <h2>{} ({})</h2>\n",
+            bidi_html(&playlist.name),
+            localizer.number(section.items.len())
+        ));
+        html.push_str("        <ul>\n");
+        for &idx in &section.items {
+            let item = &playlist.items[idx];
+            html.push_str(&format!(
+                "            <li><strong>{}</strong> - {} <span class=\"album\">({})</span></li>\n",
+                bidi_html(&item.title()),
+                bidi_html(&item.creator()),
+                bidi_html(&item.album())
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
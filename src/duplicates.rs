@@ -0,0 +1,329 @@
+use crate::{
+    Playlist, bidi_html, escape_html, escape_markdown, get_common_styles, markdown_link_target,
+    track_json,
+};
+use crate::{analysis, similarity};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Titles at least this similar, after normalization, count as the same song
+const TITLE_SIMILARITY: f64 = 0.9;
+
+/// Words in a bracketed or dashed title suffix that mark another release of
+/// the same recording rather than a different song
+const VERSION_WORDS: &[&str] = &[
+    "remaster",
+    "remastered",
+    "live",
+    "mono",
+    "stereo",
+    "version",
+    "edit",
+    "single",
+    "radio",
+    "deluxe",
+    "anniversary",
+    "bonus",
+    "explicit",
+    "feat",
+    "feat.",
+    "ft.",
+    "with",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateKind {
+    /// The same Spotify URI more than once
+    Exact,
+    /// Different URIs, but the same artist and a near-identical title, such as
+    /// a remaster next to the original
+    Probable,
+}
+
+impl DuplicateKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DuplicateKind::Exact => "Exact",
+            DuplicateKind::Probable => "Probable",
+        }
+    }
+}
+
+/// Tracks of one playlist that are copies of each other, by item index
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    pub items: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistDuplicates {
+    pub playlist: usize,
+    pub groups: Vec<DuplicateGroup>,
+}
+
+fn is_version_suffix(text: &str) -> bool {
+    text.split(|c: char| !c.is_alphanumeric() && c != '.')
+        .any(|word| VERSION_WORDS.contains(&word))
+}
+
+/// Lowercased title without version suffixes ("- Remastered 2011",
+/// "(Live)", "(feat. ...)") or punctuation
+pub fn title_key(title: &str) -> String {
+    let lower = title.to_lowercase();
+    let mut title = match lower.split_once(" - ") {
+        Some((head, tail)) if is_version_suffix(tail) => head.to_string(),
+        _ => lower,
+    };
+
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        let mut search = 0;
+        while let Some(offset) = title[search..].find(open) {
+            let start = search + offset;
+            let Some(length) = title[start..].find(close) else {
+                break;
+            };
+            let end = start + length + 1;
+            if is_version_suffix(&title[start + 1..end - 1]) {
+                title.replace_range(start..end, " ");
+                search = start + 1;
+            } else {
+                search = end;
+            }
+        }
+    }
+
+    simplify(&title)
+}
+
+/// Lowercase letters and digits, words separated by single spaces
+pub fn simplify(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn playlist_duplicates(playlist: &Playlist) -> Vec<DuplicateGroup> {
+    let mut groups = Vec::new();
+
+    // Exact: repeated URIs. Only the first of each takes part in fuzzy matching.
+    let mut by_uri: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut candidates = Vec::new();
+    for (idx, item) in playlist.items.iter().enumerate() {
+        let uri = item.uri();
+        if uri.is_empty() {
+            candidates.push(idx);
+            continue;
+        }
+        let occurrences = by_uri.entry(uri).or_default();
+        if occurrences.is_empty() {
+            candidates.push(idx);
+        }
+        occurrences.push(idx);
+    }
+    let mut exact: Vec<Vec<usize>> = by_uri
+        .into_values()
+        .filter(|items| items.len() > 1)
+        .collect();
+    exact.sort();
+    groups.extend(exact.into_iter().map(|items| DuplicateGroup {
+        kind: DuplicateKind::Exact,
+        items,
+    }));
+
+    // Probable: same artist, similar title. Comparing within each artist keeps
+    // this far from quadratic in the playlist length.
+    let mut by_artist: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+    for idx in candidates {
+        let item = &playlist.items[idx];
+        let artist = simplify(&analysis::artist_key(&item.creator()));
+        if artist.is_empty() {
+            continue;
+        }
+        by_artist
+            .entry(artist)
+            .or_default()
+            .push((idx, title_key(&item.title())));
+    }
+    let mut probable = Vec::new();
+    for tracks in by_artist.values() {
+        let mut clusters: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (idx, key) in tracks {
+            match clusters.iter_mut().find(|(head, _)| {
+                !key.is_empty() && similarity::similarity(head, key) >= TITLE_SIMILARITY
+            }) {
+                Some((_, items)) => items.push(*idx),
+                None => clusters.push((key, vec![*idx])),
+            }
+        }
+        probable.extend(
+            clusters
+                .into_iter()
+                .map(|(_, items)| items)
+                .filter(|items| items.len() > 1),
+        );
+    }
+    probable.sort();
+    groups.extend(probable.into_iter().map(|items| DuplicateGroup {
+        kind: DuplicateKind::Probable,
+        items,
+    }));
+
+    groups
+}
+
+/// Duplicate tracks within each playlist; playlists without any are left out
+pub fn find_duplicates(playlists: &[Playlist]) -> Vec<PlaylistDuplicates> {
+    playlists
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, playlist)| {
+            let groups = playlist_duplicates(playlist);
+            (!groups.is_empty()).then_some(PlaylistDuplicates {
+                playlist: idx,
+                groups,
+            })
+        })
+        .collect()
+}
+
+pub fn generate_duplicates_markdown(
+    duplicates: &[PlaylistDuplicates],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Duplicate Tracks\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+
+    if duplicates.is_empty() {
+        md.push_str("No duplicate tracks found.\n");
+        return md;
+    }
+
+    for entry in duplicates {
+        let playlist = &playlists[entry.playlist];
+        md.push_str(&format!(
+            "## [{}]({})\n\n",
+            escape_markdown(&playlist.name),
+            markdown_link_target(&filenames[entry.playlist])
+        ));
+        for group in &entry.groups {
+            let tracks: Vec<String> = group
+                .items
+                .iter()
+                .map(|&idx| {
+                    let item = &playlist.items[idx];
+                    format!(
+                        "#{} {} - {}",
+                        idx + 1,
+                        escape_markdown(&item.title()),
+                        escape_markdown(&item.creator())
+                    )
+                })
+                .collect();
+            md.push_str(&format!(
+                "- **{}:** {}\n",
+                group.kind.label(),
+                tracks.join("; ")
+            ));
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+pub fn generate_duplicates_html(
+    duplicates: &[PlaylistDuplicates],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Duplicate Tracks</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        .duplicate-kind {\n");
+    html.push_str("            font-weight: bold;\n");
+    html.push_str("        }\n");
+    html.push_str("        .probable .duplicate-kind {\n");
+    html.push_str("            color: #b26b00;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Duplicate Tracks</h1>\n");
+
+    if duplicates.is_empty() {
+        html.push_str("        <p>No duplicate tracks found.</p>\n");
+    }
+
+    for entry in duplicates {
+        let playlist = &playlists[entry.playlist];
+        html.push_str(&format!(
+            "        <h2><a href=\"{}\">{}</a></h2>\n",
+            escape_html(&filenames[entry.playlist]),
+            bidi_html(&playlist.name)
+        ));
+        html.push_str("        <ul>\n");
+        for group in &entry.groups {
+            let tracks: Vec<String> = group
+                .items
+                .iter()
+                .map(|&idx| {
+                    let item = &playlist.items[idx];
+                    format!(
+                        "#{} {} - {}",
+                        idx + 1,
+                        bidi_html(&item.title()),
+                        bidi_html(&item.creator())
+                    )
+                })
+                .collect();
+            html.push_str(&format!(
+                "            <li class=\"{}\"><span class=\"duplicate-kind\">{}:</span> {}</li>\n",
+                group.kind.label().to_lowercase(),
+                group.kind.label(),
+                tracks.join("; ")
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_duplicates_json(
+    duplicates: &[PlaylistDuplicates],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> Value {
+    json!({
+        "playlists": duplicates.iter().map(|entry| {
+            let playlist = &playlists[entry.playlist];
+            json!({
+                "name": playlist.name,
+                "file": filenames[entry.playlist],
+                "groups": entry.groups.iter().map(|group| json!({
+                    "kind": group.kind.label().to_lowercase(),
+                    "tracks": group.items.iter()
+                        .map(|&idx| track_json(playlist, idx))
+                        .collect::<Vec<_>>(),
+                })).collect::<Vec<_>>(),
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
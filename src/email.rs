@@ -0,0 +1,319 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use lettre::Transport;
+use lettre::address::{Address, Envelope};
+use lettre::transport::smtp::SmtpTransport;
+use lettre::transport::smtp::authentication::Credentials;
+
+use crate::digest::{self, DigestSection};
+use crate::l10n::Localizer;
+use crate::{Playlist, bidi_html, csp, escape_html};
+
+/// Inline styles, since mail clients drop `<style>` blocks
+const BODY_STYLE: &str = "font-family: Arial, Helvetica, sans-serif; color: #333; max-width: 640px; margin: 0 auto; padding: 20px;";
+const HEADING_STYLE: &str =
+    "color: #1db954; border-bottom: 3px solid #1db954; padding-bottom: 8px;";
+const SUBHEADING_STYLE: &str = "color: #333; margin-top: 28px;";
+const TABLE_STYLE: &str = "width: 100%; border-collapse: collapse;";
+const TH_STYLE: &str = "background-color: #1db954; color: #ffffff; padding: 8px; text-align: left;";
+const TD_STYLE: &str = "padding: 8px; border-bottom: 1px solid #dddddd;";
+const MUTED_STYLE: &str = "color: #666666;";
+
+/// A rendered message with HTML and plain-text alternatives
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// RFC 2047 encoded-word for header values that are not plain ASCII
+fn encode_header(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// Base64 body wrapped at the 76 characters MIME allows per line
+fn encode_body(body: &str) -> String {
+    let encoded = STANDARD.encode(body);
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 38);
+    for chunk in encoded.as_bytes().chunks(76) {
+        wrapped.push_str(std::str::from_utf8(chunk).unwrap_or(""));
+        wrapped.push_str("\r\n");
+    }
+    wrapped
+}
+
+impl Email {
+    /// Serializes the message as a `multipart/alternative` MIME document,
+    /// ready to save as `.eml` or hand to an SMTP server
+    pub fn to_mime(&self, from: Option<&str>, to: &[String]) -> String {
+        let boundary = format!(
+            "=_{}",
+            csp::content_hash(format!("{}{}", self.text, self.html).as_bytes())
+        );
+        let mut mime = String::new();
+
+        if let Some(from) = from {
+            mime.push_str(&format!("From: {}\r\n", from));
+        }
+        if !to.is_empty() {
+            mime.push_str(&format!("To: {}\r\n", to.join(", ")));
+        }
+        mime.push_str(&format!("Subject: {}\r\n", encode_header(&self.subject)));
+        mime.push_str("MIME-Version: 1.0\r\n");
+        mime.push_str(&format!(
+            "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
+            boundary
+        ));
+
+        for (content_type, body) in [("text/plain", &self.text), ("text/html", &self.html)] {
+            mime.push_str(&format!("--{}\r\n", boundary));
+            mime.push_str(&format!(
+                "Content-Type: {}; charset=UTF-8\r\n",
+                content_type
+            ));
+            mime.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+            mime.push_str(&encode_body(body));
+        }
+        mime.push_str(&format!("--{}--\r\n", boundary));
+
+        mime
+    }
+}
+
+/// Port on which SMTP servers expect implicit TLS rather than STARTTLS
+const SMTPS_PORT: u16 = 465;
+
+/// Where to send mail, read from `SMTP_HOST`, `SMTP_PORT` (default 587),
+/// `SMTP_USERNAME` and `SMTP_PASSWORD`
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub credentials: Option<(String, String)>,
+}
+
+impl SmtpSettings {
+    pub fn from_env() -> Result<Self, String> {
+        let host = std::env::var("SMTP_HOST")
+            .ok()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| "sending email needs SMTP_HOST to be set".to_string())?;
+        let port = match std::env::var("SMTP_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| format!("SMTP_PORT must be a port number (got '{}')", port))?,
+            Err(_) => 587,
+        };
+        let credentials = match (
+            std::env::var("SMTP_USERNAME"),
+            std::env::var("SMTP_PASSWORD"),
+        ) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            _ => None,
+        };
+        Ok(Self {
+            host,
+            port,
+            credentials,
+        })
+    }
+}
+
+fn parse_address(address: &str) -> Result<Address, String> {
+    address
+        .parse()
+        .map_err(|_| format!("invalid email address '{}'", address))
+}
+
+/// Sends a MIME message over SMTP, using implicit TLS on port 465 and
+/// STARTTLS on any other port
+pub fn send(settings: &SmtpSettings, from: &str, to: &[String], mime: &str) -> Result<(), String> {
+    let envelope = Envelope::new(
+        Some(parse_address(from)?),
+        to.iter()
+            .map(|address| parse_address(address))
+            .collect::<Result<_, _>>()?,
+    )
+    .map_err(|e| format!("invalid email envelope: {}", e))?;
+
+    let builder = if settings.port == SMTPS_PORT {
+        SmtpTransport::relay(&settings.host)
+    } else {
+        SmtpTransport::starttls_relay(&settings.host)
+    }
+    .map_err(|e| format!("cannot connect to {}: {}", settings.host, e))?
+    .port(settings.port);
+    let transport = match &settings.credentials {
+        Some((username, password)) => builder
+            .credentials(Credentials::new(username.clone(), password.clone()))
+            .build(),
+        None => builder.build(),
+    };
+
+    transport
+        .send_raw(&envelope, mime.as_bytes())
+        .map(|_| ())
+        .map_err(|e| format!("sending email failed: {}", e))
+}
+
+fn html_document(title: &str, content: &str) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(&format!("    <title>{}</title>\n", escape_html(title)));
+    html.push_str("</head>\n");
+    html.push_str(&format!("<body style=\"{}\">\n", BODY_STYLE));
+    html.push_str(content);
+    html.push_str("</body>\n</html>");
+    html
+}
+
+pub fn playlist_email(playlist: &Playlist, localizer: &Localizer) -> Email {
+    let mut content = String::new();
+    let mut text = String::new();
+
+    content.push_str(&format!(
+        "    <h1 dir=\"auto\" style=\"{}\">{}</h1>\n",
+        HEADING_STYLE,
+        escape_html(&playlist.name)
+    ));
+    content.push_str(&format!(
+        "    <p style=\"{}\">{} tracks</p>\n",
+        MUTED_STYLE,
+        localizer.number(playlist.items.len())
+    ));
+    text.push_str(&format!("{}\n", playlist.name));
+    text.push_str(&format!(
+        "{}\n\n",
+        "=".repeat(playlist.name.chars().count())
+    ));
+    text.push_str(&format!(
+        "{} tracks\n\n",
+        localizer.number(playlist.items.len())
+    ));
+
+    if !playlist.items.is_empty() {
+        content.push_str(&format!("    <table style=\"{}\">\n", TABLE_STYLE));
+        content.push_str("        <tr>\n");
+        for heading in ["#", "Track", "Artist", "Album"] {
+            content.push_str(&format!(
+                "            <th style=\"{}\">{}</th>\n",
+                TH_STYLE, heading
+            ));
+        }
+        content.push_str("        </tr>\n");
+        for (idx, item) in playlist.items.iter().enumerate() {
+            content.push_str("        <tr>\n");
+            content.push_str(&format!(
+                "            <td style=\"{} {}\">{}</td>\n",
+                TD_STYLE,
+                MUTED_STYLE,
+                idx + 1
+            ));
+            for field in [item.title(), item.creator(), item.album()] {
+                content.push_str(&format!(
+                    "            <td dir=\"auto\" style=\"{}\">{}</td>\n",
+                    TD_STYLE,
+                    escape_html(&field)
+                ));
+            }
+            content.push_str("        </tr>\n");
+
+            text.push_str(&format!(
+                "{}. {} - {} ({})\n",
+                idx + 1,
+                item.title(),
+                item.creator(),
+                item.album()
+            ));
+        }
+        content.push_str("    </table>\n");
+    }
+
+    Email {
+        subject: playlist.name.clone(),
+        html: html_document(&playlist.name, &content),
+        text,
+    }
+}
+
+pub fn digest_email(
+    sections: &[DigestSection],
+    playlists: &[Playlist],
+    year: i32,
+    month: u32,
+    localizer: &Localizer,
+) -> Email {
+    let title = digest::digest_title(year, month, localizer);
+    let total: usize = sections.iter().map(|section| section.items.len()).sum();
+    let mut content = String::new();
+    let mut text = String::new();
+
+    content.push_str(&format!(
+        "    <h1 style=\"{}\">{}</h1>\n",
+        HEADING_STYLE,
+        escape_html(&title)
+    ));
+    content.push_str(&format!(
+        "    <p>{} tracks added across {} playlists.</p>\n",
+        localizer.number(total),
+        localizer.number(sections.len())
+    ));
+    text.push_str(&format!("{}\n", title));
+    text.push_str(&format!("{}\n\n", "=".repeat(title.chars().count())));
+    text.push_str(&format!(
+        "{} tracks added across {} playlists.\n",
+        localizer.number(total),
+        localizer.number(sections.len())
+    ));
+
+    for section in sections {
+        let playlist = &playlists[section.playlist];
+        let heading = format!(
+            "{} ({})",
+            playlist.name,
+            localizer.number(section.items.len())
+        );
+        content.push_str(&format!(
+            "    <h2 style=\"{}\">{} ({})</h2>\n",
+            SUBHEADING_STYLE,
+            bidi_html(&playlist.name),
+            localizer.number(section.items.len())
+        ));
+        content.push_str("    <ul>\n");
+        text.push_str(&format!(
+            "\n{}\n{}\n\n",
+            heading,
+            "-".repeat(heading.chars().count())
+        ));
+
+        for &idx in &section.items {
+            let item = &playlist.items[idx];
+            content.push_str(&format!(
+                "        <li><strong>{}</strong> - {} <span style=\"{}\">({})</span></li>\n",
+                bidi_html(&item.title()),
+                bidi_html(&item.creator()),
+                MUTED_STYLE,
+                bidi_html(&item.album())
+            ));
+            text.push_str(&format!(
+                "- {} - {} ({})\n",
+                item.title(),
+                item.creator(),
+                item.album()
+            ));
+        }
+        content.push_str("    </ul>\n");
+    }
+
+    Email {
+        subject: title.clone(),
+        html: html_document(&title, &content),
+        text,
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why an input file couldn't be read as an export or archive, and where in
+/// it, when that's known
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParseError {
+    pub message: String,
+    /// Line and column of invalid JSON, counted from 1
+    pub position: Option<(usize, usize)>,
+    /// JSON pointer to the field that broke the parse, e.g.
+    /// `/playlists/3/items/0/addedDate`
+    pub pointer: Option<String>,
+    /// Name of the playlist the field is in
+    pub playlist: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Invalid JSON, with the position serde_json found it at
+    pub fn from_json(error: &serde_json::Error) -> Self {
+        let text = error.to_string();
+        let location = format!(" at line {} column {}", error.line(), error.column());
+        let message = text.strip_suffix(&location).unwrap_or(&text);
+        Self {
+            message: format!("invalid JSON: {}", message),
+            position: (error.line() > 0).then(|| (error.line(), error.column())),
+            ..Self::default()
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some((line, column)) = self.position {
+            write!(f, " at line {}, column {}", line, column)?;
+        }
+        if let Some(pointer) = &self.pointer {
+            write!(f, " at {}", pointer)?;
+        }
+        if let Some(playlist) = &self.playlist {
+            write!(f, ", in playlist \"{}\"", playlist)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// What stops a run, each kind with its own exit status (see
+/// [`ConverterError::exit_code`]) so scripts can tell them apart
+#[derive(Debug, thiserror::Error)]
+pub enum ConverterError {
+    /// Options that are invalid or don't go together
+    #[error("{0}")]
+    Usage(String),
+    /// A file that couldn't be read, or another I/O failure outside the
+    /// output directory
+    #[error("{}", describe_io(.path.as_deref(), .source))]
+    Io {
+        path: Option<PathBuf>,
+        #[source]
+        source: io::Error,
+    },
+    /// An input file that isn't valid JSON, or not an export or archive
+    #[error("{input}: {source}")]
+    Parse {
+        input: String,
+        #[source]
+        source: ParseError,
+    },
+    /// A file or directory of the output that couldn't be written
+    #[error("{}: {message}", .path.display())]
+    Output { path: PathBuf, message: String },
+    /// Anything else that failed: the network, a hook, a check of the result
+    #[error("{0}")]
+    Failed(String),
+}
+
+fn describe_io(path: Option<&Path>, source: &io::Error) -> String {
+    match path {
+        Some(path) => format!("{}: {}", path.display(), source),
+        None => source.to_string(),
+    }
+}
+
+impl ConverterError {
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::Usage(message.into())
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self::Failed(message.into())
+    }
+
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    pub fn parse(input: impl Into<String>, source: ParseError) -> Self {
+        Self::Parse {
+            input: input.into(),
+            source,
+        }
+    }
+
+    pub fn output(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+        Self::Output {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// 1 for a failure of the run itself, 2 for invalid options, as clap
+    /// uses for its own, 3 for I/O, 4 for unreadable input and 5 for output
+    /// that couldn't be written
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Failed(_) => 1,
+            Self::Usage(_) => 2,
+            Self::Io { .. } => 3,
+            Self::Parse { .. } => 4,
+            Self::Output { .. } => 5,
+        }
+    }
+
+    /// What to do about it, when there's more to say than the message
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::Io { source, .. } if source.kind() == io::ErrorKind::NotFound => {
+                Some("check the path; --input also takes a glob or a data export ZIP".to_string())
+            }
+            Self::Parse { source, .. } if source.pointer.is_some() => Some(
+                "fix that field in the export, or leave out --strict to fill in or ignore fields that don't match"
+                    .to_string(),
+            ),
+            Self::Parse { source, .. } if source.position.is_some() => Some(
+                "the file was cut short or edited by hand; export it from Spotify again".to_string(),
+            ),
+            Self::Output { .. } => Some(
+                "check that the output directory is writable and has space, or choose another with --output"
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConverterError {
+    fn from(source: io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
@@ -0,0 +1,329 @@
+use crate::error::ParseError;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// How an export that doesn't match the fields this version knows is read
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ParseMode {
+    /// Missing and mistyped fields get defaults, unknown ones are ignored,
+    /// and each is reported as a [`FieldIssue`]
+    #[default]
+    Lenient,
+    /// The first such field fails the parse, naming its path
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    Missing,
+    Unknown,
+    WrongType { expected: &'static str },
+}
+
+/// A field of the export that doesn't match the expected format, at a path
+/// such as `playlists[2].items[0].addedDate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldIssue {
+    pub path: String,
+    pub problem: Problem,
+}
+
+impl Problem {
+    pub fn describe(&self) -> String {
+        match self {
+            Problem::Missing => "missing field".to_string(),
+            Problem::Unknown => "unknown field".to_string(),
+            Problem::WrongType { expected } => format!("expected {}", expected),
+        }
+    }
+}
+
+impl FieldIssue {
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.path, self.problem.describe())
+    }
+
+    /// The path as a JSON pointer, `/playlists/2/items/0/addedDate`
+    pub fn pointer(&self) -> String {
+        self.path
+            .replace('[', ".")
+            .replace(']', "")
+            .split('.')
+            .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Text,
+    Integer,
+    /// A name, or a user object
+    User,
+    /// Kept as it is, whatever it holds
+    Any,
+    List(&'static Shape),
+    Object(&'static [Field]),
+    Nullable(&'static Shape),
+}
+
+struct Field {
+    name: &'static str,
+    shape: Shape,
+    /// Whether the export always has it; optional fields may be left out
+    required: bool,
+}
+
+const fn required(name: &'static str, shape: Shape) -> Field {
+    Field {
+        name,
+        shape,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, shape: Shape) -> Field {
+    Field {
+        name,
+        shape,
+        required: false,
+    }
+}
+
+const TRACK: &[Field] = &[
+    required("trackName", Shape::Text),
+    required("artistName", Shape::Text),
+    required("albumName", Shape::Text),
+    required("trackUri", Shape::Text),
+];
+
+const EPISODE: &[Field] = &[
+    optional("episodeName", Shape::Text),
+    optional("showName", Shape::Text),
+    optional("episodeUri", Shape::Text),
+];
+
+const AUDIOBOOK: &[Field] = &[
+    optional("audiobookName", Shape::Text),
+    optional("authorName", Shape::Text),
+    optional("audiobookUri", Shape::Text),
+];
+
+const LOCAL_TRACK: &[Field] = &[optional("uri", Shape::Text)];
+
+const ITEM: &[Field] = &[
+    optional("track", Shape::Nullable(&Shape::Object(TRACK))),
+    optional("episode", Shape::Nullable(&Shape::Object(EPISODE))),
+    optional("audiobook", Shape::Nullable(&Shape::Object(AUDIOBOOK))),
+    optional("localTrack", Shape::Nullable(&Shape::Object(LOCAL_TRACK))),
+    required("addedDate", Shape::Text),
+];
+
+const PLAYLIST: &[Field] = &[
+    required("name", Shape::Text),
+    required("lastModifiedDate", Shape::Text),
+    required("collaborators", Shape::List(&Shape::User)),
+    required("items", Shape::List(&Shape::Object(ITEM))),
+    optional("description", Shape::Nullable(&Shape::Text)),
+    required("numberOfFollowers", Shape::Integer),
+    optional("uri", Shape::Nullable(&Shape::Text)),
+    optional("owner", Shape::Any),
+    optional("madeFor", Shape::Any),
+];
+
+const EXPORT: &[Field] = &[required("playlists", Shape::List(&Shape::Object(PLAYLIST)))];
+
+fn default_value(shape: Shape) -> Value {
+    match shape {
+        Shape::Text | Shape::User => Value::String(String::new()),
+        Shape::Integer => Value::from(0),
+        Shape::Any | Shape::Nullable(_) => Value::Null,
+        Shape::List(_) => Value::Array(Vec::new()),
+        Shape::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .filter(|field| field.required)
+                .map(|field| (field.name.to_string(), default_value(field.shape)))
+                .collect(),
+        ),
+    }
+}
+
+fn expected(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Text => "a string",
+        Shape::Integer => "an integer",
+        Shape::User => "a name or a user object",
+        Shape::Any => "any value",
+        Shape::List(_) => "a list",
+        Shape::Object(_) => "an object",
+        Shape::Nullable(inner) => expected(*inner),
+    }
+}
+
+/// The value a mistyped one is read as: numbers keep their digits as text,
+/// numeric text becomes a number, anything else the default
+fn coerce(value: &Value, shape: Shape) -> Value {
+    match (shape, value) {
+        (Shape::Text | Shape::User, Value::Number(number)) => Value::String(number.to_string()),
+        (Shape::Integer, Value::String(text)) => text
+            .trim()
+            .parse::<i64>()
+            .map_or_else(|_| default_value(shape), Value::from),
+        (Shape::Integer, Value::Number(number)) => number
+            .as_f64()
+            .map_or_else(|| default_value(shape), |n| Value::from(n as i64)),
+        _ => default_value(shape),
+    }
+}
+
+struct Checker {
+    mode: ParseMode,
+    issues: Vec<FieldIssue>,
+}
+
+impl Checker {
+    fn report(&mut self, path: &str, problem: Problem) -> Result<(), FieldIssue> {
+        let issue = FieldIssue {
+            path: path.to_string(),
+            problem,
+        };
+        if self.mode == ParseMode::Strict {
+            return Err(issue);
+        }
+        self.issues.push(issue);
+        Ok(())
+    }
+
+    fn check(&mut self, value: &mut Value, shape: Shape, path: &str) -> Result<(), FieldIssue> {
+        let matches = match (shape, &*value) {
+            (Shape::Any, _) | (Shape::Nullable(_), Value::Null) => return Ok(()),
+            (Shape::Nullable(inner), _) => return self.check(value, *inner, path),
+            (Shape::Text, Value::String(_)) => true,
+            (Shape::Integer, Value::Number(number)) => number.is_i64(),
+            (Shape::User, Value::String(_) | Value::Object(_)) => true,
+            (Shape::List(_), Value::Array(_)) | (Shape::Object(_), Value::Object(_)) => true,
+            _ => false,
+        };
+        if !matches {
+            self.report(
+                path,
+                Problem::WrongType {
+                    expected: expected(shape),
+                },
+            )?;
+            *value = coerce(value, shape);
+        }
+
+        match (shape, value) {
+            (Shape::List(inner), Value::Array(values)) => {
+                for (idx, value) in values.iter_mut().enumerate() {
+                    self.check(value, *inner, &format!("{}[{}]", path, idx))?;
+                }
+            }
+            (Shape::Object(fields), Value::Object(object)) => {
+                self.check_fields(object, fields, path)?
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn check_fields(
+        &mut self,
+        object: &mut Map<String, Value>,
+        fields: &[Field],
+        path: &str,
+    ) -> Result<(), FieldIssue> {
+        let join = |name: &str| {
+            if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", path, name)
+            }
+        };
+        for key in object.keys() {
+            if !fields.iter().any(|field| field.name == key) {
+                self.report(&join(key), Problem::Unknown)?;
+            }
+        }
+        for field in fields {
+            match object.get_mut(field.name) {
+                Some(value) => self.check(value, field.shape, &join(field.name))?,
+                None if field.required => {
+                    self.report(&join(field.name), Problem::Missing)?;
+                    object.insert(field.name.to_string(), default_value(field.shape));
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Holds a Spotify export to the expected format: in lenient mode it is
+/// repaired in place, with every difference returned; in strict mode the
+/// first difference is the error, pointing at the field and its playlist
+pub fn check_export(value: &mut Value, mode: ParseMode) -> Result<Vec<FieldIssue>, ParseError> {
+    let Value::Object(object) = value else {
+        return Err(ParseError::new(
+            "not a Spotify playlist export: expected a JSON object with a playlists list",
+        ));
+    };
+    let mut checker = Checker {
+        mode,
+        issues: Vec::new(),
+    };
+    match checker.check_fields(object, EXPORT, "") {
+        Ok(()) => Ok(checker.issues),
+        Err(issue) => {
+            let pointer = issue.pointer();
+            let playlist = pointer
+                .strip_prefix("/playlists/")
+                .and_then(|rest| rest.split('/').next())
+                .and_then(|idx| value.pointer(&format!("/playlists/{}/name", idx)))
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            Err(ParseError {
+                message: format!(
+                    "not a Spotify playlist export: {}",
+                    issue.problem.describe()
+                ),
+                pointer: Some(pointer),
+                playlist,
+                ..ParseError::default()
+            })
+        }
+    }
+}
+
+/// One line per kind of issue and field, list indices left out, with how
+/// often it occurred and what was done about it
+pub fn summarize(issues: &[FieldIssue]) -> Vec<String> {
+    let mut groups: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for issue in issues {
+        let mut field = String::new();
+        let mut in_index = false;
+        for c in issue.path.chars() {
+            match c {
+                '[' => {
+                    in_index = true;
+                    field.push_str("[]");
+                }
+                ']' => in_index = false,
+                _ if in_index => {}
+                _ => field.push(c),
+            }
+        }
+        let action = match &issue.problem {
+            Problem::Missing => "missing, filled in with a default".to_string(),
+            Problem::Unknown => "unknown, ignored".to_string(),
+            Problem::WrongType { expected } => format!("not {}, converted", expected),
+        };
+        *groups.entry((field, action)).or_default() += 1;
+    }
+    groups
+        .into_iter()
+        .map(|((field, action), count)| format!("{} {} ({}×)", field, action, count))
+        .collect()
+}
@@ -0,0 +1,89 @@
+use crate::error::{ConverterError, ParseError};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+/// Every ZIP file starts with a local file header signature
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Number of a `PlaylistN.json` file of a Spotify data export, in whichever
+/// folder of the archive it is
+fn playlist_file_number(name: &str) -> Option<u32> {
+    let filename = name.rsplit(['/', '\\']).next()?;
+    let number = filename.strip_prefix("Playlist")?.strip_suffix(".json")?;
+    if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+fn zip_error(error: zip::result::ZipError) -> String {
+    format!("invalid ZIP file: {}", error)
+}
+
+/// The playlists of every `PlaylistN.json` in a Spotify data export ZIP,
+/// merged into one export in file number order. They are kept as JSON, for
+/// the export to be checked against the expected format as a whole.
+pub fn read_export_zip(bytes: &[u8]) -> Result<Value, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+
+    let mut files = Vec::new();
+    for idx in 0..archive.len() {
+        let file = archive.by_index(idx).map_err(zip_error)?;
+        let name = file.name().map_err(zip_error)?;
+        if let Some(number) = playlist_file_number(&name) {
+            files.push((number, idx));
+        }
+    }
+    if files.is_empty() {
+        return Err(
+            "no Playlist1.json in the ZIP file; is it a Spotify account data export?".to_string(),
+        );
+    }
+    files.sort();
+
+    let mut playlists = Vec::new();
+    for (_, idx) in files {
+        let mut file = archive.by_index(idx).map_err(zip_error)?;
+        let name = file.name().map_err(zip_error)?.into_owned();
+        let mut text = String::new();
+        file.read_to_string(&mut text)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        let mut part: Value =
+            serde_json::from_str(&text).map_err(|e| format!("{}: invalid JSON: {}", name, e))?;
+        match part.get_mut("playlists") {
+            Some(Value::Array(part)) => playlists.append(part),
+            _ => {
+                return Err(format!(
+                    "{}: not a Spotify playlist export: no playlists list",
+                    name
+                ));
+            }
+        }
+    }
+    Ok(serde_json::json!({ "playlists": playlists }))
+}
+
+/// The JSON text of an input file, `-` for stdin, which may also be a
+/// Spotify data export ZIP, recognized by its contents, whose playlist files
+/// are merged into one export
+pub fn read_input(path: &Path) -> Result<String, ConverterError> {
+    let bytes = if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| ConverterError::io(path, e))?;
+        bytes
+    } else {
+        fs::read(path).map_err(|e| ConverterError::io(path, e))?
+    };
+    let input = path.display().to_string();
+    if bytes.starts_with(ZIP_MAGIC) {
+        return read_export_zip(&bytes)
+            .map(|export| export.to_string())
+            .map_err(|e| ConverterError::parse(input, ParseError::new(e)));
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| ConverterError::parse(input, ParseError::new("not a UTF-8 text file")))
+}
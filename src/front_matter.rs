@@ -0,0 +1,76 @@
+use crate::dates::Date;
+use crate::{Playlist, tags};
+
+/// Metadata block prepended to Markdown files for static-site generators and
+/// note-taking tools
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FrontMatter {
+    #[default]
+    None,
+    /// `---` delimited YAML (Jekyll, Hugo, Obsidian, ...)
+    Yaml,
+    /// `+++` delimited TOML (Hugo, Zola)
+    Toml,
+}
+
+pub fn parse_front_matter(value: &str) -> Result<FrontMatter, String> {
+    match value.to_lowercase().as_str() {
+        "none" => Ok(FrontMatter::None),
+        "yaml" => Ok(FrontMatter::Yaml),
+        "toml" => Ok(FrontMatter::Toml),
+        _ => Err(format!(
+            "front matter must be one of 'yaml', 'toml' or 'none' (got '{}')",
+            value
+        )),
+    }
+}
+
+/// Double-quoted string, valid in both YAML and TOML
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Front matter for a playlist page: title, date (last modified), tags and
+/// track_count. Empty for [`FrontMatter::None`].
+pub fn playlist_front_matter(format: FrontMatter, playlist: &Playlist) -> String {
+    let (delimiter, separator) = match format {
+        FrontMatter::None => return String::new(),
+        FrontMatter::Yaml => ("---", ":"),
+        FrontMatter::Toml => ("+++", " ="),
+    };
+
+    let mut fields = vec![("title", quote(&playlist.name))];
+    // Both formats take a bare date; anything else stays a string
+    match Date::parse(&playlist.last_modified_date) {
+        Some(date) => fields.push(("date", date.to_string())),
+        None if !playlist.last_modified_date.is_empty() => {
+            fields.push(("date", quote(&playlist.last_modified_date)))
+        }
+        None => {}
+    }
+    let tags: Vec<String> = tags::extract_tags(playlist)
+        .iter()
+        .map(|tag| quote(tag))
+        .collect();
+    fields.push(("tags", format!("[{}]", tags.join(", "))));
+    fields.push(("track_count", playlist.items.len().to_string()));
+
+    let mut block = format!("{}\n", delimiter);
+    for (key, value) in fields {
+        block.push_str(&format!("{}{} {}\n", key, separator, value));
+    }
+    block.push_str(&format!("{}\n\n", delimiter));
+    block
+}
@@ -0,0 +1,124 @@
+use crate::{Playlist, changelog, diff, journal, metrics};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Files of the output directory that only carry state from one run to the
+/// next, kept out of the repository with a `.gitignore`
+const STATE_FILES: &[&str] = &[
+    metrics::RUN_METRICS_FILENAME,
+    journal::JOURNAL_FILENAME,
+    diff::SNAPSHOT_FILENAME,
+    changelog::HISTORY_FILENAME,
+];
+
+/// Runs git in `dir` and returns what it prints, or what it printed to
+/// stderr when it fails
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The commit message for a run that converted `new`: a subject with the
+/// number of tracks added and removed since `old`, the playlists the run
+/// converted when there is nothing to compare with, and a line per changed
+/// playlist in the body
+pub fn commit_message(old: Option<&[Playlist]>, new: &[Playlist]) -> String {
+    let total: usize = new.iter().map(|p| p.items.len()).sum();
+    let Some(old) = old else {
+        return format!(
+            "Update playlists: {} playlists, {} tracks",
+            new.len(),
+            total
+        );
+    };
+
+    let changes = diff::diff_exports(old, new);
+    let added: usize = changes
+        .modified()
+        .map(|c| c.added_tracks.len())
+        .sum::<usize>()
+        + changes
+            .added
+            .iter()
+            .map(|&idx| new[idx].items.len())
+            .sum::<usize>();
+    let removed: usize = changes
+        .modified()
+        .map(|c| c.removed_tracks.len())
+        .sum::<usize>()
+        + changes
+            .removed
+            .iter()
+            .map(|&idx| old[idx].items.len())
+            .sum::<usize>();
+
+    let mut message = format!(
+        "Update playlists: {} tracks added, {} removed\n\n{} playlists, {} tracks\n",
+        added,
+        removed,
+        new.len(),
+        total
+    );
+    for &idx in &changes.added {
+        message.push_str(&format!(
+            "\nAdded {} ({} tracks)",
+            new[idx].name,
+            new[idx].items.len()
+        ));
+    }
+    for &idx in &changes.removed {
+        message.push_str(&format!("\nRemoved {}", old[idx].name));
+    }
+    for change in changes.renamed(old, new) {
+        message.push_str(&format!(
+            "\nRenamed {} to {}",
+            old[change.old].name, new[change.new].name
+        ));
+    }
+    for change in changes.modified() {
+        message.push_str(&format!(
+            "\n{}: +{} -{}",
+            new[change.new].name,
+            change.added_tracks.len(),
+            change.removed_tracks.len()
+        ));
+    }
+    message
+}
+
+/// Commits everything in `dir` with `message`, first making it a repository
+/// if it isn't one, and pushes the commit if asked to. Returns the short
+/// hash of the commit, or `None` when nothing changed.
+pub fn commit(dir: &Path, message: &str, push: bool) -> Result<Option<String>, String> {
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "--quiet"])?;
+    }
+    let ignore = dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(&ignore, STATE_FILES.join("\n") + "\n")
+            .map_err(|e| format!("could not write {}: {}", ignore.display(), e))?;
+    }
+
+    git(dir, &["add", "--all"])?;
+    if git(dir, &["status", "--porcelain"])?.is_empty() {
+        return Ok(None);
+    }
+    git(dir, &["commit", "--quiet", "--message", message])?;
+    if push {
+        git(dir, &["push", "--quiet"])?;
+    }
+    git(dir, &["rev-parse", "--short", "HEAD"]).map(Some)
+}
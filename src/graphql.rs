@@ -0,0 +1,171 @@
+use crate::server::Archive;
+use crate::{Item, Playlist, uri};
+use async_graphql::{EmptyMutation, EmptySubscription, Object, SimpleObject};
+use std::sync::Arc;
+
+/// Most tracks a single `search` query returns
+const MAX_SEARCH_RESULTS: usize = 500;
+
+pub type ArchiveSchema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// A read-only schema over the archive's playlists and tracks
+pub fn schema(archive: Arc<Archive>) -> ArchiveSchema {
+    async_graphql::Schema::build(Query { archive }, EmptyMutation, EmptySubscription).finish()
+}
+
+pub struct Query {
+    archive: Arc<Archive>,
+}
+
+#[Object]
+impl Query {
+    /// Every playlist of the export, in export order
+    async fn playlists(&self) -> Vec<PlaylistNode<'_>> {
+        self.archive
+            .playlists()
+            .map(|(playlist, slug)| PlaylistNode { playlist, slug })
+            .collect()
+    }
+
+    /// The playlist with this slug, as used in `/api/playlists/{slug}`
+    async fn playlist(&self, slug: String) -> Option<PlaylistNode<'_>> {
+        self.archive
+            .find(&slug)
+            .map(|(playlist, slug)| PlaylistNode { playlist, slug })
+    }
+
+    /// Tracks whose name, artist or album contains every word of `query`,
+    /// ignoring case
+    async fn search(
+        &self,
+        query: String,
+        #[graphql(default = 50)] limit: usize,
+    ) -> SearchResults<'_> {
+        let (hits, truncated) = self.archive.search(&query, limit.min(MAX_SEARCH_RESULTS));
+        SearchResults {
+            tracks: hits
+                .into_iter()
+                .map(|(playlist, slug, idx)| TrackNode {
+                    playlist: PlaylistNode { playlist, slug },
+                    idx,
+                })
+                .collect(),
+            truncated,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SearchResults<'a> {
+    tracks: Vec<TrackNode<'a>>,
+    /// Whether more tracks matched than were returned
+    truncated: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct PlaylistNode<'a> {
+    playlist: &'a Playlist,
+    slug: &'a str,
+}
+
+#[Object(name = "Playlist")]
+impl<'a> PlaylistNode<'a> {
+    async fn slug(&self) -> &str {
+        self.slug
+    }
+
+    async fn name(&self) -> &str {
+        &self.playlist.name
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.playlist.description.as_deref()
+    }
+
+    async fn followers(&self) -> i64 {
+        self.playlist.number_of_followers
+    }
+
+    async fn last_modified(&self) -> &str {
+        &self.playlist.last_modified_date
+    }
+
+    async fn uri(&self) -> Option<&str> {
+        self.playlist.uri.as_deref()
+    }
+
+    async fn web_url(&self) -> Option<String> {
+        self.playlist.uri.as_deref().and_then(uri::web_url)
+    }
+
+    async fn track_count(&self) -> usize {
+        self.playlist.items.len()
+    }
+
+    /// Tracks in playlist order, optionally a window of them
+    async fn tracks(
+        &self,
+        #[graphql(default = 0)] offset: usize,
+        first: Option<usize>,
+    ) -> Vec<TrackNode<'a>> {
+        (0..self.playlist.items.len())
+            .skip(offset)
+            .take(first.unwrap_or(usize::MAX))
+            .map(|idx| TrackNode {
+                playlist: *self,
+                idx,
+            })
+            .collect()
+    }
+}
+
+pub struct TrackNode<'a> {
+    playlist: PlaylistNode<'a>,
+    /// Index of the item in the playlist
+    idx: usize,
+}
+
+impl TrackNode<'_> {
+    fn item(&self) -> &Item {
+        &self.playlist.playlist.items[self.idx]
+    }
+}
+
+#[Object(name = "Track")]
+impl<'a> TrackNode<'a> {
+    /// 1-based position in the playlist
+    async fn position(&self) -> usize {
+        self.idx + 1
+    }
+
+    /// Name of the track, episode, audiobook or local file
+    async fn name(&self) -> String {
+        self.item().title().into_owned()
+    }
+
+    /// Artist, show of an episode or author of an audiobook
+    async fn artist(&self) -> String {
+        self.item().creator().into_owned()
+    }
+
+    async fn album(&self) -> String {
+        self.item().album().into_owned()
+    }
+
+    async fn uri(&self) -> &str {
+        self.item().uri()
+    }
+
+    async fn web_url(&self) -> Option<String> {
+        uri::web_url(self.item().uri())
+    }
+
+    async fn added_date(&self) -> &str {
+        &self.item().added_date
+    }
+
+    /// The playlist this entry belongs to
+    async fn playlist(&self) -> PlaylistNode<'a> {
+        self.playlist
+    }
+}
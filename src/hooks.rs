@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Placeholder in a `--postprocess` command replaced by the generated file
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+/// A command line run through the platform shell, so users can write pipes,
+/// redirections and quoting as they would at a prompt
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Quotes a path as a single shell word
+fn quote_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if cfg!(windows) {
+        format!("\"{}\"", path)
+    } else {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    }
+}
+
+/// Pipes the export JSON through `command` and returns what it prints, which
+/// replaces the export for everything that follows
+pub fn run_pre_filter(command: &str, json: &str) -> Result<String, String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run pre-filter '{}': {}", command, e))?;
+
+    // Written from another thread so a filter that streams its output
+    // cannot deadlock against a full stdin pipe
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(json.as_bytes()));
+        let output = child.wait_with_output();
+        // A filter may stop reading early; only its exit status matters
+        let _ = writer.join();
+        output
+    })
+    .map_err(|e| format!("pre-filter '{}' failed: {}", command, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "pre-filter '{}' exited with {}",
+            command, output.status
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| format!("pre-filter '{}' did not print UTF-8", command))
+}
+
+/// Runs a `--postprocess` command for one generated file, with `{file}`
+/// replaced by its quoted path
+pub fn run_postprocess(command: &str, file: &Path) -> Result<(), String> {
+    let command = command.replace(FILE_PLACEHOLDER, &quote_path(file));
+    let status = shell(&command)
+        .status()
+        .map_err(|e| format!("could not run postprocess '{}': {}", command, e))?;
+    if !status.success() {
+        return Err(format!("postprocess '{}' exited with {}", command, status));
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Journal of a run's API results, kept in the output directory while the run
+/// is in progress
+pub const JOURNAL_FILENAME: &str = ".enrichment-journal.jsonl";
+
+/// Results of API lookups, appended to a file as they come in so that an
+/// interrupted run (Ctrl+C, network drop) can skip everything already looked
+/// up. The first line records the scope, such as the market, the results are
+/// valid for; a journal from a run with a different scope is discarded.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    entries: HashMap<String, Value>,
+}
+
+impl Journal {
+    pub fn open(output_dir: &Path, scope: &str) -> io::Result<Self> {
+        let path = output_dir.join(JOURNAL_FILENAME);
+        let mut entries = HashMap::new();
+
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        let mut lines = existing.lines();
+        let header = json!({ "scope": scope });
+        let resumable = lines
+            .next()
+            .and_then(|line| serde_json::from_str::<Value>(line).ok())
+            == Some(header.clone());
+        if resumable {
+            // A line cut short by the interruption doesn't parse and is skipped
+            for line in lines {
+                if let Ok(Value::Object(mut entry)) = serde_json::from_str(line)
+                    && let (Some(Value::String(key)), Some(value)) =
+                        (entry.remove("key"), entry.remove("value"))
+                {
+                    entries.insert(key, value);
+                }
+            }
+        }
+
+        let mut file = if resumable {
+            OpenOptions::new().append(true).open(&path)?
+        } else {
+            let mut file = File::create(&path)?;
+            writeln!(file, "{}", header)?;
+            file
+        };
+        // An interrupted line must not run into the next entry
+        if resumable && !existing.ends_with('\n') {
+            writeln!(file)?;
+        }
+
+        Ok(Self {
+            path,
+            file,
+            entries,
+        })
+    }
+
+    /// Results carried over from an earlier run
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.entries
+            .get(key)
+            .and_then(|value| T::deserialize(value).ok())
+    }
+
+    pub fn record<T: Serialize>(&mut self, key: &str, value: &T) -> io::Result<()> {
+        let value = serde_json::to_value(value)?;
+        writeln!(self.file, "{}", json!({ "key": key, "value": value }))?;
+        self.entries.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Removes the journal once the run has completed, so the next run looks
+    /// everything up afresh
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.file);
+        fs::remove_file(self.path)
+    }
+}
@@ -0,0 +1,69 @@
+/// Built-in name patterns for playlists Spotify generates or that follow
+/// Spotify's naming, checked after any user-supplied rules
+const DEFAULT_RULES: &[(&str, &str)] = &[
+    ("Wrapped", "Your Top Songs *"),
+    ("Wrapped", "Wrapped *"),
+    ("Blend", "* + *"),
+    ("Blend", "Blend *"),
+    ("This Is", "This Is *"),
+    ("Radio", "* Radio"),
+    ("Made For You", "Daily Mix *"),
+    ("Made For You", "Discover Weekly*"),
+    ("Made For You", "Release Radar*"),
+    ("Made For You", "On Repeat"),
+    ("Made For You", "Repeat Rewind"),
+];
+
+/// Classifies playlists whose name matches `pattern`, where `*` matches any
+/// run of characters and letters match case-insensitively
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindRule {
+    pub label: String,
+    pub pattern: String,
+}
+
+/// Parses a `LABEL=PATTERN` rule
+pub fn parse_kind_rule(value: &str) -> Result<KindRule, String> {
+    match value.split_once('=') {
+        Some((label, pattern)) if !label.trim().is_empty() && !pattern.is_empty() => Ok(KindRule {
+            label: label.trim().to_string(),
+            pattern: pattern.to_string(),
+        }),
+        _ => Err(format!(
+            "playlist kind must look like 'LABEL=PATTERN', e.g. 'Blend=* + *' (got '{}')",
+            value
+        )),
+    }
+}
+
+/// User rules first, so they can override the built-in ones
+pub fn rules_with_defaults(custom: Vec<KindRule>) -> Vec<KindRule> {
+    let mut rules = custom;
+    rules.extend(DEFAULT_RULES.iter().map(|(label, pattern)| KindRule {
+        label: label.to_string(),
+        pattern: pattern.to_string(),
+    }));
+    rules
+}
+
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| wildcard_match(rest, &text[skip..])),
+        Some((c, rest)) => text
+            .split_first()
+            .is_some_and(|(t, text_rest)| t == c && wildcard_match(rest, text_rest)),
+    }
+}
+
+/// Label of the first rule matching the playlist name
+pub fn classify<'a>(name: &str, rules: &'a [KindRule]) -> Option<&'a str> {
+    let name: Vec<char> = name.trim().to_lowercase().chars().collect();
+    rules
+        .iter()
+        .find(|rule| {
+            let pattern: Vec<char> = rule.pattern.to_lowercase().chars().collect();
+            wildcard_match(&pattern, &name)
+        })
+        .map(|rule| rule.label.as_str())
+}
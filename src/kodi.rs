@@ -0,0 +1,57 @@
+use crate::{Playlist, escape_html};
+
+/// Directory, relative to the output directory, holding the smart playlists
+pub const KODI_DIR: &str = "kodi";
+
+/// Distinct non-empty values in first-seen order
+fn distinct<'a>(values: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut distinct: Vec<&str> = Vec::new();
+    for value in values {
+        if !value.is_empty() && !distinct.contains(&value) {
+            distinct.push(value);
+        }
+    }
+    distinct
+}
+
+fn push_rule(xsp: &mut String, field: &str, values: &[&str]) {
+    xsp.push_str(&format!("    <rule field=\"{}\" operator=\"is\">\n", field));
+    for value in values {
+        xsp.push_str(&format!("        <value>{}</value>\n", escape_html(value)));
+    }
+    xsp.push_str("    </rule>\n");
+}
+
+/// A Kodi smart playlist (`.xsp`) of songs whose title is one of the
+/// playlist's titles and whose artist is one of its artists. XSP rules can't
+/// pair an artist with a title, so a song by one of the artists sharing its
+/// title with another track of the playlist also matches. Returns `None` for
+/// playlists without any matchable track.
+pub fn generate_xsp(playlist: &Playlist) -> Option<String> {
+    let tracks = || {
+        playlist
+            .items
+            .iter()
+            .map(|item| &item.track)
+            .filter(|track| !track.track_name.is_empty() && !track.artist_name.is_empty())
+    };
+    let titles = distinct(tracks().map(|track| track.track_name.as_str()));
+    let artists = distinct(tracks().map(|track| track.artist_name.as_str()));
+    if titles.is_empty() {
+        return None;
+    }
+
+    let mut xsp = String::new();
+    xsp.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\" ?>\n");
+    xsp.push_str("<smartplaylist type=\"songs\">\n");
+    xsp.push_str(&format!(
+        "    <name>{}</name>\n",
+        escape_html(&playlist.name)
+    ));
+    xsp.push_str("    <match>all</match>\n");
+    push_rule(&mut xsp, "title", &titles);
+    push_rule(&mut xsp, "artist", &artists);
+    xsp.push_str("</smartplaylist>\n");
+
+    Some(xsp)
+}
@@ -0,0 +1,49 @@
+use icu_calendar::{Date, Gregorian};
+use icu_datetime::FixedCalendarDateTimeFormatter;
+use icu_datetime::fieldsets::YM;
+use icu_decimal::DecimalFormatter;
+use icu_decimal::input::Decimal;
+use icu_locale_core::Locale;
+use std::fmt;
+
+use crate::dates;
+
+/// Locale-aware formatting of counts and months. Without a locale, numbers
+/// are written without grouping and months in English.
+#[derive(Debug, Clone, Default)]
+pub struct Localizer {
+    locale: Option<Locale>,
+}
+
+impl Localizer {
+    pub fn new(locale: Option<Locale>) -> Self {
+        Self { locale }
+    }
+
+    /// A count with the locale's digit grouping: 1,234,567 in English,
+    /// 1 234 567 in French
+    pub fn number<N: Into<Decimal> + fmt::Display>(&self, value: N) -> String {
+        let Some(locale) = &self.locale else {
+            return value.to_string();
+        };
+        match DecimalFormatter::try_new(locale.into(), Default::default()) {
+            Ok(formatter) => formatter.format(&value.into()).to_string(),
+            Err(_) => value.to_string(),
+        }
+    }
+
+    /// A month and year, such as "March 2024" or "mars 2024"
+    pub fn month_year(&self, year: i32, month: u32) -> String {
+        let english = || format!("{} {}", dates::month_name(month), year);
+        let Some(locale) = &self.locale else {
+            return english();
+        };
+        let Ok(date) = Date::try_new_gregorian(year, month as u8, 1) else {
+            return english();
+        };
+        match FixedCalendarDateTimeFormatter::<Gregorian, _>::try_new(locale.into(), YM::long()) {
+            Ok(formatter) => formatter.format(&date).to_string(),
+            Err(_) => english(),
+        }
+    }
+}
@@ -0,0 +1,2970 @@
+//! Conversion of Spotify playlist exports into Markdown, HTML, CSV, M3U and
+//! XSPF files, as used by the `spotify_converter` command.
+//!
+//! Parse an export (or an archive written by `--format archive`) with
+//! [`parse_export`], then render each playlist and the index with one of the
+//! [`PlaylistRenderer`]s:
+//!
+//! ```no_run
+//! use spotify_converter::{HtmlRenderer, PlaylistRenderer, RenderOptions, parse_export};
+//!
+//! let root = parse_export(&std::fs::read_to_string("Playlist1.json").unwrap()).unwrap();
+//! let options = RenderOptions::default();
+//! let renderer = HtmlRenderer;
+//! let filenames = renderer.filenames(&root.playlists);
+//! for (playlist, filename) in root.playlists.iter().zip(&filenames) {
+//!     std::fs::write(filename, renderer.render_playlist(playlist, &options)).unwrap();
+//! }
+//! let index = renderer.render_index(&root.playlists, &filenames, &options);
+//! std::fs::write("index.html", index).unwrap();
+//! ```
+
+pub mod analysis;
+pub mod archive;
+pub mod availability;
+pub mod beets;
+pub mod changelog;
+pub mod collation;
+pub mod config;
+pub mod console;
+pub mod csp;
+pub mod csv;
+pub mod dates;
+pub mod diff;
+pub mod digest;
+pub mod duplicates;
+pub mod email;
+pub mod error;
+pub mod export_check;
+pub mod export_zip;
+pub mod front_matter;
+pub mod git;
+pub mod graphql;
+pub mod hooks;
+pub mod journal;
+pub mod kinds;
+pub mod kodi;
+pub mod l10n;
+pub mod links;
+pub mod lint;
+pub mod m3u;
+pub mod match_keys;
+pub mod metrics;
+pub mod normalize;
+pub mod output;
+pub mod parallel;
+pub mod platform;
+pub mod plugin;
+pub mod previews;
+pub mod schedule;
+pub mod select;
+pub mod server;
+pub mod similarity;
+pub mod slug;
+pub mod source;
+pub mod spotify;
+pub mod stale;
+pub mod summary;
+pub mod tags;
+pub mod template;
+pub mod tenure;
+pub mod uri;
+pub mod validate;
+pub mod xspf;
+
+use error::ParseError;
+use export_check::ParseMode;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// A Spotify playlist export, the `Playlist1.json` of an account data download
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "SpotifyExport")]
+pub struct Root {
+    pub playlists: Vec<Playlist>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub name: String,
+    pub last_modified_date: String,
+    pub collaborators: Vec<Collaborator>,
+    pub items: Vec<Item>,
+    pub description: Option<String>,
+    pub number_of_followers: i64,
+    /// Playlist URI, present in some export variants
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// Owner, present in some export variants: a display name or a user object
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub owner: Value,
+    /// Listener a Spotify-generated playlist was made for, in some export variants
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub made_for: Value,
+}
+
+/// Someone invited to edit a playlist: a username, or a user object in some
+/// export variants
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Collaborator {
+    Username(String),
+    User(serde_json::Map<String, Value>),
+}
+
+impl Collaborator {
+    /// Name to show, `None` when the export leaves it empty
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Collaborator::Username(name) => Some(name.clone()).filter(|name| !name.is_empty()),
+            Collaborator::User(user) => source::user_object_name(user),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    /// Empty for episodes and audiobooks, whose `track` the export leaves null
+    #[serde(
+        default,
+        deserialize_with = "null_as_default",
+        serialize_with = "default_as_null"
+    )]
+    #[schemars(with = "Option<Track>")]
+    pub track: Track,
+    #[serde(default)]
+    pub episode: Option<Episode>,
+    #[serde(default)]
+    pub audiobook: Option<Audiobook>,
+    #[serde(default)]
+    pub local_track: Option<LocalTrack>,
+    pub added_date: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Track {
+    pub track_name: String,
+    pub artist_name: String,
+    pub album_name: String,
+    pub track_uri: String,
+}
+
+/// A podcast episode saved to a playlist
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Episode {
+    pub episode_name: String,
+    pub show_name: String,
+    pub episode_uri: String,
+}
+
+/// An audiobook saved to a playlist
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Audiobook {
+    pub audiobook_name: String,
+    pub author_name: String,
+    pub audiobook_uri: String,
+}
+
+/// A file from the listener's own library rather than Spotify's, which the
+/// export only describes by its `spotify:local:` URI
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LocalTrack {
+    pub uri: String,
+}
+
+impl LocalTrack {
+    pub fn file(&self) -> uri::LocalFile {
+        uri::local_file(&self.uri).unwrap_or_default()
+    }
+}
+
+/// What a playlist item is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    Track,
+    Episode,
+    Audiobook,
+    Local,
+}
+
+impl ItemKind {
+    /// Label telling episodes and audiobooks apart from tracks in a listing
+    pub fn badge(self) -> Option<&'static str> {
+        match self {
+            ItemKind::Track | ItemKind::Local => None,
+            ItemKind::Episode => Some("Episode"),
+            ItemKind::Audiobook => Some("Audiobook"),
+        }
+    }
+}
+
+impl Item {
+    pub fn kind(&self) -> ItemKind {
+        if self.episode.is_some() {
+            ItemKind::Episode
+        } else if self.audiobook.is_some() {
+            ItemKind::Audiobook
+        } else if self.local_track.is_some() {
+            ItemKind::Local
+        } else {
+            ItemKind::Track
+        }
+    }
+
+    /// Name of the track, episode, audiobook or local file
+    pub fn title(&self) -> Cow<'_, str> {
+        match (&self.episode, &self.audiobook, &self.local_track) {
+            (Some(episode), _, _) => Cow::Borrowed(&episode.episode_name),
+            (None, Some(audiobook), _) => Cow::Borrowed(&audiobook.audiobook_name),
+            (None, None, Some(local)) if !local.uri.is_empty() => Cow::Owned(local.file().title),
+            (None, None, _) => Cow::Borrowed(&self.track.track_name),
+        }
+    }
+
+    /// Artist of a track or local file, show of an episode or author of an
+    /// audiobook
+    pub fn creator(&self) -> Cow<'_, str> {
+        match (&self.episode, &self.audiobook, &self.local_track) {
+            (Some(episode), _, _) => Cow::Borrowed(&episode.show_name),
+            (None, Some(audiobook), _) => Cow::Borrowed(&audiobook.author_name),
+            (None, None, Some(local)) if !local.uri.is_empty() => Cow::Owned(local.file().artist),
+            (None, None, _) => Cow::Borrowed(&self.track.artist_name),
+        }
+    }
+
+    /// Album of a track or local file; episodes and audiobooks have none
+    pub fn album(&self) -> Cow<'_, str> {
+        match (&self.episode, &self.audiobook, &self.local_track) {
+            (None, None, Some(local)) if !local.uri.is_empty() => Cow::Owned(local.file().album),
+            (None, None, _) => Cow::Borrowed(&self.track.album_name),
+            _ => Cow::Borrowed(""),
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        match (&self.episode, &self.audiobook, &self.local_track) {
+            (Some(episode), _, _) => &episode.episode_uri,
+            (None, Some(audiobook), _) => &audiobook.audiobook_uri,
+            (None, None, Some(local)) => &local.uri,
+            (None, None, None) => &self.track.track_uri,
+        }
+    }
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + serde::Deserialize<'de>,
+{
+    Ok(<Option<T> as serde::Deserialize>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+fn default_as_null<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Default + PartialEq + serde::Serialize,
+{
+    if *value == T::default() {
+        serializer.serialize_none()
+    } else {
+        serializer.serialize_some(value)
+    }
+}
+
+/// Parses an input file: a Spotify playlist export, or an archive written by
+/// `--format archive`, recognized by its `format` field. Fields an export
+/// lacks are filled with defaults and ones this version doesn't know are
+/// ignored; [`parse_export_with`] reports them, or fails on them.
+pub fn parse_export(text: &str) -> Result<Root, ParseError> {
+    parse_export_with(text, ParseMode::Lenient).map(|(root, _)| root)
+}
+
+/// [`parse_export`], with the fields of the export that didn't match the
+/// expected format, or in [`ParseMode::Strict`] failing on the first
+pub fn parse_export_with(
+    text: &str,
+    mode: ParseMode,
+) -> Result<(Root, Vec<export_check::FieldIssue>), ParseError> {
+    archive::parse_input(text, mode)
+}
+
+/// An output format that renders each playlist to a file of its own, plus an
+/// index linking to them. Formats are looked up by name in [`Renderers`], so
+/// adding one doesn't touch the conversion itself.
+pub trait PlaylistRenderer: Send + Sync {
+    /// Extension of the rendered files, without the dot
+    fn extension(&self) -> &str;
+
+    /// The file of one playlist
+    fn render_playlist(&self, playlist: &Playlist, options: &RenderOptions) -> String;
+
+    /// The index of all playlists, `filenames[i]` being the file
+    /// `playlists[i]` was written to
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        options: &RenderOptions,
+    ) -> String;
+
+    /// A unique filename for each playlist, from its name, as the command
+    /// names them without a filename template
+    fn filenames(&self, playlists: &[Playlist]) -> Vec<String> {
+        let style = FilenameStyle::default();
+        unique_basenames(playlist_basenames(playlists, style), style)
+            .into_iter()
+            .map(|basename| format!("{}.{}", basename, self.extension()))
+            .collect()
+    }
+}
+
+/// Output formats by the name `--format` selects them with
+pub type Renderers = BTreeMap<String, Box<dyn PlaylistRenderer>>;
+
+/// The built-in formats rendered playlist by playlist
+pub fn builtin_renderers(csv_options: csv::CsvOptions) -> Renderers {
+    let mut renderers = Renderers::new();
+    renderers.insert("markdown".to_string(), Box::new(MarkdownRenderer));
+    renderers.insert("html".to_string(), Box::new(HtmlRenderer));
+    renderers.insert(
+        "csv".to_string(),
+        Box::new(CsvRenderer {
+            options: csv_options,
+        }),
+    );
+    renderers.insert("m3u".to_string(), Box::new(M3uRenderer));
+    renderers.insert("xspf".to_string(), Box::new(XspfRenderer));
+    renderers
+}
+
+/// Markdown pages, one per playlist
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownRenderer;
+
+impl PlaylistRenderer for MarkdownRenderer {
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render_playlist(&self, playlist: &Playlist, options: &RenderOptions) -> String {
+        generate_markdown(playlist, options)
+    }
+
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        options: &RenderOptions,
+    ) -> String {
+        generate_index_markdown(playlists, filenames, options)
+    }
+}
+
+/// Standalone HTML pages, one per playlist
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlRenderer;
+
+impl PlaylistRenderer for HtmlRenderer {
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render_playlist(&self, playlist: &Playlist, options: &RenderOptions) -> String {
+        generate_html(playlist, options)
+    }
+
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        options: &RenderOptions,
+    ) -> String {
+        generate_index_html(playlists, filenames, options)
+    }
+}
+
+/// Bare HTML tables for embedding into existing pages
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlFragmentRenderer;
+
+impl PlaylistRenderer for HtmlFragmentRenderer {
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render_playlist(&self, playlist: &Playlist, options: &RenderOptions) -> String {
+        generate_tracks_table(playlist, options)
+    }
+
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        options: &RenderOptions,
+    ) -> String {
+        generate_index_html_fragment(playlists, filenames, options)
+    }
+}
+
+/// CSV (or, with a tab delimiter, TSV) files, one per playlist
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvRenderer {
+    pub options: csv::CsvOptions,
+}
+
+impl PlaylistRenderer for CsvRenderer {
+    fn extension(&self) -> &str {
+        self.options.extension()
+    }
+
+    fn render_playlist(&self, playlist: &Playlist, _options: &RenderOptions) -> String {
+        csv::generate_csv(playlist, &self.options)
+    }
+
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        _options: &RenderOptions,
+    ) -> String {
+        csv::generate_index_csv(playlists, filenames, &self.options)
+    }
+}
+
+/// Extended M3U playlists of Spotify URIs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct M3uRenderer;
+
+impl PlaylistRenderer for M3uRenderer {
+    fn extension(&self) -> &str {
+        m3u::EXTENSION
+    }
+
+    fn render_playlist(&self, playlist: &Playlist, _options: &RenderOptions) -> String {
+        m3u::generate_m3u(playlist)
+    }
+
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        _options: &RenderOptions,
+    ) -> String {
+        m3u::generate_index_m3u(playlists, filenames)
+    }
+}
+
+/// XSPF playlists of Spotify URIs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XspfRenderer;
+
+impl PlaylistRenderer for XspfRenderer {
+    fn extension(&self) -> &str {
+        "xspf"
+    }
+
+    fn render_playlist(&self, playlist: &Playlist, _options: &RenderOptions) -> String {
+        xspf::generate_xspf(playlist)
+    }
+
+    fn render_index(
+        &self,
+        playlists: &[Playlist],
+        filenames: &[String],
+        _options: &RenderOptions,
+    ) -> String {
+        xspf::generate_index_xspf(playlists, filenames)
+    }
+}
+
+/// How per-track anchors (`#track-42`) are derived
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TrackAnchors {
+    /// From the track's position in the playlist: `track-42`
+    #[default]
+    Position,
+    /// From the Spotify track id in the URI: `track-4uLU6hMCjMI75M1A2tKUQC`
+    Uri,
+    /// No per-track anchors
+    None,
+}
+
+/// Which kind of link Spotify items point to
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UriStyle {
+    /// `spotify:` URIs opening the desktop app
+    #[default]
+    Uri,
+    /// `https://open.spotify.com` URLs opening in the browser
+    Web,
+    /// Web URLs, plus a secondary link opening the desktop app
+    Both,
+}
+
+impl UriStyle {
+    /// Primary link target for `uri`, and the secondary app link for `Both`
+    fn links(self, uri: &str) -> (String, Option<String>) {
+        match (self, uri::web_url(uri)) {
+            (UriStyle::Uri, _) | (_, None) => (uri.to_string(), None),
+            (UriStyle::Web, Some(url)) => (url, None),
+            (UriStyle::Both, Some(url)) => (url, Some(uri.to_string())),
+        }
+    }
+}
+
+/// Markdown dialect the Markdown output targets
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MarkdownFlavor {
+    /// GitHub Flavored Markdown: pipe tables, raw HTML anchors
+    #[default]
+    Gfm,
+    /// Pandoc's Markdown: pipe tables, `[]{#id}` anchors, escaping of its
+    /// extensions (math, citations, superscripts)
+    Pandoc,
+    /// Strict CommonMark, which has no tables, so tracks become a list
+    CommonMark,
+}
+
+impl MarkdownFlavor {
+    /// Escapes text so it renders literally. GFM output only escapes what
+    /// breaks tables and links; the stricter flavors escape every character
+    /// that has meaning to them.
+    fn escape(self, text: &str) -> String {
+        let special: &[char] = match self {
+            MarkdownFlavor::Gfm => return escape_markdown(text),
+            MarkdownFlavor::CommonMark => &[
+                '\\', '`', '*', '_', '[', ']', '<', '>', '|', '~', '#', '!', '&',
+            ],
+            MarkdownFlavor::Pandoc => &[
+                '\\', '`', '*', '_', '[', ']', '<', '>', '|', '~', '#', '!', '&', '$', '^', '@',
+            ],
+        };
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if special.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    /// Inline anchor target for `id`
+    fn anchor(self, id: &str) -> String {
+        match self {
+            MarkdownFlavor::Pandoc => format!("[]{{#{}}}", id),
+            _ => format!("<a id=\"{}\"></a>", id),
+        }
+    }
+
+    /// `text` struck through; CommonMark has no syntax for it, so there it's
+    /// raw HTML
+    fn strikethrough(self, text: &str) -> String {
+        match self {
+            MarkdownFlavor::CommonMark => format!("<del>{}</del>", text),
+            _ => format!("~~{}~~", text),
+        }
+    }
+
+    /// Heading with an explicit id, so links to it don't depend on each
+    /// renderer's own heading-to-anchor rules
+    fn heading(self, level: usize, text: &str, id: &str) -> String {
+        let hashes = "#".repeat(level);
+        match self {
+            MarkdownFlavor::Pandoc => format!("{} {} {{#{}}}\n\n", hashes, text, id),
+            _ => format!("{} {}{}\n\n", hashes, self.anchor(id), text),
+        }
+    }
+}
+
+/// Builds the Markdown links of one file, either inline or as numbered
+/// references whose definitions are appended with [`MarkdownLinks::finish`]
+struct MarkdownLinks {
+    reference: bool,
+    targets: Vec<String>,
+}
+
+impl MarkdownLinks {
+    fn new(options: &RenderOptions) -> Self {
+        Self {
+            reference: options.reference_links,
+            targets: Vec::new(),
+        }
+    }
+
+    /// A link to `target`; repeated targets share one reference
+    fn link(&mut self, text: &str, target: &str) -> String {
+        if !self.reference {
+            return format!("[{}]({})", text, target);
+        }
+        let number = match self.targets.iter().position(|known| known == target) {
+            Some(idx) => idx + 1,
+            None => {
+                self.targets.push(target.to_string());
+                self.targets.len()
+            }
+        };
+        format!("[{}][{}]", text, number)
+    }
+
+    /// Appends the reference definitions, if any, to the end of `md`
+    fn finish(self, md: &mut String) {
+        if self.targets.is_empty() {
+            return;
+        }
+        md.push('\n');
+        for (idx, target) in self.targets.iter().enumerate() {
+            md.push_str(&format!("[{}]: {}\n", idx + 1, target));
+        }
+    }
+}
+
+/// A playlist page cut short after `rows` tracks, linking to `full_listing`
+/// for the rest
+#[derive(Debug, Clone)]
+pub struct TrackPreview {
+    pub rows: usize,
+    pub full_listing: String,
+}
+
+/// How a playlist's tracks changed since the previous generation of the
+/// output: `added` holds indices of its items that are new, `removed` the
+/// items that are gone
+#[derive(Debug, Clone, Default)]
+pub struct TrackChanges {
+    pub added: HashSet<usize>,
+    pub removed: Vec<Item>,
+}
+
+/// Presentation options shared by the Markdown and HTML generators
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub track_anchors: TrackAnchors,
+    pub uri_style: UriStyle,
+    /// Keep the track table header visible while scrolling
+    pub sticky_header: bool,
+    /// Only show the back-to-top button after scrolling down
+    pub auto_hide_back_to_top: bool,
+    /// Per-row buttons copying the track's Spotify URL and URI
+    pub copy_buttons: bool,
+    /// Search box and filter chips on the HTML index
+    pub index_filters: bool,
+    /// Number of most frequent artists shown per playlist on the index
+    pub index_top_artists: usize,
+    /// `/`, `j`/`k`, `Enter` and `Esc` navigation in HTML pages
+    pub keyboard_shortcuts: bool,
+    /// Playlists with more tracks than this render their table in chunks of
+    /// this size while scrolling
+    pub lazy_rows: Option<usize>,
+    /// How the index is split into sections
+    pub index_grouping: Option<IndexGrouping>,
+    /// Render tag chips and per-tag index pages from `#hashtags` and `[tags]`
+    pub tags: bool,
+    pub markdown_flavor: MarkdownFlavor,
+    /// Markdown links as `[text][n]` with definitions at the end of the file
+    pub reference_links: bool,
+    /// Metadata block at the top of each playlist Markdown file
+    pub front_matter: front_matter::FrontMatter,
+    /// List playlists on the index alphabetically instead of in export order
+    pub sort_index_by_name: bool,
+    /// How names are ordered when sorting alphabetically
+    pub collation: collation::Collation,
+    /// How counts and months are written
+    pub localizer: l10n::Localizer,
+    /// Set per playlist when its page only previews the track list
+    pub track_preview: Option<TrackPreview>,
+    /// Set per playlist when tracks are marked as added or removed since the
+    /// previous generation
+    pub track_changes: Option<TrackChanges>,
+    /// Downloaded preview clips by track URI, played from each HTML row
+    pub preview_audio: Arc<HashMap<String, String>>,
+    /// Set per playlist when its page is in a subdirectory: the way back up
+    /// to the output directory, e.g. `../`, that links to the index and
+    /// assets start with
+    pub root_path: String,
+    /// Set per playlist when its Markdown is one section of a single document
+    /// with the others: its anchor ids start with this and it doesn't link
+    /// to an index
+    pub section_id: Option<String>,
+}
+
+impl RenderOptions {
+    /// Anchor id `id` of the page, prefixed when the page is a section
+    fn page_id(&self, id: &str) -> String {
+        match (&self.section_id, id) {
+            (Some(section), "top") => section.clone(),
+            (Some(section), _) => format!("{}-{}", section, id),
+            (None, _) => id.to_string(),
+        }
+    }
+
+    /// Number of tracks the page lists, less than all of them for previews
+    fn visible_tracks(&self, playlist: &Playlist) -> usize {
+        match &self.track_preview {
+            Some(preview) => playlist.items.len().min(preview.rows),
+            None => playlist.items.len(),
+        }
+    }
+
+    fn is_new(&self, idx: usize) -> bool {
+        self.track_changes
+            .as_ref()
+            .is_some_and(|changes| changes.added.contains(&idx))
+    }
+
+    /// Tracks removed since the previous generation, if any
+    fn removed_tracks(&self) -> &[Item] {
+        self.track_changes
+            .as_ref()
+            .map_or(&[], |changes| changes.removed.as_slice())
+    }
+
+    /// Turns off every feature that relies on JavaScript so the HTML output is
+    /// fully functional as static markup. Returns the flags that were dropped.
+    pub fn disable_scripts(&mut self) -> Vec<&'static str> {
+        let mut disabled = Vec::new();
+
+        if self.auto_hide_back_to_top {
+            self.auto_hide_back_to_top = false;
+            disabled.push("--auto-hide-back-to-top");
+        }
+        if self.copy_buttons {
+            self.copy_buttons = false;
+            disabled.push("--copy-buttons");
+        }
+        if self.index_filters {
+            self.index_filters = false;
+            disabled.push("--index-filters");
+        }
+        if self.keyboard_shortcuts {
+            self.keyboard_shortcuts = false;
+            disabled.push("--keyboard-shortcuts");
+        }
+        if self.lazy_rows.is_some() {
+            self.lazy_rows = None;
+            disabled.push("--lazy-rows");
+        }
+
+        disabled
+    }
+}
+
+/// How the index is split into sections
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexGrouping {
+    /// By the part of the name before a separator, e.g. `" – "`
+    Prefix(String),
+    /// By the kind name-pattern rules assign, with a kind badge on each card
+    Kind(Vec<kinds::KindRule>),
+    /// Into updated this year, last year and older, most recent first
+    Recency { current_year: i32 },
+}
+
+pub fn parse_index_grouping(value: &str) -> Result<IndexGrouping, String> {
+    match value {
+        "kind" => return Ok(IndexGrouping::Kind(Vec::new())),
+        "recency" => {
+            return Ok(IndexGrouping::Recency {
+                current_year: dates::Date::today().year,
+            });
+        }
+        _ => {}
+    }
+    match value.split_once(':') {
+        Some(("prefix", separator)) if !separator.is_empty() => {
+            Ok(IndexGrouping::Prefix(separator.to_string()))
+        }
+        _ => Err(format!(
+            "index grouping must look like 'prefix:<separator>' or be 'kind' or 'recency' (got '{}')",
+            value
+        )),
+    }
+}
+
+pub fn parse_markdown_flavor(value: &str) -> Result<MarkdownFlavor, String> {
+    match value.to_lowercase().as_str() {
+        "gfm" => Ok(MarkdownFlavor::Gfm),
+        "pandoc" => Ok(MarkdownFlavor::Pandoc),
+        "commonmark" => Ok(MarkdownFlavor::CommonMark),
+        _ => Err(format!(
+            "markdown flavor must be one of 'gfm', 'pandoc' or 'commonmark' (got '{}')",
+            value
+        )),
+    }
+}
+
+pub fn parse_uri_style(value: &str) -> Result<UriStyle, String> {
+    match value.to_lowercase().as_str() {
+        "uri" => Ok(UriStyle::Uri),
+        "web" => Ok(UriStyle::Web),
+        "both" => Ok(UriStyle::Both),
+        _ => Err(format!(
+            "uri style must be one of 'uri', 'web' or 'both' (got '{}')",
+            value
+        )),
+    }
+}
+
+pub fn parse_track_anchors(value: &str) -> Result<TrackAnchors, String> {
+    match value.to_lowercase().as_str() {
+        "position" => Ok(TrackAnchors::Position),
+        "uri" => Ok(TrackAnchors::Uri),
+        "none" => Ok(TrackAnchors::None),
+        _ => Err(format!(
+            "track anchors must be one of 'position', 'uri' or 'none' (got '{}')",
+            value
+        )),
+    }
+}
+
+/// Stable, unique anchor ids for every row of a playlist's track table
+fn track_anchor_ids(playlist: &Playlist, style: TrackAnchors) -> Vec<Option<String>> {
+    let mut ids = slug::UniqueIds::new();
+
+    playlist
+        .items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let uri_id: String = item
+                .uri()
+                .rsplit(':')
+                .next()
+                .unwrap_or("")
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect();
+
+            match style {
+                TrackAnchors::None => None,
+                TrackAnchors::Uri if !uri_id.is_empty() => {
+                    Some(ids.claim(format!("track-{}", uri_id)))
+                }
+                _ => Some(ids.claim(format!("track-{}", idx + 1))),
+            }
+        })
+        .collect()
+}
+
+/// Longest base filename kept as it is. Most file systems allow names of 255
+/// bytes, eCryptfs only 143; this leaves room for a ` (2)` suffix and
+/// extensions such as `.full.html`.
+pub const MAX_BASENAME_BYTES: usize = 120;
+
+/// Cuts a base filename longer than [`MAX_BASENAME_BYTES`] short, ending it
+/// with a hash of the whole name so names that only differ after the cut
+/// still get files of their own
+pub fn shorten_basename(basename: String) -> String {
+    if basename.len() <= MAX_BASENAME_BYTES {
+        return basename;
+    }
+    let hash = &csp::content_hash(basename.as_bytes())[..8];
+    let mut end = MAX_BASENAME_BYTES - hash.len() - 1;
+    while !basename.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}-{}", basename[..end].trim_end_matches([' ', '.']), hash)
+}
+
+/// A name usable as a single file name on any platform: path separators,
+/// characters Windows forbids and control characters become `-`, leading
+/// dots are dropped so the name can be neither `..` nor a hidden file, and
+/// trailing dots and spaces, which Windows strips, are dropped too, so `aux.`
+/// doesn't quietly turn into the device `AUX`. Device names Windows
+/// reserves, such as `CON`, get a `-` appended, and names longer than
+/// [`MAX_BASENAME_BYTES`] are shortened, see [`shorten_basename`].
+pub fn sanitize_filename(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            _ => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .trim_end_matches(['.', ' '])
+        .trim()
+        .to_string();
+    let name = if platform::is_reserved_name(&name) {
+        let stem_end = name.find('.').unwrap_or(name.len());
+        let stem = name[..stem_end].trim_end();
+        format!("{}-{}", stem, &name[stem_end..])
+    } else {
+        name
+    };
+    shorten_basename(name)
+}
+
+/// A relative path such as a filename template's `Mix/html`: each
+/// `/`-separated part made a usable name by [`sanitize_filename`], and parts
+/// left empty, `..` among them, dropped
+pub fn sanitize_path(path: &str) -> String {
+    path.split('/')
+        .map(sanitize_filename)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Base filenames the command writes files of its own under: the index,
+/// reports and other files that sit next to the playlists
+pub const RESERVED_BASENAMES: &[&str] = &[
+    "index",
+    "all_tracks",
+    "archive",
+    "changelog",
+    "changes",
+    "duplicates",
+    "remediation",
+    "run-summary",
+    "stale",
+    "stats",
+    "tenure",
+    "unavailable",
+    "validation",
+];
+
+/// Endings of the extra files written next to a playlist's own, such as
+/// `Mix.full.md`, which a playlist named `Mix.full` would collide with
+const DERIVED_SUFFIXES: &[&str] = &[".full", ".embed"];
+
+/// Numbers repeated basenames, `New Playlist`, `New Playlist (2)` and so
+/// on, or `new-playlist-2` for slugs, so playlists sharing a name don't
+/// overwrite each other's files. Case is ignored as case-insensitive file
+/// systems would. Names of the command's own files, see
+/// [`RESERVED_BASENAMES`], and names ending like a playlist's extra files
+/// are numbered too.
+pub fn unique_basenames(basenames: Vec<String>, style: FilenameStyle) -> Vec<String> {
+    let mut taken: HashSet<String> = RESERVED_BASENAMES
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    basenames
+        .into_iter()
+        .map(|basename| {
+            let mut unique = basename.clone();
+            let mut number = 2;
+            loop {
+                let key = unique.to_lowercase();
+                if !DERIVED_SUFFIXES.iter().any(|suffix| key.ends_with(suffix)) && taken.insert(key)
+                {
+                    break;
+                }
+                unique = if style.slug {
+                    format!("{}-{}", basename, number)
+                } else {
+                    format!("{} ({})", basename, number)
+                };
+                number += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+/// How playlist names are turned into filenames
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FilenameStyle {
+    /// Spell out emoji as shortcodes, so emoji-heavy names don't turn into
+    /// awkward filenames
+    pub emoji_shortcodes: bool,
+    /// Lowercase ASCII slugs, such as `ete-a-tokyo`, for hosting on the web
+    pub slug: bool,
+}
+
+/// Base filename (without extension) for a playlist
+pub fn playlist_basename(name: &str, style: FilenameStyle) -> String {
+    if style.slug {
+        sanitize_filename(&slug::ascii_slug(name, style.emoji_shortcodes))
+    } else if style.emoji_shortcodes {
+        sanitize_filename(&slug::replace_emoji(name))
+    } else {
+        sanitize_filename(name)
+    }
+}
+
+/// Base filenames (without extension) for the playlists. Names with no letter
+/// or digit to name a file by, such as only spaces, dots or emoji, become
+/// `untitled-1`, `untitled-2` and so on.
+pub fn playlist_basenames(playlists: &[Playlist], style: FilenameStyle) -> Vec<String> {
+    let mut untitled = 0;
+    playlists
+        .iter()
+        .map(|playlist| {
+            let name = playlist_basename(&playlist.name, style);
+            if name.chars().any(char::is_alphanumeric) {
+                name
+            } else {
+                untitled += 1;
+                format!("untitled-{}", untitled)
+            }
+        })
+        .collect()
+}
+
+/// Encodes characters that would end a Markdown link destination early
+fn markdown_link_target(target: &str) -> String {
+    target
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escapes a name and isolates its text direction, so an Arabic or Hebrew
+/// name doesn't reorder the numbers and punctuation around it
+fn bidi_html(text: &str) -> String {
+    format!("<bdi>{}</bdi>", escape_html(text))
+}
+
+/// A track as it appears in JSON reports, with its 1-based position
+fn track_json(playlist: &Playlist, item: usize) -> Value {
+    let item_at = &playlist.items[item];
+    serde_json::json!({
+        "position": item + 1,
+        "track_name": item_at.title(),
+        "artist_name": item_at.creator(),
+        "album_name": item_at.album(),
+        "track_uri": item_at.uri(),
+    })
+}
+
+fn push_indented(out: &mut String, text: &str, indent: &str) {
+    for line in text.lines() {
+        out.push_str(indent);
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn get_common_styles() -> &'static str {
+    r#"
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+            max-width: 1200px;
+            margin: 0 auto;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }
+        .container {
+            background-color: white;
+            border-radius: 8px;
+            padding: 30px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }
+        h1 {
+            color: #1db954;
+            margin-bottom: 20px;
+        }
+        a {
+            color: #1db954;
+            text-decoration: none;
+        }
+        a:hover {
+            text-decoration: underline;
+        }
+        .back-to-top {
+            position: fixed;
+            bottom: 20px;
+            right: 20px;
+            background-color: #1db954;
+            color: white;
+            padding: 12px 20px;
+            border-radius: 25px;
+            text-decoration: none;
+            box-shadow: 0 2px 8px rgba(0,0,0,0.2);
+            transition: background-color 0.3s;
+        }
+        .back-to-top:hover {
+            background-color: #1ed760;
+            text-decoration: none;
+        }
+        .nav-link {
+            display: inline-block;
+            margin-bottom: 20px;
+            padding: 8px 16px;
+            background-color: #f0f0f0;
+            border-radius: 4px;
+        }
+        .keyboard-selected {
+            outline: 2px solid #1db954;
+            outline-offset: -2px;
+        }
+    "#
+}
+
+/// Wires up the per-row copy buttons, falling back to a temporary textarea
+/// where the async clipboard API is unavailable (e.g. pages opened via file://).
+const COPY_BUTTONS_SCRIPT: &str = r#"        (function () {
+            function fallbackCopy(text) {
+                var area = document.createElement('textarea');
+                area.value = text;
+                document.body.appendChild(area);
+                area.select();
+                document.execCommand('copy');
+                document.body.removeChild(area);
+                return Promise.resolve();
+            }
+            function copy(text) {
+                if (navigator.clipboard && window.isSecureContext) {
+                    return navigator.clipboard.writeText(text).catch(function () {
+                        return fallbackCopy(text);
+                    });
+                }
+                return fallbackCopy(text);
+            }
+            document.documentElement.classList.add('copy-enabled');
+            document.addEventListener('click', function (event) {
+                var button = event.target.closest('.copy-button');
+                if (!button) return;
+                var label = button.textContent;
+                copy(button.getAttribute('data-copy')).then(function () {
+                    button.textContent = 'Copied!';
+                    setTimeout(function () { button.textContent = label; }, 1200);
+                });
+            });
+        })();
+"#;
+
+/// Keyboard navigation: `/` focuses the search box, `j`/`k` move between track
+/// rows or playlist cards, `Enter` opens the selected one and `Esc` leaves the
+/// search box or goes back to the index.
+const KEYBOARD_SCRIPT: &str = r#"        (function () {
+            var cards = !!document.querySelector('.playlist-card');
+            var current = -1;
+            function items() {
+                var selector = cards ? '.playlist-card:not([hidden])' : 'tbody tr:not([hidden])';
+                return Array.prototype.slice.call(document.querySelectorAll(selector));
+            }
+            function select(index) {
+                var list = items();
+                if (!list.length) return;
+                current = Math.max(0, Math.min(index, list.length - 1));
+                list.forEach(function (item, i) {
+                    item.classList.toggle('keyboard-selected', i === current);
+                });
+                list[current].scrollIntoView({ block: 'nearest' });
+            }
+            function link(item) {
+                return cards ? item.querySelector('h3 a') : item.querySelector('td:nth-child(2) a');
+            }
+            document.addEventListener('keydown', function (event) {
+                if (event.ctrlKey || event.metaKey || event.altKey) return;
+                var typing = /^(INPUT|TEXTAREA|SELECT)$/.test(event.target.tagName);
+                if (event.key === 'Escape') {
+                    if (typing) {
+                        event.target.blur();
+                    } else {
+                        var back = document.querySelector('a.nav-link');
+                        if (back) back.click();
+                    }
+                    return;
+                }
+                if (typing) return;
+                if (event.key === '/') {
+                    var search = document.querySelector('input[type="search"]');
+                    if (search) {
+                        event.preventDefault();
+                        search.focus();
+                    }
+                } else if (event.key === 'j') {
+                    select(current + 1);
+                } else if (event.key === 'k') {
+                    select(current - 1);
+                } else if (event.key === 'Enter' && current >= 0) {
+                    var target = link(items()[current]);
+                    if (target) target.click();
+                }
+            });
+        })();
+"#;
+
+/// Appends the rows inlined by `generate_lazy_tracks_table` one chunk at a
+/// time as the sentinel below the table scrolls into view. Deep links to a row
+/// that hasn't been rendered yet render up to it first.
+const LAZY_ROWS_SCRIPT: &str = r#"        (function () {
+            var data = document.getElementById('lazy-rows');
+            var sentinel = document.querySelector('.lazy-sentinel');
+            var body = document.querySelector('table tbody');
+            if (!data || !sentinel || !body) return;
+            var rows = JSON.parse(data.textContent);
+            var chunk = Number(data.getAttribute('data-chunk')) || 200;
+            var next = 0;
+            var observer = null;
+            function renderChunk() {
+                body.insertAdjacentHTML('beforeend', rows.slice(next, next + chunk).join(''));
+                next += chunk;
+                if (next >= rows.length) {
+                    if (observer) observer.disconnect();
+                    sentinel.remove();
+                }
+            }
+            var target = decodeURIComponent(window.location.hash.slice(1));
+            if (target && !document.getElementById(target)) {
+                while (next < rows.length && !document.getElementById(target)) renderChunk();
+                var row = document.getElementById(target);
+                if (row) row.scrollIntoView();
+            }
+            if (next >= rows.length) return;
+            if ('IntersectionObserver' in window) {
+                observer = new IntersectionObserver(function (entries) {
+                    if (entries[0].isIntersecting) renderChunk();
+                }, { rootMargin: '800px' });
+                observer.observe(sentinel);
+            } else {
+                while (next < rows.length) renderChunk();
+            }
+        })();
+"#;
+
+/// Reveals the back-to-top button once the reader has scrolled past the first
+/// screen. The button stays visible when scripts are disabled.
+const BACK_TO_TOP_SCRIPT: &str = r#"        (function () {
+            var button = document.querySelector('.back-to-top');
+            if (!button) return;
+            button.classList.add('auto-hide');
+            function update() {
+                button.classList.toggle('visible', window.scrollY > window.innerHeight / 2);
+            }
+            window.addEventListener('scroll', update, { passive: true });
+            update();
+        })();
+"#;
+
+pub fn generate_markdown(playlist: &Playlist, options: &RenderOptions) -> String {
+    let mut md = front_matter::playlist_front_matter(options.front_matter, playlist);
+    let mut links = MarkdownLinks::new(options);
+    let flavor = options.markdown_flavor;
+
+    // Header
+    md.push_str(&flavor.heading(1, &flavor.escape(&playlist.name), &options.page_id("top")));
+
+    // Back to index link
+    if options.section_id.is_none() {
+        md.push_str(&format!(
+            "[← Back to Index]({}index.md)\n\n",
+            options.root_path
+        ));
+    }
+
+    if let Some(description) = playlist.description.as_deref().filter(|d| !d.is_empty()) {
+        md.push_str(&format!("{}\n\n", flavor.escape(description)));
+    }
+
+    if let Some(playlist_uri) = &playlist.uri {
+        let (href, app_href) = options.uri_style.links(playlist_uri);
+        md.push_str(&links.link("▶ Open in Spotify", &href));
+        if let Some(app_href) = app_href {
+            md.push_str(&format!(" · {}", links.link("Open in app", &app_href)));
+        }
+        md.push_str("\n\n");
+    }
+
+    // Metadata
+    md.push_str(&flavor.heading(
+        2,
+        "Playlist Information",
+        &options.page_id("playlist-information"),
+    ));
+    md.push_str(&format!(
+        "- **Last Modified:** {}\n",
+        playlist.last_modified_date
+    ));
+    md.push_str(&format!(
+        "- **Followers:** {}\n",
+        options.localizer.number(playlist.number_of_followers)
+    ));
+    if let Some(owner) = source::owner_name(playlist) {
+        md.push_str(&format!("- **Owner:** {}\n", flavor.escape(&owner)));
+    }
+    if let Some(made_for) = source::made_for_name(playlist) {
+        md.push_str(&format!("- **Made For:** {}\n", flavor.escape(&made_for)));
+    }
+    md.push_str(&format!(
+        "- **Total Tracks:** {}\n",
+        options.localizer.number(playlist.items.len())
+    ));
+
+    let stats = analysis::analyze(playlist);
+    if stats.unique_artists > 0 {
+        md.push_str(&format!(
+            "- **Unique Artists:** {}\n",
+            options.localizer.number(stats.unique_artists)
+        ));
+    }
+    if let Some((artist, count)) = stats.most_frequent_artist() {
+        md.push_str(&format!(
+            "- **Most Frequent Artist:** {} ({} tracks)\n",
+            flavor.escape(artist),
+            options.localizer.number(*count)
+        ));
+    }
+    if let (Some(first), Some(last)) = (&stats.first_added, &stats.last_added) {
+        md.push_str(&format!("- **Added Between:** {} – {}\n", first, last));
+    }
+    md.push('\n');
+
+    let collaborators: Vec<String> = playlist
+        .collaborators
+        .iter()
+        .filter_map(Collaborator::name)
+        .collect();
+    if !collaborators.is_empty() {
+        md.push_str(&flavor.heading(2, "Collaborators", &options.page_id("collaborators")));
+        for name in &collaborators {
+            md.push_str(&format!("- {}\n", flavor.escape(name)));
+        }
+        md.push('\n');
+    }
+
+    if !playlist.items.is_empty() {
+        md.push_str(&flavor.heading(2, "Tracks", &options.page_id("tracks")));
+        if flavor != MarkdownFlavor::CommonMark {
+            md.push_str("| # | Track Name | Artist | Album | Added Date |\n");
+            md.push_str("|---|------------|--------|-------|------------|\n");
+        }
+
+        let anchors = track_anchor_ids(playlist, options.track_anchors)
+            .into_iter()
+            .map(|anchor| anchor.map(|id| options.page_id(&id)));
+        let visible = options.visible_tracks(playlist);
+        for (idx, (item, anchor)) in playlist.items.iter().zip(anchors).take(visible).enumerate() {
+            let number = match &anchor {
+                Some(id) => format!("{}[{}](#{})", flavor.anchor(id), idx + 1, id),
+                None => (idx + 1).to_string(),
+            };
+            let mut link = flavor.escape(&item.title());
+            if !item.uri().is_empty() {
+                let (href, app_href) = options.uri_style.links(item.uri());
+                link = links.link(&link, &href);
+                if let Some(app_href) = app_href {
+                    link.push_str(&format!(" {}", links.link("↗", &app_href)));
+                }
+            }
+            if let Some(badge) = item.kind().badge() {
+                link.push_str(&format!(" `{}`", badge));
+            }
+            if options.is_new(idx) {
+                link.push_str(" **new**");
+            }
+            if item.kind() == ItemKind::Local {
+                link.push_str(" (local file)");
+            }
+            if flavor == MarkdownFlavor::CommonMark {
+                // Episodes and audiobooks have no album
+                let album = if matches!(item.kind(), ItemKind::Track | ItemKind::Local) {
+                    format!(" - *{}*", flavor.escape(&item.album()))
+                } else {
+                    String::new()
+                };
+                md.push_str(&format!(
+                    "{}. {}{} - {}{} (added {})\n",
+                    idx + 1,
+                    anchor.map(|id| flavor.anchor(&id)).unwrap_or_default(),
+                    link,
+                    flavor.escape(&item.creator()),
+                    album,
+                    item.added_date
+                ));
+            } else {
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    number,
+                    link,
+                    flavor.escape(&item.creator()),
+                    flavor.escape(&item.album()),
+                    item.added_date
+                ));
+            }
+        }
+
+        if let Some(preview) = options
+            .track_preview
+            .as_ref()
+            .filter(|_| visible < playlist.items.len())
+        {
+            md.push_str(&format!(
+                "\n{}\n",
+                links.link(
+                    &format!(
+                        "View all {} tracks →",
+                        options.localizer.number(playlist.items.len())
+                    ),
+                    &markdown_link_target(&preview.full_listing)
+                )
+            ));
+        }
+    }
+
+    let removed = options.removed_tracks();
+    if !removed.is_empty() {
+        md.push('\n');
+        md.push_str(&flavor.heading(
+            2,
+            "Removed Since Last Run",
+            &options.page_id("removed-tracks"),
+        ));
+        for item in removed {
+            md.push_str(&format!(
+                "- {}\n",
+                flavor.strikethrough(&format!(
+                    "{} - {}",
+                    flavor.escape(&item.title()),
+                    flavor.escape(&item.creator())
+                ))
+            ));
+        }
+    }
+
+    md.push_str(&format!("\n[↑ Back to Top](#{})\n", options.page_id("top")));
+    if options.section_id.is_none() {
+        md.push_str(&format!(
+            "\n[← Back to Index]({}index.md)\n",
+            options.root_path
+        ));
+    }
+    links.finish(&mut md);
+
+    md
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+pub fn generate_html(playlist: &Playlist, options: &RenderOptions) -> String {
+    let mut html = String::new();
+    let lazy_chunk = options
+        .lazy_rows
+        .filter(|&threshold| options.visible_tracks(playlist) > threshold);
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str(&format!(
+        "    <title>{}</title>\n",
+        escape_html(&playlist.name)
+    ));
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        .metadata {\n");
+    html.push_str("            background-color: #f9f9f9;\n");
+    html.push_str("            padding: 15px;\n");
+    html.push_str("            border-radius: 5px;\n");
+    html.push_str("            margin-bottom: 30px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .metadata p {\n");
+    html.push_str("            margin: 5px 0;\n");
+    html.push_str("        }\n");
+    html.push_str("        .description {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("        }\n");
+    html.push_str("        table {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            border-collapse: collapse;\n");
+    html.push_str("        }\n");
+    html.push_str("        th {\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            text-align: start;\n");
+    html.push_str("        }\n");
+    html.push_str("        td {\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            border-bottom: 1px solid #ddd;\n");
+    html.push_str("        }\n");
+    html.push_str("        tr:hover {\n");
+    html.push_str("            background-color: #f5f5f5;\n");
+    html.push_str("        }\n");
+    html.push_str("        .track-number {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("            text-align: center;\n");
+    html.push_str("            width: 50px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .track-number a {\n");
+    html.push_str("            color: inherit;\n");
+    html.push_str("        }\n");
+    if lazy_chunk.is_some() {
+        html.push_str("        .lazy-sentinel {\n");
+        html.push_str("            padding: 20px;\n");
+        html.push_str("            color: #999;\n");
+        html.push_str("            text-align: center;\n");
+        html.push_str("        }\n");
+    }
+    if options.sticky_header {
+        html.push_str("        thead th {\n");
+        html.push_str("            position: sticky;\n");
+        html.push_str("            top: 0;\n");
+        html.push_str("            z-index: 1;\n");
+        html.push_str("        }\n");
+    }
+    html.push_str("        .open-in-spotify {\n");
+    html.push_str("            display: inline-block;\n");
+    html.push_str("            margin: 0 10px 20px 0;\n");
+    html.push_str("            padding: 8px 16px;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            border-radius: 20px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .open-in-spotify.secondary {\n");
+    html.push_str("            color: #1db954;\n");
+    html.push_str("            background-color: #f0f0f0;\n");
+    html.push_str("        }\n");
+    html.push_str("        .app-link {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("            font-size: 12px;\n");
+    html.push_str("        }\n");
+    push_badge_styles(&mut html);
+    if options.copy_buttons {
+        html.push_str("        .copy-buttons {\n");
+        html.push_str("            display: none;\n");
+        html.push_str("        }\n");
+        html.push_str("        .copy-enabled .copy-buttons {\n");
+        html.push_str("            display: inline;\n");
+        html.push_str("        }\n");
+        html.push_str("        .copy-button {\n");
+        html.push_str("            margin-left: 6px;\n");
+        html.push_str("            padding: 2px 6px;\n");
+        html.push_str("            font-size: 11px;\n");
+        html.push_str("            color: #666;\n");
+        html.push_str("            background-color: #f0f0f0;\n");
+        html.push_str("            border: 1px solid #ddd;\n");
+        html.push_str("            border-radius: 4px;\n");
+        html.push_str("            cursor: pointer;\n");
+        html.push_str("        }\n");
+        html.push_str("        .copy-button:hover {\n");
+        html.push_str("            color: #1db954;\n");
+        html.push_str("            border-color: #1db954;\n");
+        html.push_str("        }\n");
+    }
+    if options.auto_hide_back_to_top {
+        html.push_str("        .back-to-top.auto-hide {\n");
+        html.push_str("            opacity: 0;\n");
+        html.push_str("            pointer-events: none;\n");
+        html.push_str("            transition: opacity 0.3s, background-color 0.3s;\n");
+        html.push_str("        }\n");
+        html.push_str("        .back-to-top.auto-hide.visible {\n");
+        html.push_str("            opacity: 1;\n");
+        html.push_str("            pointer-events: auto;\n");
+        html.push_str("        }\n");
+    }
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+
+    // Back to index link
+    html.push_str(&format!(
+        "        <a href=\"{}index.html\" class=\"nav-link\">← Back to Index</a>\n",
+        escape_html(&options.root_path)
+    ));
+
+    // Header
+    html.push_str(&format!(
+        "        <h1 dir=\"auto\">{}</h1>\n",
+        escape_html(&playlist.name)
+    ));
+
+    if let Some(description) = playlist.description.as_deref().filter(|d| !d.is_empty()) {
+        html.push_str(&format!(
+            "        <p class=\"description\" dir=\"auto\">{}</p>\n",
+            escape_html(description)
+        ));
+    }
+
+    if let Some(playlist_uri) = &playlist.uri {
+        let (href, app_href) = options.uri_style.links(playlist_uri);
+        html.push_str(&format!(
+            "        <a href=\"{}\" class=\"open-in-spotify\">▶ Open in Spotify</a>\n",
+            escape_html(&href)
+        ));
+        if let Some(app_href) = app_href {
+            html.push_str(&format!(
+                "        <a href=\"{}\" class=\"open-in-spotify secondary\">Open in app</a>\n",
+                escape_html(&app_href)
+            ));
+        }
+    }
+
+    // Metadata
+    html.push_str("        <div class=\"metadata\">\n");
+    html.push_str(&format!(
+        "            <p><strong>Last Modified:</strong> {}</p>\n",
+        escape_html(&playlist.last_modified_date)
+    ));
+    html.push_str(&format!(
+        "            <p><strong>Followers:</strong> {}</p>\n",
+        options.localizer.number(playlist.number_of_followers)
+    ));
+    if let Some(owner) = source::owner_name(playlist) {
+        html.push_str(&format!(
+            "            <p><strong>Owner:</strong> {}</p>\n",
+            bidi_html(&owner)
+        ));
+    }
+    if let Some(made_for) = source::made_for_name(playlist) {
+        html.push_str(&format!(
+            "            <p><strong>Made For:</strong> {}</p>\n",
+            bidi_html(&made_for)
+        ));
+    }
+    html.push_str(&format!(
+        "            <p><strong>Total Tracks:</strong> {}</p>\n",
+        options.localizer.number(playlist.items.len())
+    ));
+
+    let stats = analysis::analyze(playlist);
+    if stats.unique_artists > 0 {
+        html.push_str(&format!(
+            "            <p><strong>Unique Artists:</strong> {}</p>\n",
+            options.localizer.number(stats.unique_artists)
+        ));
+    }
+    if let Some((artist, count)) = stats.most_frequent_artist() {
+        html.push_str(&format!(
+            "            <p><strong>Most Frequent Artist:</strong> {} ({} tracks)</p>\n",
+            bidi_html(artist),
+            options.localizer.number(*count)
+        ));
+    }
+    if let (Some(first), Some(last)) = (&stats.first_added, &stats.last_added) {
+        html.push_str(&format!(
+            "            <p><strong>Added Between:</strong> {} – {}</p>\n",
+            escape_html(first),
+            escape_html(last)
+        ));
+    }
+    html.push_str("        </div>\n");
+
+    let collaborators: Vec<String> = playlist
+        .collaborators
+        .iter()
+        .filter_map(Collaborator::name)
+        .collect();
+    if !collaborators.is_empty() {
+        html.push_str("        <h2>Collaborators</h2>\n");
+        html.push_str("        <ul class=\"collaborators\">\n");
+        for name in &collaborators {
+            html.push_str(&format!("            <li>{}</li>\n", bidi_html(name)));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    // Tracks table
+    if !playlist.items.is_empty() {
+        html.push_str("        <h2>Tracks</h2>\n");
+        let table = match lazy_chunk {
+            Some(chunk) => generate_lazy_tracks_table(playlist, options, chunk),
+            None => generate_tracks_table(playlist, options),
+        };
+        push_indented(&mut html, &table, "        ");
+
+        if let Some(preview) = options
+            .track_preview
+            .as_ref()
+            .filter(|preview| preview.rows < playlist.items.len())
+        {
+            html.push_str(&format!(
+                "        <p class=\"full-listing\"><a href=\"{}\">View all {} tracks →</a></p>\n",
+                escape_html(&preview.full_listing),
+                options.localizer.number(playlist.items.len())
+            ));
+        }
+    }
+
+    let removed = options.removed_tracks();
+    if !removed.is_empty() {
+        html.push_str("        <h2 id=\"removed-tracks\">Removed Since Last Run</h2>\n");
+        html.push_str("        <ul class=\"removed-tracks\">\n");
+        for item in removed {
+            html.push_str(&format!(
+                "            <li><del><span dir=\"auto\">{}</span> – <span dir=\"auto\">{}</span></del></li>\n",
+                escape_html(&item.title()),
+                escape_html(&item.creator())
+            ));
+        }
+        html.push_str("        </ul>\n");
+    }
+
+    html.push_str("    </div>\n");
+
+    // Floating back to top button
+    html.push_str("    <a href=\"#\" class=\"back-to-top\">↑ Top</a>\n");
+
+    if options.auto_hide_back_to_top {
+        html.push_str("    <script>\n");
+        html.push_str(BACK_TO_TOP_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    if lazy_chunk.is_some() {
+        html.push_str("    <script>\n");
+        html.push_str(LAZY_ROWS_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    if options.copy_buttons {
+        html.push_str("    <script>\n");
+        html.push_str(COPY_BUTTONS_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    if options.keyboard_shortcuts {
+        html.push_str("    <script>\n");
+        html.push_str(KEYBOARD_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+/// Style of the badge marking episodes and audiobooks in a track table, and
+/// of the marker after local files
+fn push_badge_styles(html: &mut String) {
+    html.push_str("        .badge {\n");
+    html.push_str("            font-size: 11px;\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("            background-color: #f0f0f0;\n");
+    html.push_str("            border-radius: 4px;\n");
+    html.push_str("            padding: 1px 6px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .local-file {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("        }\n");
+    html.push_str("        .badge.new {\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("        }\n");
+    html.push_str("        .removed-tracks {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("        }\n");
+}
+
+pub fn generate_tracks_table(playlist: &Playlist, options: &RenderOptions) -> String {
+    tracks_table_with_rows(&generate_track_rows(playlist, options))
+}
+
+/// The `<tr>` for every track of a playlist, without indentation
+fn generate_track_rows(playlist: &Playlist, options: &RenderOptions) -> Vec<String> {
+    let anchors = track_anchor_ids(playlist, options.track_anchors);
+
+    playlist
+        .items
+        .iter()
+        .zip(anchors)
+        .take(options.visible_tracks(playlist))
+        .enumerate()
+        .map(|(idx, (item, anchor))| {
+            let mut row = String::new();
+
+            match anchor {
+                Some(id) => {
+                    row.push_str(&format!("<tr id=\"{}\">\n", id));
+                    row.push_str(&format!(
+                        "    <td class=\"track-number\"><a href=\"#{}\">{}</a></td>\n",
+                        id,
+                        idx + 1
+                    ));
+                }
+                None => {
+                    row.push_str("<tr>\n");
+                    row.push_str(&format!(
+                        "    <td class=\"track-number\">{}</td>\n",
+                        idx + 1
+                    ));
+                }
+            }
+            let mut cell = escape_html(&item.title());
+            if !item.uri().is_empty() {
+                let (href, app_href) = options.uri_style.links(item.uri());
+                cell = format!("<a href=\"{}\">{}</a>", escape_html(&href), cell);
+                if let Some(app_href) = app_href {
+                    cell.push_str(&format!(
+                        " <a href=\"{}\" class=\"app-link\" title=\"Open in the Spotify app\">↗</a>",
+                        escape_html(&app_href)
+                    ));
+                }
+            }
+            if let Some(badge) = item.kind().badge() {
+                cell.push_str(&format!(" <span class=\"badge\">{}</span>", badge));
+            }
+            if options.is_new(idx) {
+                cell.push_str(" <span class=\"badge new\">new</span>");
+            }
+            if item.kind() == ItemKind::Local {
+                cell.push_str(" <span class=\"local-file\">(local file)</span>");
+            }
+            if options.copy_buttons && !item.uri().is_empty() {
+                cell.push_str(&copy_buttons_html(item.uri()));
+            }
+            if let Some(clip) = options.preview_audio.get(item.uri()) {
+                cell.push_str(&format!(
+                    " <audio controls preload=\"none\" src=\"{}\"></audio>",
+                    escape_html(clip)
+                ));
+            }
+            row.push_str(&format!("    <td dir=\"auto\">{}</td>\n", cell));
+            row.push_str(&format!(
+                "    <td dir=\"auto\">{}</td>\n",
+                escape_html(&item.creator())
+            ));
+            row.push_str(&format!(
+                "    <td dir=\"auto\">{}</td>\n",
+                escape_html(&item.album())
+            ));
+            row.push_str(&format!("    <td>{}</td>\n", escape_html(&item.added_date)));
+            row.push_str("</tr>\n");
+
+            row
+        })
+        .collect()
+}
+
+fn tracks_table_with_rows(rows: &[String]) -> String {
+    let mut table = String::new();
+
+    table.push_str("<table>\n");
+    table.push_str("    <thead>\n");
+    table.push_str("        <tr>\n");
+    table.push_str("            <th class=\"track-number\">#</th>\n");
+    table.push_str("            <th>Track Name</th>\n");
+    table.push_str("            <th>Artist</th>\n");
+    table.push_str("            <th>Album</th>\n");
+    table.push_str("            <th>Added Date</th>\n");
+    table.push_str("        </tr>\n");
+    table.push_str("    </thead>\n");
+    table.push_str("    <tbody>\n");
+
+    for row in rows {
+        push_indented(&mut table, row, "        ");
+    }
+
+    table.push_str("    </tbody>\n");
+    table.push_str("</table>\n");
+
+    table
+}
+
+/// Tracks table for playlists above the lazy-loading threshold: the first
+/// chunk of rows is rendered directly, the rest are inlined as a JSON array of
+/// row markup and appended by `LAZY_ROWS_SCRIPT` as the reader scrolls.
+fn generate_lazy_tracks_table(
+    playlist: &Playlist,
+    options: &RenderOptions,
+    chunk: usize,
+) -> String {
+    let rows = generate_track_rows(playlist, options);
+    let (initial, pending) = rows.split_at(chunk.min(rows.len()));
+
+    let mut table = tracks_table_with_rows(initial);
+    if pending.is_empty() {
+        return table;
+    }
+
+    let pending: Vec<String> = pending.iter().map(|row| row.replace('\n', "")).collect();
+    // `</` must not appear inside a script element
+    let data = serde_json::to_string(&pending)
+        .unwrap_or_else(|_| "[]".to_string())
+        .replace("</", "<\\/");
+
+    table.push_str(&format!(
+        "<script type=\"application/json\" id=\"lazy-rows\" data-chunk=\"{}\">{}</script>\n",
+        chunk, data
+    ));
+    table.push_str("<div class=\"lazy-sentinel\">Loading more tracks…</div>\n");
+    table.push_str(&format!(
+        "<noscript><p>{} more tracks are only listed with JavaScript enabled.</p></noscript>\n",
+        pending.len()
+    ));
+
+    table
+}
+
+/// Copy-to-clipboard buttons for a track's web URL and URI. They stay hidden
+/// until `COPY_BUTTONS_SCRIPT` marks the page as `copy-enabled`, so pages
+/// without scripts don't show dead buttons.
+fn copy_buttons_html(track_uri: &str) -> String {
+    let mut buttons = String::from(" <span class=\"copy-buttons\">");
+    if let Some(url) = uri::web_url(track_uri) {
+        buttons.push_str(&format!(
+            "<button type=\"button\" class=\"copy-button\" data-copy=\"{}\" title=\"Copy Spotify link\">URL</button>",
+            escape_html(&url)
+        ));
+    }
+    buttons.push_str(&format!(
+        "<button type=\"button\" class=\"copy-button\" data-copy=\"{}\" title=\"Copy Spotify URI\">URI</button>",
+        escape_html(track_uri)
+    ));
+    buttons.push_str("</span>");
+    buttons
+}
+
+pub fn generate_embed_html(playlist: &Playlist, options: &RenderOptions) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str(&format!(
+        "    <title>{}</title>\n",
+        escape_html(&playlist.name)
+    ));
+    html.push_str("    <base target=\"_blank\">\n");
+    html.push_str("    <style>\n");
+    html.push_str("        body {\n");
+    html.push_str("            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;\n");
+    html.push_str("            font-size: 13px;\n");
+    html.push_str("            margin: 0;\n");
+    html.push_str("            background-color: white;\n");
+    html.push_str("        }\n");
+    html.push_str("        .embed-header {\n");
+    html.push_str("            position: sticky;\n");
+    html.push_str("            top: 0;\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            padding: 8px 12px;\n");
+    html.push_str("            font-weight: bold;\n");
+    html.push_str("        }\n");
+    html.push_str("        .embed-header span {\n");
+    html.push_str("            font-weight: normal;\n");
+    html.push_str("            opacity: 0.8;\n");
+    html.push_str("        }\n");
+    html.push_str("        table {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            border-collapse: collapse;\n");
+    html.push_str("        }\n");
+    html.push_str("        thead {\n");
+    html.push_str("            display: none;\n");
+    html.push_str("        }\n");
+    html.push_str("        td {\n");
+    html.push_str("            padding: 4px 8px;\n");
+    html.push_str("            border-bottom: 1px solid #eee;\n");
+    html.push_str("        }\n");
+    html.push_str("        a {\n");
+    html.push_str("            color: #1db954;\n");
+    html.push_str("            text-decoration: none;\n");
+    html.push_str("        }\n");
+    html.push_str("        .track-number {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("            text-align: right;\n");
+    html.push_str("            width: 30px;\n");
+    html.push_str("        }\n");
+    push_badge_styles(&mut html);
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+
+    html.push_str(&format!(
+        "    <div class=\"embed-header\">{} <span>· {} tracks</span></div>\n",
+        bidi_html(&playlist.name),
+        options.localizer.number(playlist.items.len())
+    ));
+
+    push_indented(&mut html, &generate_tracks_table(playlist, options), "    ");
+
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+/// Groups playlists by the part of their name before `separator`, keeping groups
+/// in the order they first appear. Playlists without the separator come last,
+/// under `None`.
+fn group_by_prefix(playlists: &[Playlist], separator: &str) -> Vec<(Option<String>, Vec<usize>)> {
+    let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+    let mut ungrouped = Vec::new();
+
+    for (idx, playlist) in playlists.iter().enumerate() {
+        let prefix = playlist
+            .name
+            .split_once(separator)
+            .map(|(prefix, _)| prefix.trim())
+            .filter(|prefix| !prefix.is_empty());
+
+        match prefix {
+            Some(prefix) => match groups
+                .iter_mut()
+                .find(|(name, _)| name.as_deref() == Some(prefix))
+            {
+                Some((_, members)) => members.push(idx),
+                None => groups.push((Some(prefix.to_string()), vec![idx])),
+            },
+            None => ungrouped.push(idx),
+        }
+    }
+
+    if !ungrouped.is_empty() {
+        groups.push((None, ungrouped));
+    }
+
+    groups
+}
+
+fn group_by_kind(
+    playlists: &[Playlist],
+    rules: &[kinds::KindRule],
+) -> Vec<(Option<String>, Vec<usize>)> {
+    let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+    let mut unclassified = Vec::new();
+
+    for (idx, playlist) in playlists.iter().enumerate() {
+        match kinds::classify(&playlist.name, rules) {
+            Some(kind) => match groups
+                .iter_mut()
+                .find(|(name, _)| name.as_deref() == Some(kind))
+            {
+                Some((_, members)) => members.push(idx),
+                None => groups.push((Some(kind.to_string()), vec![idx])),
+            },
+            None => unclassified.push(idx),
+        }
+    }
+
+    if !unclassified.is_empty() {
+        groups.push((None, unclassified));
+    }
+
+    groups
+}
+
+/// Sections by year of last modification, each sorted most recent first.
+/// Playlists without a parseable date come last.
+fn group_by_recency(
+    playlists: &[Playlist],
+    current_year: i32,
+) -> Vec<(Option<String>, Vec<usize>)> {
+    let mut sections: Vec<(Option<String>, Vec<usize>)> = vec![
+        (Some("Updated This Year".to_string()), Vec::new()),
+        (Some("Updated Last Year".to_string()), Vec::new()),
+        (Some("Older".to_string()), Vec::new()),
+        (None, Vec::new()),
+    ];
+
+    let mut by_date: Vec<(Option<dates::Date>, usize)> = playlists
+        .iter()
+        .enumerate()
+        .map(|(idx, playlist)| (dates::Date::parse(&playlist.last_modified_date), idx))
+        .collect();
+    by_date.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    for (date, idx) in by_date {
+        let section = match date {
+            Some(date) if date.year >= current_year => 0,
+            Some(date) if date.year == current_year - 1 => 1,
+            Some(_) => 2,
+            None => 3,
+        };
+        sections[section].1.push(idx);
+    }
+
+    sections.retain(|(_, members)| !members.is_empty());
+    sections
+}
+
+fn index_groups(
+    playlists: &[Playlist],
+    options: &RenderOptions,
+) -> Vec<(Option<String>, Vec<usize>)> {
+    let mut groups = match &options.index_grouping {
+        Some(IndexGrouping::Prefix(separator)) => group_by_prefix(playlists, separator),
+        Some(IndexGrouping::Kind(rules)) => group_by_kind(playlists, rules),
+        Some(IndexGrouping::Recency { current_year }) => group_by_recency(playlists, *current_year),
+        None => vec![(None, (0..playlists.len()).collect())],
+    };
+
+    if options.sort_index_by_name {
+        let compare = options.collation.comparator();
+        for (_, members) in &mut groups {
+            members.sort_by(|&a, &b| compare(&playlists[a].name, &playlists[b].name));
+        }
+    }
+
+    groups
+}
+
+/// The kind badge shown on index entries when grouping by kind
+fn playlist_kind<'a>(playlist: &Playlist, options: &'a RenderOptions) -> Option<&'a str> {
+    match &options.index_grouping {
+        Some(IndexGrouping::Kind(rules)) => kinds::classify(&playlist.name, rules),
+        _ => None,
+    }
+}
+
+/// The most frequent artists of a playlist, as many as the index shows per card
+fn top_artists(playlist: &Playlist, options: &RenderOptions) -> Vec<(String, usize)> {
+    if options.index_top_artists == 0 {
+        return Vec::new();
+    }
+
+    let mut top = analysis::analyze(playlist).top_artists;
+    top.truncate(options.index_top_artists);
+    top
+}
+
+/// What tells apart playlists sharing a name (ignoring case) on the index:
+/// the last-modified date if no other playlist of that name has it, else the
+/// track count, else the playlist's position among them. `None` for names
+/// only one playlist has.
+fn duplicate_name_details(playlists: &[Playlist], options: &RenderOptions) -> Vec<Option<String>> {
+    let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, playlist) in playlists.iter().enumerate() {
+        by_name
+            .entry(playlist.name.trim().to_lowercase())
+            .or_default()
+            .push(idx);
+    }
+
+    let mut details = vec![None; playlists.len()];
+    for members in by_name.values().filter(|members| members.len() > 1) {
+        let unique = |idx: usize, key: fn(&Playlist) -> String| {
+            let value = key(&playlists[idx]);
+            members
+                .iter()
+                .filter(|&&other| key(&playlists[other]) == value)
+                .count()
+                == 1
+        };
+        for (position, &idx) in members.iter().enumerate() {
+            let playlist = &playlists[idx];
+            details[idx] = Some(
+                if !playlist.last_modified_date.is_empty()
+                    && unique(idx, |p| p.last_modified_date.clone())
+                {
+                    format!("modified {}", playlist.last_modified_date)
+                } else if unique(idx, |p| p.items.len().to_string()) {
+                    format!("{} tracks", options.localizer.number(playlist.items.len()))
+                } else {
+                    format!("{} of {}", position + 1, members.len())
+                },
+            );
+        }
+    }
+    details
+}
+
+fn markdown_index_entry(
+    playlist: &Playlist,
+    detail: Option<&str>,
+    filename: &str,
+    options: &RenderOptions,
+    links: &mut MarkdownLinks,
+    ids: &mut slug::UniqueIds,
+) -> String {
+    let mut entry = format!(
+        "- {}{}{} - {} tracks, {} followers",
+        options
+            .markdown_flavor
+            .anchor(&ids.id_for("playlist", &playlist.name)),
+        links.link(
+            &format!("**{}**", options.markdown_flavor.escape(&playlist.name)),
+            &markdown_link_target(filename)
+        ),
+        detail
+            .map(|detail| format!(" ({})", detail))
+            .unwrap_or_default(),
+        options.localizer.number(playlist.items.len()),
+        options.localizer.number(playlist.number_of_followers)
+    );
+
+    if let Some(kind) = playlist_kind(playlist, options) {
+        entry.push_str(&format!(" *{}*", kind));
+    }
+
+    let top_artists = top_artists(playlist, options);
+    if !top_artists.is_empty() {
+        let artists: Vec<String> = top_artists
+            .iter()
+            .map(|(artist, count)| {
+                format!(
+                    "{} ({})",
+                    options.markdown_flavor.escape(artist),
+                    options.localizer.number(*count)
+                )
+            })
+            .collect();
+        entry.push_str(&format!(" - top: {}", artists.join(", ")));
+    }
+
+    if options.tags {
+        for tag in tags::extract_tags(playlist) {
+            entry.push_str(&format!(
+                " {}",
+                links.link(
+                    &format!("#{}", tag),
+                    &markdown_link_target(&tags::tag_filename(&tag, "md"))
+                )
+            ));
+        }
+    }
+
+    entry.push('\n');
+    entry
+}
+
+pub fn generate_tag_markdown(
+    tag: &str,
+    members: &[usize],
+    playlists: &[Playlist],
+    filenames: &[String],
+    options: &RenderOptions,
+) -> String {
+    let mut md = String::new();
+    let mut links = MarkdownLinks::new(options);
+    let mut ids = slug::UniqueIds::new();
+
+    md.push_str(
+        &options
+            .markdown_flavor
+            .heading(1, &format!("#{}", tag), "top"),
+    );
+    md.push_str("[← Back to Index](index.md)\n\n");
+    md.push_str(&format!(
+        "**Playlists:** {}\n\n",
+        options.localizer.number(members.len())
+    ));
+    let details = duplicate_name_details(playlists, options);
+
+    for &idx in members {
+        md.push_str(&markdown_index_entry(
+            &playlists[idx],
+            details[idx].as_deref(),
+            &filenames[idx],
+            options,
+            &mut links,
+            &mut ids,
+        ));
+    }
+    links.finish(&mut md);
+
+    md
+}
+
+pub fn generate_index_markdown(
+    playlists: &[Playlist],
+    filenames: &[String],
+    options: &RenderOptions,
+) -> String {
+    let mut md = String::new();
+    let mut links = MarkdownLinks::new(options);
+    let mut ids = slug::UniqueIds::new();
+    let flavor = options.markdown_flavor;
+
+    md.push_str(&flavor.heading(1, "My Spotify Playlists", "top"));
+
+    let total_tracks: usize = playlists.iter().map(|p| p.items.len()).sum();
+    md.push_str(&format!(
+        "**Total Playlists:** {}\n\n",
+        options.localizer.number(playlists.len())
+    ));
+    md.push_str(&format!(
+        "**Total Tracks:** {}\n\n",
+        options.localizer.number(total_tracks)
+    ));
+
+    if options.tags {
+        let all_tags = tags::collect_tags(playlists, &options.collation);
+        if !all_tags.is_empty() {
+            md.push_str(&flavor.heading(2, "Tags", "tags"));
+            for (tag, members) in &all_tags {
+                md.push_str(&format!(
+                    "- {} ({})\n",
+                    links.link(
+                        &format!("#{}", tag),
+                        &markdown_link_target(&tags::tag_filename(tag, "md"))
+                    ),
+                    options.localizer.number(members.len())
+                ));
+            }
+            md.push('\n');
+        }
+    }
+
+    md.push_str(&flavor.heading(2, "Playlists", "playlists"));
+    let details = duplicate_name_details(playlists, options);
+
+    let grouped = options.index_grouping.is_some();
+    for (group, members) in index_groups(playlists, options) {
+        if grouped {
+            let group_name = group.as_deref().unwrap_or("Other");
+            md.push_str(&flavor.heading(
+                3,
+                &format!(
+                    "{} ({})",
+                    flavor.escape(group_name),
+                    options.localizer.number(members.len())
+                ),
+                &ids.id_for("group", group_name),
+            ));
+        }
+
+        for idx in members {
+            md.push_str(&markdown_index_entry(
+                &playlists[idx],
+                details[idx].as_deref(),
+                &filenames[idx],
+                options,
+                &mut links,
+                &mut ids,
+            ));
+        }
+
+        if grouped {
+            md.push('\n');
+        }
+    }
+    links.finish(&mut md);
+
+    md
+}
+
+fn push_index_styles(html: &mut String) {
+    html.push_str("        .stats {\n");
+    html.push_str("            display: flex;\n");
+    html.push_str("            gap: 30px;\n");
+    html.push_str("            margin-bottom: 30px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .stat-card {\n");
+    html.push_str("            background-color: #f9f9f9;\n");
+    html.push_str("            padding: 20px;\n");
+    html.push_str("            border-radius: 8px;\n");
+    html.push_str("            flex: 1;\n");
+    html.push_str("        }\n");
+    html.push_str("        .stat-card h3 {\n");
+    html.push_str("            margin: 0 0 10px 0;\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("            font-size: 14px;\n");
+    html.push_str("            text-transform: uppercase;\n");
+    html.push_str("        }\n");
+    html.push_str("        .stat-card p {\n");
+    html.push_str("            margin: 0;\n");
+    html.push_str("            font-size: 32px;\n");
+    html.push_str("            font-weight: bold;\n");
+    html.push_str("            color: #1db954;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-grid {\n");
+    html.push_str("            display: grid;\n");
+    html.push_str("            grid-template-columns: repeat(auto-fill, minmax(300px, 1fr));\n");
+    html.push_str("            gap: 20px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-card {\n");
+    html.push_str("            background-color: #f9f9f9;\n");
+    html.push_str("            padding: 20px;\n");
+    html.push_str("            border-radius: 8px;\n");
+    html.push_str("            transition: transform 0.2s, box-shadow 0.2s;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-card:hover {\n");
+    html.push_str("            transform: translateY(-2px);\n");
+    html.push_str("            box-shadow: 0 4px 12px rgba(0,0,0,0.15);\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-card h3 {\n");
+    html.push_str("            margin: 0 0 10px 0;\n");
+    html.push_str("            color: #333;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-card h3 a {\n");
+    html.push_str("            color: #333;\n");
+    html.push_str("        }\n");
+    html.push_str("        .name-detail {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("            font-size: 0.75em;\n");
+    html.push_str("            font-weight: normal;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-meta {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("            font-size: 14px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-group {\n");
+    html.push_str("            margin-bottom: 20px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .playlist-group summary {\n");
+    html.push_str("            cursor: pointer;\n");
+    html.push_str("            font-size: 18px;\n");
+    html.push_str("            font-weight: bold;\n");
+    html.push_str("            margin-bottom: 15px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .group-count {\n");
+    html.push_str("            color: #999;\n");
+    html.push_str("            font-weight: normal;\n");
+    html.push_str("            font-size: 14px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .index-filters {\n");
+    html.push_str("            margin-bottom: 20px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .filter-search {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            box-sizing: border-box;\n");
+    html.push_str("            padding: 10px;\n");
+    html.push_str("            margin-bottom: 10px;\n");
+    html.push_str("            border: 1px solid #ddd;\n");
+    html.push_str("            border-radius: 4px;\n");
+    html.push_str("            font-size: 16px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .filter-group {\n");
+    html.push_str("            display: inline-block;\n");
+    html.push_str("            margin: 0 20px 10px 0;\n");
+    html.push_str("        }\n");
+    html.push_str("        .filter-chip {\n");
+    html.push_str("            padding: 4px 12px;\n");
+    html.push_str("            margin: 0 4px 4px 0;\n");
+    html.push_str("            border: 1px solid #ddd;\n");
+    html.push_str("            border-radius: 14px;\n");
+    html.push_str("            background-color: white;\n");
+    html.push_str("            cursor: pointer;\n");
+    html.push_str("        }\n");
+    html.push_str("        .filter-chip.active {\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            border-color: #1db954;\n");
+    html.push_str("        }\n");
+    html.push_str("        .filter-status {\n");
+    html.push_str("            color: #666;\n");
+    html.push_str("            font-size: 14px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .artist-badges {\n");
+    html.push_str("            margin-top: 10px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .artist-badge {\n");
+    html.push_str("            display: inline-block;\n");
+    html.push_str("            margin: 0 6px 6px 0;\n");
+    html.push_str("            padding: 2px 10px;\n");
+    html.push_str("            border-radius: 12px;\n");
+    html.push_str("            background-color: #eee;\n");
+    html.push_str("            color: #333;\n");
+    html.push_str("            font-size: 12px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .kind-badge {\n");
+    html.push_str("            display: inline-block;\n");
+    html.push_str("            margin-bottom: 8px;\n");
+    html.push_str("            padding: 2px 10px;\n");
+    html.push_str("            border-radius: 12px;\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            font-size: 12px;\n");
+    html.push_str("            text-transform: uppercase;\n");
+    html.push_str("        }\n");
+    html.push_str("        .tag-list {\n");
+    html.push_str("            margin-top: 10px;\n");
+    html.push_str("        }\n");
+    html.push_str("        .tag {\n");
+    html.push_str("            display: inline-block;\n");
+    html.push_str("            margin: 0 6px 6px 0;\n");
+    html.push_str("            padding: 2px 10px;\n");
+    html.push_str("            border-radius: 12px;\n");
+    html.push_str("            background-color: #e8f8ee;\n");
+    html.push_str("            font-size: 12px;\n");
+    html.push_str("        }\n");
+}
+
+fn generate_tag_chips(tags: &[String]) -> String {
+    let mut chips = String::new();
+
+    chips.push_str("<div class=\"tag-list\">\n");
+    for tag in tags {
+        chips.push_str(&format!(
+            "    <a href=\"{}\" class=\"tag\">#{}</a>\n",
+            escape_html(&tags::tag_filename(tag, "html")),
+            escape_html(tag)
+        ));
+    }
+    chips.push_str("</div>\n");
+
+    chips
+}
+
+/// Size ranges offered by the index filter chips, as (bucket, label, upper bound)
+const SIZE_BUCKETS: &[(&str, &str, usize)] = &[
+    ("empty", "Empty", 0),
+    ("small", "1–20 tracks", 20),
+    ("medium", "21–100 tracks", 100),
+    ("large", "100+ tracks", usize::MAX),
+];
+
+fn size_bucket(track_count: usize) -> &'static str {
+    SIZE_BUCKETS
+        .iter()
+        .find(|(_, _, max)| track_count <= *max)
+        .map(|(bucket, _, _)| *bucket)
+        .unwrap_or("large")
+}
+
+fn modified_year(playlist: &Playlist) -> &str {
+    playlist.last_modified_date.get(..4).unwrap_or("")
+}
+
+/// Filter chips and a name search box for the index. Hidden until
+/// `INDEX_FILTER_SCRIPT` runs, since they do nothing without it.
+fn generate_index_filters(playlists: &[Playlist]) -> String {
+    let mut filters = String::new();
+
+    filters.push_str("<div class=\"index-filters\" hidden>\n");
+    filters.push_str(
+        "    <input type=\"search\" class=\"filter-search\" placeholder=\"Search playlists…\" aria-label=\"Search playlists\">\n",
+    );
+
+    filters.push_str("    <div class=\"filter-group\" data-filter=\"size\">\n");
+    for (bucket, label, _) in SIZE_BUCKETS {
+        if playlists
+            .iter()
+            .any(|p| size_bucket(p.items.len()) == *bucket)
+        {
+            filters.push_str(&format!(
+                "        <button type=\"button\" class=\"filter-chip\" data-value=\"{}\">{}</button>\n",
+                bucket, label
+            ));
+        }
+    }
+    filters.push_str("    </div>\n");
+
+    filters.push_str("    <div class=\"filter-group\" data-filter=\"followers\">\n");
+    filters.push_str(
+        "        <button type=\"button\" class=\"filter-chip\" data-value=\"any\">Has followers</button>\n",
+    );
+    filters.push_str("    </div>\n");
+
+    let mut years: Vec<&str> = playlists
+        .iter()
+        .map(modified_year)
+        .filter(|year| !year.is_empty())
+        .collect();
+    years.sort_unstable_by(|a, b| b.cmp(a));
+    years.dedup();
+
+    filters.push_str("    <div class=\"filter-group\" data-filter=\"year\">\n");
+    for year in years {
+        filters.push_str(&format!(
+            "        <button type=\"button\" class=\"filter-chip\" data-value=\"{0}\">{0}</button>\n",
+            escape_html(year)
+        ));
+    }
+    filters.push_str("    </div>\n");
+
+    filters.push_str("    <p class=\"filter-status\"></p>\n");
+    filters.push_str("</div>\n");
+
+    filters
+}
+
+/// Index filtering: at most one active chip per group, combined with AND
+/// across groups and the search box.
+const INDEX_FILTER_SCRIPT: &str = r#"        (function () {
+            var panel = document.querySelector('.index-filters');
+            if (!panel) return;
+            panel.hidden = false;
+            var search = panel.querySelector('.filter-search');
+            var status = panel.querySelector('.filter-status');
+            var cards = Array.prototype.slice.call(document.querySelectorAll('.playlist-card'));
+            var active = {};
+
+            function matches(card) {
+                var query = search.value.trim().toLowerCase();
+                if (query && card.getAttribute('data-name').indexOf(query) === -1) return false;
+                if (active.size && card.getAttribute('data-size') !== active.size) return false;
+                if (active.followers && Number(card.getAttribute('data-followers')) <= 0) return false;
+                if (active.year && card.getAttribute('data-year') !== active.year) return false;
+                return true;
+            }
+
+            function apply() {
+                var shown = 0;
+                cards.forEach(function (card) {
+                    var visible = matches(card);
+                    card.hidden = !visible;
+                    if (visible) shown++;
+                });
+                document.querySelectorAll('.playlist-group').forEach(function (group) {
+                    group.hidden = !group.querySelector('.playlist-card:not([hidden])');
+                });
+                status.textContent = shown === cards.length ? '' : shown + ' of ' + cards.length + ' playlists shown';
+            }
+
+            panel.addEventListener('click', function (event) {
+                var chip = event.target.closest('.filter-chip');
+                if (!chip) return;
+                var group = chip.closest('.filter-group');
+                var name = group.getAttribute('data-filter');
+                var value = chip.getAttribute('data-value');
+                active[name] = active[name] === value ? null : value;
+                group.querySelectorAll('.filter-chip').forEach(function (other) {
+                    other.classList.toggle('active', active[name] === other.getAttribute('data-value'));
+                });
+                apply();
+            });
+            search.addEventListener('input', apply);
+        })();
+"#;
+
+fn generate_playlist_card(
+    playlist: &Playlist,
+    detail: Option<&str>,
+    filename: &str,
+    options: &RenderOptions,
+    ids: &mut slug::UniqueIds,
+) -> String {
+    let mut card = String::new();
+
+    let filter_attributes = if options.index_filters {
+        format!(
+            " data-name=\"{}\" data-size=\"{}\" data-followers=\"{}\" data-year=\"{}\"",
+            escape_html(&playlist.name.to_lowercase()),
+            size_bucket(playlist.items.len()),
+            playlist.number_of_followers,
+            escape_html(modified_year(playlist))
+        )
+    } else {
+        String::new()
+    };
+    card.push_str(&format!(
+        "<div class=\"playlist-card\" id=\"{}\"{}>\n",
+        ids.id_for("playlist", &playlist.name),
+        filter_attributes
+    ));
+    card.push_str(&format!(
+        "    <h3 dir=\"auto\"><a href=\"{}\">{}</a>{}</h3>\n",
+        escape_html(filename),
+        escape_html(&playlist.name),
+        detail
+            .map(|detail| format!(
+                " <span class=\"name-detail\">{}</span>",
+                escape_html(detail)
+            ))
+            .unwrap_or_default()
+    ));
+    if let Some(kind) = playlist_kind(playlist, options) {
+        card.push_str(&format!(
+            "    <span class=\"kind-badge\">{}</span>\n",
+            escape_html(kind)
+        ));
+    }
+    card.push_str("    <div class=\"playlist-meta\">\n");
+    card.push_str(&format!(
+        "        {} tracks<br>\n",
+        options.localizer.number(playlist.items.len())
+    ));
+    card.push_str(&format!(
+        "        {} followers\n",
+        options.localizer.number(playlist.number_of_followers)
+    ));
+    card.push_str("    </div>\n");
+
+    let top_artists = top_artists(playlist, options);
+    if !top_artists.is_empty() {
+        card.push_str("    <div class=\"artist-badges\">\n");
+        for (artist, count) in top_artists {
+            card.push_str(&format!(
+                "        <span class=\"artist-badge\" title=\"{1} tracks\">{0} · {1}</span>\n",
+                escape_html(&artist),
+                options.localizer.number(count)
+            ));
+        }
+        card.push_str("    </div>\n");
+    }
+
+    if options.tags {
+        let tags = tags::extract_tags(playlist);
+        if !tags.is_empty() {
+            push_indented(&mut card, &generate_tag_chips(&tags), "    ");
+        }
+    }
+
+    card.push_str("</div>\n");
+
+    card
+}
+
+pub fn generate_index_html(
+    playlists: &[Playlist],
+    filenames: &[String],
+    options: &RenderOptions,
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>My Spotify Playlists</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    push_index_styles(&mut html);
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+
+    html.push_str("        <h1>My Spotify Playlists</h1>\n");
+
+    // Stats
+    let total_tracks: usize = playlists.iter().map(|p| p.items.len()).sum();
+    html.push_str("        <div class=\"stats\">\n");
+    html.push_str("            <div class=\"stat-card\">\n");
+    html.push_str("                <h3>Total Playlists</h3>\n");
+    html.push_str(&format!(
+        "                <p>{}</p>\n",
+        options.localizer.number(playlists.len())
+    ));
+    html.push_str("            </div>\n");
+    html.push_str("            <div class=\"stat-card\">\n");
+    html.push_str("                <h3>Total Tracks</h3>\n");
+    html.push_str(&format!(
+        "                <p>{}</p>\n",
+        options.localizer.number(total_tracks)
+    ));
+    html.push_str("            </div>\n");
+    html.push_str("        </div>\n");
+
+    // Tags
+    if options.tags {
+        let all_tags = tags::collect_tags(playlists, &options.collation);
+        if !all_tags.is_empty() {
+            html.push_str("        <h2>Tags</h2>\n");
+            html.push_str("        <div class=\"tag-list\">\n");
+            for (tag, members) in &all_tags {
+                html.push_str(&format!(
+                    "            <a href=\"{}\" class=\"tag\">#{} ({})</a>\n",
+                    escape_html(&tags::tag_filename(tag, "html")),
+                    escape_html(tag),
+                    options.localizer.number(members.len())
+                ));
+            }
+            html.push_str("        </div>\n");
+        }
+    }
+
+    // Playlist grid
+    let mut ids = slug::UniqueIds::new();
+    let details = duplicate_name_details(playlists, options);
+    html.push_str("        <h2>Playlists</h2>\n");
+
+    if options.index_filters {
+        push_indented(&mut html, &generate_index_filters(playlists), "        ");
+    }
+
+    if options.index_grouping.is_some() {
+        for (group, members) in index_groups(playlists, options) {
+            let group_name = group.as_deref().unwrap_or("Other");
+            html.push_str(&format!(
+                "        <details class=\"playlist-group\" id=\"{}\" open>\n",
+                ids.id_for("group", group_name)
+            ));
+            html.push_str(&format!(
+                "            <summary>{} <span class=\"group-count\">{}</span></summary>\n",
+                escape_html(group_name),
+                options.localizer.number(members.len())
+            ));
+            html.push_str("            <div class=\"playlist-grid\">\n");
+            for idx in members {
+                push_indented(
+                    &mut html,
+                    &generate_playlist_card(
+                        &playlists[idx],
+                        details[idx].as_deref(),
+                        &filenames[idx],
+                        options,
+                        &mut ids,
+                    ),
+                    "                ",
+                );
+            }
+            html.push_str("            </div>\n");
+            html.push_str("        </details>\n");
+        }
+    } else {
+        html.push_str("        <div class=\"playlist-grid\">\n");
+        for (_, members) in index_groups(playlists, options) {
+            for idx in members {
+                push_indented(
+                    &mut html,
+                    &generate_playlist_card(
+                        &playlists[idx],
+                        details[idx].as_deref(),
+                        &filenames[idx],
+                        options,
+                        &mut ids,
+                    ),
+                    "            ",
+                );
+            }
+        }
+        html.push_str("        </div>\n");
+    }
+
+    html.push_str("    </div>\n");
+
+    if options.index_filters {
+        html.push_str("    <script>\n");
+        html.push_str(INDEX_FILTER_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    if options.keyboard_shortcuts {
+        html.push_str("    <script>\n");
+        html.push_str(KEYBOARD_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_tag_html(
+    tag: &str,
+    members: &[usize],
+    playlists: &[Playlist],
+    filenames: &[String],
+    options: &RenderOptions,
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str(&format!("    <title>#{}</title>\n", escape_html(tag)));
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    push_index_styles(&mut html);
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str(&format!("        <h1>#{}</h1>\n", escape_html(tag)));
+    let mut ids = slug::UniqueIds::new();
+    let details = duplicate_name_details(playlists, options);
+    html.push_str("        <div class=\"playlist-grid\">\n");
+    for &idx in members {
+        push_indented(
+            &mut html,
+            &generate_playlist_card(
+                &playlists[idx],
+                details[idx].as_deref(),
+                &filenames[idx],
+                options,
+                &mut ids,
+            ),
+            "            ",
+        );
+    }
+    html.push_str("        </div>\n");
+    html.push_str("    </div>\n");
+    if options.keyboard_shortcuts {
+        html.push_str("    <script>\n");
+        html.push_str(KEYBOARD_SCRIPT);
+        html.push_str("    </script>\n");
+    }
+
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_index_html_fragment(
+    playlists: &[Playlist],
+    filenames: &[String],
+    options: &RenderOptions,
+) -> String {
+    let mut table = String::new();
+    let details = duplicate_name_details(playlists, options);
+
+    table.push_str("<table>\n");
+    table.push_str("    <thead>\n");
+    table.push_str("        <tr>\n");
+    table.push_str("            <th>Playlist</th>\n");
+    table.push_str("            <th>Tracks</th>\n");
+    table.push_str("            <th>Followers</th>\n");
+    table.push_str("        </tr>\n");
+    table.push_str("    </thead>\n");
+    table.push_str("    <tbody>\n");
+
+    for (idx, (playlist, filename)) in playlists.iter().zip(filenames.iter()).enumerate() {
+        table.push_str("        <tr>\n");
+        table.push_str(&format!(
+            "            <td dir=\"auto\"><a href=\"{}\">{}</a>{}</td>\n",
+            escape_html(filename),
+            escape_html(&playlist.name),
+            details[idx]
+                .as_deref()
+                .map(|detail| format!(" ({})", escape_html(detail)))
+                .unwrap_or_default()
+        ));
+        table.push_str(&format!(
+            "            <td>{}</td>\n",
+            options.localizer.number(playlist.items.len())
+        ));
+        table.push_str(&format!(
+            "            <td>{}</td>\n",
+            options.localizer.number(playlist.number_of_followers)
+        ));
+        table.push_str("        </tr>\n");
+    }
+
+    table.push_str("    </tbody>\n");
+    table.push_str("</table>\n");
+
+    table
+}
+
+/// Removes every Spotify URI from the parsed export, so no output format can
+/// publish them. Items without a URI are rendered as plain names.
+pub fn scrub_uris(root: &mut Root) {
+    for playlist in &mut root.playlists {
+        playlist.uri = None;
+        for item in &mut playlist.items {
+            item.track.track_uri.clear();
+            if let Some(episode) = &mut item.episode {
+                episode.episode_uri.clear();
+            }
+            if let Some(audiobook) = &mut item.audiobook {
+                audiobook.audiobook_uri.clear();
+            }
+            if let Some(local) = &mut item.local_track {
+                // The names of a local file only live in its URI, so they
+                // move to the track fields before it goes
+                let file = local.file();
+                item.track.track_name = file.title;
+                item.track.artist_name = file.artist;
+                item.track.album_name = file.album;
+                local.uri.clear();
+            }
+        }
+    }
+}
+
+/// Replaces collaborator names with "Collaborator 1", "Collaborator 2" and so
+/// on, so published pages don't say who a playlist is shared with
+pub fn anonymize_collaborators(root: &mut Root) {
+    for playlist in &mut root.playlists {
+        for (idx, collaborator) in playlist.collaborators.iter_mut().enumerate() {
+            *collaborator = Collaborator::Username(format!("Collaborator {}", idx + 1));
+        }
+    }
+}
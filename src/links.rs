@@ -0,0 +1,264 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::uri;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenLink {
+    pub file: PathBuf,
+    pub target: String,
+    pub reason: String,
+}
+
+/// Links and anchor ids found in one generated document
+#[derive(Debug, Default)]
+struct Document {
+    links: Vec<String>,
+    ids: HashSet<String>,
+}
+
+fn attribute_values<'a>(html: &'a str, attribute: &str) -> Vec<&'a str> {
+    let needle = format!(" {}=\"", attribute);
+    let mut values = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find(&needle) {
+        let value_start = start + needle.len();
+        match rest[value_start..].find('"') {
+            Some(len) => {
+                values.push(&rest[value_start..value_start + len]);
+                rest = &rest[value_start + len..];
+            }
+            None => break,
+        }
+    }
+
+    values
+}
+
+fn decode_html_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn parse_html(html: &str) -> Document {
+    Document {
+        links: attribute_values(html, "href")
+            .into_iter()
+            .chain(attribute_values(html, "src"))
+            .map(decode_html_entities)
+            .collect(),
+        ids: attribute_values(html, "id")
+            .into_iter()
+            .map(decode_html_entities)
+            .collect(),
+    }
+}
+
+/// GitHub-style heading anchor: lowercase, punctuation dropped, spaces to hyphens
+fn heading_slug(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_markdown(md: &str) -> Document {
+    let mut document = Document {
+        ids: attribute_values(md, "id")
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        ..Document::default()
+    };
+
+    for line in md.lines() {
+        if let Some(heading) = line.strip_prefix('#') {
+            document
+                .ids
+                .insert(heading_slug(heading.trim_start_matches('#')));
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            let target_start = start + 2;
+            // Link destinations are written without spaces or parentheses, so
+            // the first `)` ends the link
+            match rest[target_start..].find(')') {
+                Some(len) => {
+                    let target = &rest[target_start..target_start + len];
+                    document
+                        .links
+                        .push(target.split_whitespace().next().unwrap_or("").to_string());
+                    rest = &rest[target_start + len..];
+                }
+                None => break,
+            }
+        }
+    }
+
+    document
+}
+
+/// Decodes `%XX` escapes, leaving malformed ones as they are
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = text.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn is_external(target: &str) -> bool {
+    target.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+    })
+}
+
+fn collect_documents(dir: &Path, documents: &mut HashMap<PathBuf, Document>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_documents(&path, documents)?;
+            continue;
+        }
+        let document = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("html") => parse_html(&fs::read_to_string(&path)?),
+            Some("md") => parse_markdown(&fs::read_to_string(&path)?),
+            _ => continue,
+        };
+        documents.insert(path, document);
+    }
+    Ok(())
+}
+
+/// Checks every internal link and anchor in the Markdown and HTML files below
+/// `dir`. External links are returned separately for optional online checking.
+pub fn check_internal_links(dir: &Path) -> io::Result<(Vec<BrokenLink>, BTreeSet<String>)> {
+    let mut documents = HashMap::new();
+    collect_documents(dir, &mut documents)?;
+
+    let mut broken = Vec::new();
+    let mut external = BTreeSet::new();
+    let mut files: Vec<&PathBuf> = documents.keys().collect();
+    files.sort();
+
+    for file in files {
+        for link in &documents[file].links {
+            if link.is_empty() || link == "#" {
+                continue;
+            }
+            if is_external(link) {
+                external.insert(link.clone());
+                continue;
+            }
+
+            let (path, fragment) = match link.split_once('#') {
+                Some((path, fragment)) => (path, Some(percent_decode(fragment))),
+                None => (link.as_str(), None),
+            };
+            let path = path.split('?').next().unwrap_or("");
+
+            let target = if path.is_empty() {
+                file.clone()
+            } else {
+                file.parent().unwrap_or(dir).join(percent_decode(path))
+            };
+
+            if !target.exists() {
+                broken.push(BrokenLink {
+                    file: file.clone(),
+                    target: link.clone(),
+                    reason: "file not found".to_string(),
+                });
+                continue;
+            }
+
+            if let Some(fragment) = fragment.filter(|fragment| !fragment.is_empty())
+                && let Some(document) = documents.get(&target)
+                && !document.ids.contains(&fragment)
+            {
+                broken.push(BrokenLink {
+                    file: file.clone(),
+                    target: link.clone(),
+                    reason: format!("no anchor '{}'", fragment),
+                });
+            }
+        }
+    }
+
+    Ok((broken, external))
+}
+
+/// HEAD-checks external links, at most `per_second` requests per second.
+/// `spotify:` URIs are checked through their open.spotify.com page; other
+/// non-HTTP links are skipped. Returns the links that failed with the reason.
+pub fn check_external_links(links: &BTreeSet<String>, per_second: f64) -> Vec<(String, String)> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(15))
+        .build();
+    let interval = Duration::from_secs_f64(1.0 / per_second.max(0.01));
+    let mut failures = Vec::new();
+    let mut checked = HashSet::new();
+    let mut last_request: Option<Instant> = None;
+
+    for link in links {
+        let url = if link.starts_with("http://") || link.starts_with("https://") {
+            link.clone()
+        } else if let Some(url) = uri::web_url(link) {
+            url
+        } else {
+            continue;
+        };
+        if !checked.insert(url.clone()) {
+            continue;
+        }
+
+        if let Some(last) = last_request {
+            let elapsed = last.elapsed();
+            if elapsed < interval {
+                thread::sleep(interval - elapsed);
+            }
+        }
+        last_request = Some(Instant::now());
+
+        match agent.head(&url).call() {
+            Ok(_) => {}
+            Err(ureq::Error::Status(code, _)) => {
+                failures.push((link.clone(), format!("HTTP {}", code)))
+            }
+            Err(error) => failures.push((link.clone(), error.to_string())),
+        }
+    }
+
+    failures
+}
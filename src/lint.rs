@@ -0,0 +1,236 @@
+use crate::dates::Date;
+use crate::{
+    Item, ItemKind, Playlist, bidi_html, escape_html, escape_markdown, get_common_styles,
+    markdown_link_target, uri,
+};
+use serde_json::{Value, json};
+
+/// Something in the export that converts fine but looks wrong
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataWarning {
+    /// Index of the playlist
+    pub playlist: usize,
+    /// 1-based position of the track, for warnings about a track
+    pub position: Option<usize>,
+    /// Export field the warning is about, as spelled in the JSON
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Episodes, audiobooks and local files have no Spotify track to check
+fn is_track(item: &Item) -> bool {
+    item.kind() == ItemKind::Track
+}
+
+/// Suspicious values in the export: added dates in the future or that
+/// aren't dates, negative follower counts, tracks with empty names and
+/// track URIs that aren't `spotify:track:<id>`
+pub fn check_export(playlists: &[Playlist], today: Date) -> Vec<DataWarning> {
+    let mut warnings = Vec::new();
+
+    for (idx, playlist) in playlists.iter().enumerate() {
+        if playlist.number_of_followers < 0 {
+            warnings.push(DataWarning {
+                playlist: idx,
+                position: None,
+                field: "numberOfFollowers",
+                message: format!(
+                    "follower count is negative ({})",
+                    playlist.number_of_followers
+                ),
+            });
+        }
+
+        for (item_idx, item) in playlist.items.iter().enumerate() {
+            let mut warn = |field, message| {
+                warnings.push(DataWarning {
+                    playlist: idx,
+                    position: Some(item_idx + 1),
+                    field,
+                    message,
+                })
+            };
+
+            if !item.added_date.is_empty() {
+                match Date::parse(&item.added_date) {
+                    Some(added) if added > today => warn(
+                        "addedDate",
+                        format!("added date {} is in the future", added),
+                    ),
+                    Some(_) => {}
+                    None => warn(
+                        "addedDate",
+                        format!("added date '{}' is not a date", item.added_date),
+                    ),
+                }
+            }
+
+            if !is_track(item) {
+                continue;
+            }
+            let track = &item.track;
+            if track.track_name.trim().is_empty() {
+                warn("trackName", "track name is empty".to_string());
+            }
+            if uri::track_id(&track.track_uri).is_none() {
+                warn(
+                    "trackUri",
+                    if track.track_uri.is_empty() {
+                        "track URI is missing".to_string()
+                    } else {
+                        format!(
+                            "track URI '{}' is not a spotify:track: URI",
+                            track.track_uri
+                        )
+                    },
+                );
+            }
+        }
+    }
+
+    warnings
+}
+
+/// The warning as one line, e.g. for printing while converting
+pub fn describe(warning: &DataWarning, playlists: &[Playlist]) -> String {
+    let name = &playlists[warning.playlist].name;
+    match warning.position {
+        Some(position) => format!("{}, track {}: {}", name, position, warning.message),
+        None => format!("{}: {}", name, warning.message),
+    }
+}
+
+pub fn generate_validation_markdown(
+    warnings: &[DataWarning],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Data Warnings\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+    md.push_str(&format!("- **Warnings:** {}\n\n", warnings.len()));
+
+    if !warnings.is_empty() {
+        md.push_str("| Playlist | Track | Field | Warning |\n");
+        md.push_str("|----------|-------|-------|---------|\n");
+        for warning in warnings {
+            md.push_str(&format!(
+                "| [{}]({}) | {} | `{}` | {} |\n",
+                escape_markdown(&playlists[warning.playlist].name),
+                markdown_link_target(&filenames[warning.playlist]),
+                warning
+                    .position
+                    .map(|position| position.to_string())
+                    .unwrap_or_default(),
+                warning.field,
+                escape_markdown(&warning.message)
+            ));
+        }
+    }
+
+    md
+}
+
+pub fn generate_validation_html(
+    warnings: &[DataWarning],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Data Warnings</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        table {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            border-collapse: collapse;\n");
+    html.push_str("        }\n");
+    html.push_str("        th {\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            text-align: start;\n");
+    html.push_str("        }\n");
+    html.push_str("        td {\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            border-bottom: 1px solid #ddd;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Data Warnings</h1>\n");
+    html.push_str("        <ul>\n");
+    html.push_str(&format!(
+        "            <li><strong>Warnings:</strong> {}</li>\n",
+        warnings.len()
+    ));
+    html.push_str("        </ul>\n");
+
+    if !warnings.is_empty() {
+        html.push_str("        <table>\n");
+        html.push_str("            <thead>\n");
+        html.push_str("                <tr>\n");
+        for heading in ["Playlist", "Track", "Field", "Warning"] {
+            html.push_str(&format!("                    <th>{}</th>\n", heading));
+        }
+        html.push_str("                </tr>\n");
+        html.push_str("            </thead>\n");
+        html.push_str("            <tbody>\n");
+        for warning in warnings {
+            html.push_str("                <tr>\n");
+            html.push_str(&format!(
+                "                    <td><a href=\"{}\">{}</a></td>\n",
+                escape_html(&filenames[warning.playlist]),
+                bidi_html(&playlists[warning.playlist].name)
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                warning
+                    .position
+                    .map(|position| position.to_string())
+                    .unwrap_or_default()
+            ));
+            html.push_str(&format!(
+                "                    <td><code>{}</code></td>\n",
+                warning.field
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                escape_html(&warning.message)
+            ));
+            html.push_str("                </tr>\n");
+        }
+        html.push_str("            </tbody>\n");
+        html.push_str("        </table>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_validation_json(
+    warnings: &[DataWarning],
+    playlists: &[Playlist],
+    filenames: &[String],
+) -> Value {
+    json!({
+        "warnings": warnings.iter().map(|warning| {
+            json!({
+                "playlist": playlists[warning.playlist].name,
+                "file": filenames[warning.playlist],
+                "position": warning.position,
+                "field": warning.field,
+                "message": warning.message,
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,50 @@
+use crate::{ItemKind, Playlist};
+
+/// Extension of the written playlists; `.m3u8` tells players the file is UTF-8
+pub const EXTENSION: &str = "m3u8";
+
+/// Text on one `#EXTINF` or directive line, which can't span lines
+fn single_line(text: &str) -> String {
+    text.split(['\r', '\n'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// An extended M3U playlist with an `#EXTINF` line per track, "Artist -
+/// Track" with an unknown duration, and the Spotify URI as its location.
+/// Items without a URI have no location to list and are left out, as are
+/// local files, whose `spotify:local:` URI no player can open.
+pub fn generate_m3u(playlist: &Playlist) -> String {
+    let mut m3u = String::from("#EXTM3U\n");
+    m3u.push_str(&format!("#PLAYLIST:{}\n", single_line(&playlist.name)));
+
+    for item in &playlist.items {
+        if item.uri().is_empty() || item.kind() == ItemKind::Local {
+            continue;
+        }
+        let creator = item.creator();
+        let title = if creator.is_empty() {
+            item.title().into_owned()
+        } else {
+            format!("{} - {}", creator, item.title())
+        };
+        m3u.push_str(&format!("#EXTINF:-1,{}\n", single_line(&title)));
+        m3u.push_str(item.uri());
+        m3u.push('\n');
+    }
+
+    m3u
+}
+
+/// A playlist of the generated playlist files, which players such as VLC
+/// open as nested playlists
+pub fn generate_index_m3u(playlists: &[Playlist], filenames: &[String]) -> String {
+    let mut m3u = String::from("#EXTM3U\n");
+    for (playlist, filename) in playlists.iter().zip(filenames) {
+        m3u.push_str(&format!("#EXTINF:-1,{}\n", single_line(&playlist.name)));
+        m3u.push_str(filename);
+        m3u.push('\n');
+    }
+    m3u
+}
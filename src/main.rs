@@ -1,513 +1,2697 @@
-use clap::Parser;
-use serde_derive::Deserialize;
-use serde_derive::Serialize;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use serde_json::Value;
+use spotify_converter::{
+    FilenameStyle, HtmlFragmentRenderer, IndexGrouping, MarkdownFlavor, Playlist, RenderOptions,
+    Root, TrackPreview, analysis, anonymize_collaborators, archive, availability, beets,
+    builtin_renderers, changelog, collation, config, console, csp, csv, dates, diff, digest,
+    duplicates, email,
+    error::ConverterError,
+    export_check::{self, FieldIssue, ParseMode},
+    export_zip, front_matter, generate_embed_html, generate_markdown, generate_tag_html,
+    generate_tag_markdown, git, hooks, journal, kinds, kodi, l10n, links, lint, match_keys,
+    metrics, normalize, output, parallel, parse_export_with, parse_index_grouping,
+    parse_markdown_flavor, parse_track_anchors, parse_uri_style, playlist_basename,
+    playlist_basenames, plugin, previews, sanitize_path, schedule, scrub_uris, select, server,
+    slug, source, spotify, stale, summary, tags, template, tenure, unique_basenames, validate,
+};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Root {
-    pub playlists: Vec<Playlist>,
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Convert Spotify playlists JSON to Markdown, HTML or CSV files", long_about = None)]
+#[command(subcommand_negates_reqs = true, args_override_self = true)]
+#[command(
+    after_help = "Exit status: 0 on success, 1 when the run fails, 2 for invalid options, 3 for I/O errors, 4 for input that can't be parsed, 5 when output can't be written"
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Named profile: options from profiles/NAME.json in the config directory, and its own saved credentials
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Never touch the network; options that need it are rejected instead of run
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Locale for sorting, number grouping and month names, e.g. sv or de-AT (default: language-neutral order, plain numbers, English)
+    #[arg(long, global = true, visible_alias = "lang")]
+    locale: Option<String>,
+
+    /// Print without colors; they are also left out when output isn't a terminal or NO_COLOR is set
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Input JSON file path, or the ZIP of a Spotify account data export; repeat it or give a glob such as 'Playlist*.json' to merge several files into one output
+    #[arg(short, long, num_args = 1..)]
+    input: Vec<String>,
+
+    /// Output directory for files
+    #[arg(short, long, default_value = "output")]
+    output: String,
+
+    /// Generate into a fresh directory next to the output directory and swap it in only when the run succeeds,
+    /// so a failed run leaves the previous output as it was
+    #[arg(long)]
+    swap: bool,
+
+    /// Write into a subdirectory of the output directory named after today's date (UTC), e.g. output/2024-06-01/,
+    /// and point output/latest at it when the run succeeds
+    #[arg(long)]
+    dated: bool,
+
+    /// Output format: markdown, html, csv, email (`.eml` with HTML and plain-text parts), m3u (`.m3u8` playlists with Spotify URIs), xspf, archive (a single normalized archive.json, itself accepted as input), or the name of a plugin
+    #[arg(short, long, default_value = "markdown")]
+    format: String,
+
+    /// Directory of WebAssembly output format plugins; `--format NAME` uses NAME.wasm from it
+    #[arg(long, value_name = "DIR", default_value = plugin::DEFAULT_PLUGIN_DIR)]
+    plugin_dir: String,
+
+    /// Field delimiter for CSV output: a single character, or tab, comma, semicolon, pipe
+    #[arg(long, default_value = ",")]
+    delimiter: String,
+
+    /// Quoting of CSV fields: necessary, always or never
+    #[arg(long, default_value = "necessary")]
+    quote_style: String,
+
+    /// Write a single denormalized tracks table instead of one file per playlist (csv only; there is no Parquet output,
+    /// but DuckDB reads the table directly: `select * from 'tracks.csv'`)
+    #[arg(long)]
+    flat: bool,
+
+    /// Write one document to stdout instead of files, for pipelines: every playlist's Markdown one after another, the flat tracks table (csv) or the archive
+    #[arg(long)]
+    stdout: bool,
+
+    /// Emit only the HTML table, without doctype, head or styles, for pasting into other pages
+    #[arg(long)]
+    fragment: bool,
+
+    /// Also write a compact, iframe-friendly `<name>.embed.html` page per playlist
+    #[arg(long)]
+    embed: bool,
+
+    /// Render tags found in playlist names and descriptions (`#tag` or `[tag]`) and write per-tag index pages
+    #[arg(long)]
+    tags: bool,
+
+    /// How emoji in playlist names appear in filenames: keep, or shortcode (e.g. "fire")
+    #[arg(long, default_value = "keep")]
+    emoji: String,
+
+    /// Name files by lowercase ASCII slugs, e.g. "Été à Tōkyō 🎶" as ete-a-tokyo, for hosting on the web;
+    /// pages keep the playlist's own name as their title. Emoji are left out unless --emoji shortcode.
+    #[arg(long)]
+    slug: bool,
+
+    /// Template for output filenames, e.g. "{index:03}-{slug}" (fields: name, slug, index, year, track_count,
+    /// format); a '/' puts files in subdirectories, e.g. "{name}/{format}" for a directory per playlist
+    #[arg(long)]
+    filename_template: Option<String>,
+
+    /// Order of playlists on the index: export (as in Spotify's sidebar) or name
+    #[arg(long, default_value = "export")]
+    sort_index: String,
+
+    /// Prefix filenames with the playlist's position in the export (e.g. "007 - Road Trip") and keep the index in export order
+    #[arg(long)]
+    numbered: bool,
+
+    /// Per-track anchors for deep links: position (#track-42), uri (#track-<spotify id>) or none
+    #[arg(long, default_value = "position")]
+    track_anchors: String,
+
+    /// Link style for Spotify items: uri (desktop app), web (open.spotify.com) or both
+    #[arg(long, visible_alias = "link-style", default_value = "uri")]
+    uri_style: String,
+
+    /// Keep the HTML track table header visible while scrolling
+    #[arg(long)]
+    sticky_header: bool,
+
+    /// Show the HTML back-to-top button only after scrolling down
+    #[arg(long)]
+    auto_hide_back_to_top: bool,
+
+    /// Add per-row buttons to the HTML track table copying the Spotify URL and URI
+    #[arg(long)]
+    copy_buttons: bool,
+
+    /// Add a search box and filter chips (size, followers, modified year) to the HTML index
+    #[arg(long)]
+    index_filters: bool,
+
+    /// Number of most frequent artists shown per playlist on the index (0 to hide)
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    index_top_artists: usize,
+
+    /// Add keyboard navigation to HTML pages: / search, j/k move, Enter open, Esc back to index
+    #[arg(long)]
+    keyboard_shortcuts: bool,
+
+    /// Render HTML track tables with more than N rows in chunks of N while scrolling
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(usize))]
+    lazy_rows: Option<usize>,
+
+    /// Show only the first N tracks on each playlist page, linking to a full `<name>.full` listing when there are more
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(usize))]
+    preview_rows: Option<usize>,
+
+    /// Earlier export to compare against; writes a changes report listing added, removed, renamed and changed playlists
+    #[arg(long, value_name = "OLD_JSON")]
+    compare: Option<String>,
+
+    /// Mark tracks added since the previous run into this output directory with a "new" badge, and list
+    /// removed ones struck through on each playlist page; the first run records what later ones compare against
+    #[arg(long)]
+    annotate_changes: bool,
+
+    /// Write a changelog report listing what each run into this output directory changed: playlists added,
+    /// removed and renamed, and the tracks of each that came and went
+    #[arg(long)]
+    changelog: bool,
+
+    /// Commit the output directory to git after a successful run, making it a repository first if it isn't one;
+    /// the commit message counts the tracks added and removed since the previous run
+    #[arg(long)]
+    git_commit: bool,
+
+    /// Push the commit made by --git-commit to the repository's upstream
+    #[arg(long)]
+    git_push: bool,
+
+    /// Emit CSP-compatible HTML: styles and scripts go to hashed files in assets/ instead of inline
+    #[arg(long)]
+    csp: bool,
+
+    /// Check generated HTML for well-formedness and fail the run if any page is malformed
+    #[arg(long)]
+    check_output: bool,
+
+    /// Command the export JSON is piped through before conversion; it prints the JSON to use, e.g. `jq '...'`
+    #[arg(long, value_name = "COMMAND")]
+    pre_filter: Option<String>,
+
+    /// Fail on any field of the export that doesn't match the expected format, naming its path, instead of filling in defaults and listing them
+    #[arg(long)]
+    strict: bool,
+
+    /// Command run for every generated file, with `{file}` replaced by its path, e.g. `'minify -o {file} {file}'`
+    #[arg(long, value_name = "COMMAND")]
+    postprocess: Option<String>,
+
+    /// Permission mode of the generated files in octal, e.g. 644 so every user on a shared host can read them
+    #[arg(long, value_name = "MODE")]
+    file_mode: Option<String>,
+
+    /// Date each playlist's files by its lastModifiedDate, and the index and other files by the latest one, so listings sorted by date follow the playlists
+    #[arg(long)]
+    mtime_from_modified: bool,
+
+    /// Keep running and regenerate at these times, a cron expression in UTC such as "0 3 * * *"; runs are skipped while the input is unchanged, and a directory as --input means its newest .json file
+    #[arg(long, value_name = "CRON")]
+    schedule: Option<String>,
+
+    /// Render playlists on at most this many threads; defaults to one per CPU
+    #[arg(long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Go easy on slow machines such as NAS boxes: render on one thread (unless --jobs is given) and pause between files
+    #[arg(long)]
+    nice: bool,
+
+    /// Rough cap on memory for rendered pages waiting to be written, e.g. 256M; playlists are then rendered in batches
+    #[arg(long, value_name = "SIZE")]
+    max_memory: Option<String>,
+
+    /// Emit HTML without any JavaScript; options that need it are ignored
+    #[arg(long)]
+    no_js: bool,
+
+    /// Group the index into sections by playlist name prefix, e.g. `prefix:" – "`, by kind
+    /// (Wrapped, Blend, Radio, This Is, ...) with `kind`, or by last modification with `recency`
+    #[arg(long, value_name = "prefix:SEPARATOR|kind|recency")]
+    group_index_by: Option<String>,
+
+    /// Look up every track through the Spotify API and write a report of unavailable ones
+    /// (needs SPOTIFY_CLIENT_ID and SPOTIFY_CLIENT_SECRET)
+    #[arg(long)]
+    check_availability: bool,
+
+    /// Country code (e.g. SE) to check availability in; reports tracks not playable in that market
+    #[arg(long, value_name = "COUNTRY")]
+    market: Option<String>,
+
+    /// Also suggest replacement URIs for dead or moved tracks, from Spotify's relinking
+    /// or a search by title and artist, in a remediation report
+    #[arg(long)]
+    relink: bool,
+
+    /// Download 30-second preview clips into assets/previews/ and add a player to each HTML track row
+    /// (needs SPOTIFY_CLIENT_ID and SPOTIFY_CLIENT_SECRET; Spotify no longer offers clips for every track)
+    #[arg(long)]
+    preview_audio: bool,
+
+    /// Leave Spotify URIs out of the output entirely (names only), for public archives
+    #[arg(long, alias = "no-links")]
+    scrub_uris: bool,
+
+    /// List playlist collaborators as "Collaborator 1", "Collaborator 2", … instead of by name
+    #[arg(long)]
+    anonymize_collaborators: bool,
+
+    /// Clean up track, artist and album names before rendering and analysis: trim whitespace and unify "ft."/"featuring" as "feat."
+    #[arg(long)]
+    normalize: bool,
+
+    /// Also title-case track, artist and album names (with --normalize)
+    #[arg(long)]
+    title_case: bool,
+
+    /// Convert only the playlist with this name; case, emoji and punctuation don't matter, and a partial
+    /// or slightly misspelled name is matched too, listing what it matched. Repeat for several playlists
+    #[arg(long, value_name = "NAME")]
+    playlist: Vec<String>,
+
+    /// Leave out playlists the export marks as generated by Spotify (owner or madeFor metadata)
+    #[arg(long)]
+    exclude_generated: bool,
+
+    /// Extra rule for `--group-index-by kind`, e.g. `"Workout=Gym *"`; `*` matches anything
+    #[arg(long, value_name = "LABEL=PATTERN")]
+    playlist_kind: Vec<String>,
+
+    /// Write a report of playlists not modified in this long, e.g. 3y, 18m or 90d
+    #[arg(long, value_name = "SPAN")]
+    stale_after: Option<String>,
+
+    /// Write a report of how long tracks have been on each playlist, oldest residents first
+    #[arg(long)]
+    tenure: bool,
+
+    /// Warn about suspicious data without failing: future added dates, negative follower counts,
+    /// empty track names and URIs that aren't spotify:track:, and list them in a validation report
+    #[arg(long)]
+    validate_on_convert: bool,
+
+    /// Write a report of tracks repeated within a playlist: exact (same URI) and probable (same artist, near-identical title)
+    #[arg(long)]
+    duplicates: bool,
+
+    /// Write match-keys.jsonl: every track with normalized artist and title keys, for tools
+    /// that find the audio in local collections through Chromaprint/AcoustID
+    #[arg(long)]
+    match_keys: bool,
+
+    /// Write beets-smartplaylist.yaml: a beets smartplaylist plugin section recreating each playlist from a local library
+    #[arg(long)]
+    beets: bool,
+
+    /// Write a Kodi smart playlist (.xsp) per playlist into kodi/, matching songs by title and artist
+    #[arg(long)]
+    kodi: bool,
+
+    /// Formats to write the reports in, comma-separated: md, html and json; defaults to md, or html with `--format html`.
+    /// Asking for json also writes stats.json with per-playlist figures.
+    #[arg(long, value_name = "FORMATS")]
+    report_format: Option<String>,
+
+    /// Date the export was taken (YYYY-MM-DD), for tenure; defaults to the latest date in it
+    #[arg(long, value_name = "DATE")]
+    export_date: Option<String>,
+
+    /// Markdown dialect to write: gfm, pandoc, or commonmark (no tables; tracks become a list)
+    #[arg(long, default_value = "gfm")]
+    markdown_flavor: String,
+
+    /// Write Markdown links as numbered references collected at the bottom of each file
+    #[arg(long)]
+    reference_links: bool,
+
+    /// Front matter (title, date, tags, track_count) for each playlist Markdown file: yaml, toml or none
+    #[arg(long, default_value = "none")]
+    front_matter: String,
+
+    #[command(flatten)]
+    email: EmailArgs,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Playlist {
-    pub name: String,
-    pub last_modified_date: String,
-    pub collaborators: Vec<Value>,
-    pub items: Vec<Item>,
-    pub description: Value,
-    pub number_of_followers: i64,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the links and anchors of a generated archive
+    CheckLinks(CheckLinksArgs),
+    /// Write a single page of everything added across playlists in one month
+    Digest(DigestArgs),
+    /// Save, check or remove the Spotify app credentials used for API features
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+    /// Serve a generated archive over HTTP, with a REST API over the export
+    Serve(ServeArgs),
+    /// Print the JSON Schema of the accepted input: a Spotify export, an archive.json, or either
+    Schema {
+        /// Only this shape: export or archive
+        #[arg(value_name = "SHAPE")]
+        shape: Option<String>,
+    },
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Item {
-    pub track: Track,
-    pub episode: Value,
-    pub audiobook: Value,
-    pub local_track: Value,
-    pub added_date: String,
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    /// Input JSON file path, or the ZIP of a Spotify account data export
+    #[arg(short, long)]
+    input: String,
+
+    /// Directory of generated files to serve next to the API
+    #[arg(short, long, default_value = "output")]
+    dir: String,
+
+    /// Address to listen on; use 0.0.0.0:PORT to accept connections from other machines
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Also answer GraphQL queries over the playlists at /api/graphql
+    #[arg(long)]
+    graphql: bool,
+
+    /// Require credentials on every request: basic (SERVE_USERNAME and SERVE_PASSWORD) or token (SERVE_TOKEN, sent as a Bearer token)
+    #[arg(long, value_name = "MODE")]
+    auth: Option<String>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Track {
-    pub track_name: String,
-    pub artist_name: String,
-    pub album_name: String,
-    pub track_uri: String,
+#[derive(Subcommand, Debug)]
+enum AuthAction {
+    /// Save Spotify app credentials, checking them with Spotify first
+    Login {
+        /// Client ID of the Spotify app; asked for if not given
+        #[arg(long)]
+        client_id: Option<String>,
+    },
+    /// Show which credentials are in use and whether Spotify accepts them
+    Status,
+    /// Remove the saved credentials
+    Logout,
 }
 
-#[derive(Parser, Debug)]
-#[command(author, version, about = "Convert Spotify playlists JSON to Markdown or HTML files", long_about = None)]
-struct Args {
-    /// Input JSON file path
+#[derive(clap::Args, Debug)]
+struct DigestArgs {
+    /// Input JSON file path, or the ZIP of a Spotify account data export
     #[arg(short, long)]
     input: String,
 
-    /// Output directory for files
+    /// Month to summarize, e.g. 2024-03
+    #[arg(short, long)]
+    month: String,
+
+    /// Output directory for the digest page
     #[arg(short, long, default_value = "output")]
     output: String,
 
-    /// Output format: markdown or html
+    /// Output format: markdown, html or email
     #[arg(short, long, default_value = "markdown")]
     format: String,
+
+    #[command(flatten)]
+    email: EmailArgs,
 }
 
-fn sanitize_filename(name: &str) -> String {
-    name.chars()
-        .map(|c| match c {
-            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
-            _ => c,
-        })
-        .collect::<String>()
-        .trim()
-        .to_string()
-}
-
-fn escape_html(text: &str) -> String {
-    text.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#39;")
-}
-
-fn get_common_styles() -> &'static str {
-    r#"
-        body {
-            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
-            max-width: 1200px;
-            margin: 0 auto;
-            padding: 20px;
-            background-color: #f5f5f5;
-        }
-        .container {
-            background-color: white;
-            border-radius: 8px;
-            padding: 30px;
-            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
-        }
-        h1 {
-            color: #1db954;
-            margin-bottom: 20px;
-        }
-        a {
-            color: #1db954;
-            text-decoration: none;
-        }
-        a:hover {
-            text-decoration: underline;
-        }
-        .back-to-top {
-            position: fixed;
-            bottom: 20px;
-            right: 20px;
-            background-color: #1db954;
-            color: white;
-            padding: 12px 20px;
-            border-radius: 25px;
-            text-decoration: none;
-            box-shadow: 0 2px 8px rgba(0,0,0,0.2);
-            transition: background-color 0.3s;
-        }
-        .back-to-top:hover {
-            background-color: #1ed760;
-            text-decoration: none;
-        }
-        .nav-link {
-            display: inline-block;
-            margin-bottom: 20px;
-            padding: 8px 16px;
-            background-color: #f0f0f0;
-            border-radius: 4px;
-        }
-    "#
-}
-
-fn generate_markdown(playlist: &Playlist) -> String {
-    let mut md = String::new();
-
-    // Header
-    md.push_str(&format!("# {}\n\n", playlist.name));
-
-    // Back to index link
-    md.push_str("[← Back to Index](index.md)\n\n");
-
-    // Metadata
-    md.push_str("## Playlist Information\n\n");
-    md.push_str(&format!(
-        "- **Last Modified:** {}\n",
-        playlist.last_modified_date
-    ));
-    md.push_str(&format!(
-        "- **Followers:** {}\n",
-        playlist.number_of_followers
-    ));
-    md.push_str(&format!("- **Total Tracks:** {}\n\n", playlist.items.len()));
-
-    if !playlist.items.is_empty() {
-        md.push_str("## Tracks\n\n");
-        md.push_str("| # | Track Name | Artist | Album | Added Date |\n");
-        md.push_str("|---|------------|--------|-------|------------|\n");
-
-        for (idx, item) in playlist.items.iter().enumerate() {
-            let track = &item.track;
-            md.push_str(&format!(
-                "| {} | [{}]({}) | {} | {} | {} |\n",
-                idx + 1,
-                escape_markdown(&track.track_name),
-                track.track_uri,
-                escape_markdown(&track.artist_name),
-                escape_markdown(&track.album_name),
-                item.added_date
+#[derive(clap::Args, Debug)]
+struct EmailArgs {
+    /// Send the email format's messages to this address (repeatable), through the SMTP server
+    /// in SMTP_HOST, SMTP_PORT, SMTP_USERNAME and SMTP_PASSWORD
+    #[arg(long, value_name = "ADDRESS")]
+    send_to: Vec<String>,
+
+    /// Sender address for --send-to
+    #[arg(long, value_name = "ADDRESS")]
+    email_from: Option<String>,
+}
+
+/// Checks the email sending options up front, returning the SMTP settings and
+/// sender when messages are to be sent
+fn email_delivery(
+    args: &EmailArgs,
+    format: &str,
+    offline: bool,
+) -> Result<Option<(email::SmtpSettings, String)>, ConverterError> {
+    if args.send_to.is_empty() {
+        if args.email_from.is_some() {
+            return Err(ConverterError::usage(
+                "--email-from is only supported with --send-to",
             ));
         }
+        return Ok(None);
+    }
+    if format != "email" {
+        return Err(ConverterError::usage(
+            "--send-to is only supported with the email format",
+        ));
+    }
+    require_network(offline, "--send-to")?;
+    let Some(from) = args.email_from.clone() else {
+        return Err(ConverterError::usage(
+            "--send-to needs a sender address in --email-from",
+        ));
+    };
+    let settings = email::SmtpSettings::from_env().map_err(ConverterError::Usage)?;
+    Ok(Some((settings, from)))
+}
+
+/// Writes a message as `.eml` and, with `delivery`, sends it
+fn deliver_email(
+    message: &email::Email,
+    filepath: &Path,
+    recipients: &[String],
+    delivery: &Option<(email::SmtpSettings, String)>,
+) -> Result<(), ConverterError> {
+    let from = delivery.as_ref().map(|(_, from)| from.as_str());
+    let mime = message.to_mime(from, recipients);
+    output::write_file(filepath, &mime)?;
+
+    if let Some((settings, from)) = delivery {
+        email::send(settings, from, recipients, &mime).map_err(ConverterError::Failed)?;
+        console::success(format!(
+            "Sent: {} to {}",
+            message.subject,
+            recipients.join(", ")
+        ));
     }
+    Ok(())
+}
+
+#[derive(clap::Args, Debug)]
+struct CheckLinksArgs {
+    /// Directory holding the generated Markdown or HTML files
+    #[arg(default_value = "output")]
+    dir: String,
 
-    md.push_str("\n[↑ Back to Top](#)\n\n");
-    md.push_str("[← Back to Index](index.md)\n");
+    /// Also check Spotify and other web links over the network
+    #[arg(long)]
+    external: bool,
 
-    md
+    /// Maximum number of external requests per second
+    #[arg(long, value_name = "N", default_value_t = 2.0)]
+    rate_limit: f64,
 }
 
-fn escape_markdown(text: &str) -> String {
-    text.replace('|', "\\|")
-        .replace('[', "\\[")
-        .replace(']', "\\]")
+/// Fails fast when `--offline` is combined with an option that needs network access
+fn require_network(offline: bool, option: &str) -> Result<(), ConverterError> {
+    if offline {
+        return Err(ConverterError::usage(format!(
+            "{} needs network access and cannot be used with --offline",
+            option
+        )));
+    }
+    Ok(())
+}
+
+/// Reads and parses an input file, with the fields of an export that didn't
+/// match the expected format
+fn read_export(input: &str, mode: ParseMode) -> Result<(Root, Vec<FieldIssue>), ConverterError> {
+    let text = export_zip::read_input(Path::new(input))?;
+    parse_export_with(&text, mode).map_err(|e| ConverterError::parse(input, e))
 }
 
-fn generate_html(playlist: &Playlist) -> String {
-    let mut html = String::new();
+fn serve(args: &ServeArgs) -> Result<(), ConverterError> {
+    let (root, _) = read_export(&args.input, ParseMode::Lenient)?;
+    let static_dir = Path::new(&args.dir);
+    if !static_dir.is_dir() {
+        println!(
+            "Note: {} is not a directory; only the API is served",
+            args.dir
+        );
+    }
+    let auth = args
+        .auth
+        .as_deref()
+        .map(|mode| server::parse_auth_mode(mode).and_then(server::Auth::from_env))
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+    let listener = std::net::TcpListener::bind(&args.listen)
+        .map_err(|e| ConverterError::failed(format!("cannot listen on {}: {}", args.listen, e)))?;
+    let loopback = listener
+        .local_addr()
+        .is_ok_and(|addr| addr.ip().is_loopback());
+    if auth.is_none() && !loopback {
+        println!(
+            "Note: {} is reachable from other machines and anyone who can connect sees the archive; consider --auth",
+            args.listen
+        );
+    }
 
-    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
-    html.push_str("    <meta charset=\"UTF-8\">\n");
-    html.push_str(
-        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    println!(
+        "Serving {} playlists on http://{}/ (API under /api/playlists and /api/tracks, metrics at /metrics)",
+        root.playlists.len(),
+        args.listen
     );
-    html.push_str(&format!(
-        "    <title>{}</title>\n",
-        escape_html(&playlist.name)
-    ));
-    html.push_str("    <style>\n");
-    html.push_str(get_common_styles());
-    html.push_str("        .metadata {\n");
-    html.push_str("            background-color: #f9f9f9;\n");
-    html.push_str("            padding: 15px;\n");
-    html.push_str("            border-radius: 5px;\n");
-    html.push_str("            margin-bottom: 30px;\n");
-    html.push_str("        }\n");
-    html.push_str("        .metadata p {\n");
-    html.push_str("            margin: 5px 0;\n");
-    html.push_str("        }\n");
-    html.push_str("        table {\n");
-    html.push_str("            width: 100%;\n");
-    html.push_str("            border-collapse: collapse;\n");
-    html.push_str("        }\n");
-    html.push_str("        th {\n");
-    html.push_str("            background-color: #1db954;\n");
-    html.push_str("            color: white;\n");
-    html.push_str("            padding: 12px;\n");
-    html.push_str("            text-align: left;\n");
-    html.push_str("        }\n");
-    html.push_str("        td {\n");
-    html.push_str("            padding: 12px;\n");
-    html.push_str("            border-bottom: 1px solid #ddd;\n");
-    html.push_str("        }\n");
-    html.push_str("        tr:hover {\n");
-    html.push_str("            background-color: #f5f5f5;\n");
-    html.push_str("        }\n");
-    html.push_str("        .track-number {\n");
-    html.push_str("            color: #999;\n");
-    html.push_str("            text-align: center;\n");
-    html.push_str("            width: 50px;\n");
-    html.push_str("        }\n");
-    html.push_str("    </style>\n");
-    html.push_str("</head>\n<body>\n");
-    html.push_str("    <div class=\"container\">\n");
-
-    // Back to index link
-    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
-
-    // Header
-    html.push_str(&format!(
-        "        <h1>{}</h1>\n",
-        escape_html(&playlist.name)
-    ));
+    if args.graphql {
+        println!("GraphQL endpoint: http://{}/api/graphql", args.listen);
+    }
+    let archive = server::Archive::new(
+        root.playlists,
+        static_dir.is_dir().then(|| static_dir.to_path_buf()),
+    );
+    server::serve(listener, archive, args.graphql, auth)?;
+    Ok(())
+}
 
-    // Metadata
-    html.push_str("        <div class=\"metadata\">\n");
-    html.push_str(&format!(
-        "            <p><strong>Last Modified:</strong> {}</p>\n",
-        escape_html(&playlist.last_modified_date)
-    ));
-    html.push_str(&format!(
-        "            <p><strong>Followers:</strong> {}</p>\n",
-        playlist.number_of_followers
-    ));
-    html.push_str(&format!(
-        "            <p><strong>Total Tracks:</strong> {}</p>\n",
-        playlist.items.len()
-    ));
-    html.push_str("        </div>\n");
-
-    // Tracks table
-    if !playlist.items.is_empty() {
-        html.push_str("        <h2>Tracks</h2>\n");
-        html.push_str("        <table>\n");
-        html.push_str("            <thead>\n");
-        html.push_str("                <tr>\n");
-        html.push_str("                    <th class=\"track-number\">#</th>\n");
-        html.push_str("                    <th>Track Name</th>\n");
-        html.push_str("                    <th>Artist</th>\n");
-        html.push_str("                    <th>Album</th>\n");
-        html.push_str("                    <th>Added Date</th>\n");
-        html.push_str("                </tr>\n");
-        html.push_str("            </thead>\n");
-        html.push_str("            <tbody>\n");
-
-        for (idx, item) in playlist.items.iter().enumerate() {
-            let track = &item.track;
-            html.push_str("                <tr>\n");
-            html.push_str(&format!(
-                "                    <td class=\"track-number\">{}</td>\n",
-                idx + 1
-            ));
-            html.push_str(&format!(
-                "                    <td><a href=\"{}\">{}</a></td>\n",
-                escape_html(&track.track_uri),
-                escape_html(&track.track_name)
-            ));
-            html.push_str(&format!(
-                "                    <td>{}</td>\n",
-                escape_html(&track.artist_name)
-            ));
-            html.push_str(&format!(
-                "                    <td>{}</td>\n",
-                escape_html(&track.album_name)
-            ));
-            html.push_str(&format!(
-                "                    <td>{}</td>\n",
-                escape_html(&item.added_date)
+/// Marks the conversion run as successful in the output directory's run
+/// record, read by `serve` for its `/metrics` endpoint
+fn record_success(
+    output_dir: &Path,
+    started: std::time::Instant,
+    playlists: usize,
+    files: usize,
+) -> io::Result<()> {
+    metrics::RunMetrics::record_success(
+        output_dir,
+        started.elapsed().as_secs_f64(),
+        playlists,
+        files,
+    )
+}
+
+/// The playlists the previous `--annotate-changes` or `--changelog` run
+/// into `output_dir` rendered, if there was one and its snapshot is readable
+fn previous_snapshot(output_dir: &Path) -> Option<Root> {
+    let path = output_dir.join(diff::SNAPSHOT_FILENAME);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!(
+                "Note: no earlier run to compare with; changes are tracked from the next run on"
+            );
+            return None;
+        }
+        Err(e) => {
+            console::warning(format!(
+                "Not marking changes: cannot read {}: {}",
+                path.display(),
+                e
             ));
-            html.push_str("                </tr>\n");
+            return None;
         }
+    };
+    match archive::parse_input(&text, ParseMode::Lenient) {
+        Ok((root, _)) => Some(root),
+        Err(e) => {
+            console::warning(format!("Not marking changes: {}: {}", path.display(), e));
+            None
+        }
+    }
+}
 
-        html.push_str("            </tbody>\n");
-        html.push_str("        </table>\n");
+/// With `--git-commit`, commits the output directory, counting the tracks
+/// added and removed since `previous`, the playlists the previous run
+/// rendered, when known
+fn git_commit(
+    args: &Args,
+    output_dir: &Path,
+    root: &Root,
+    previous: Option<&Root>,
+) -> Result<(), ConverterError> {
+    if !args.git_commit {
+        return Ok(());
     }
+    let message = git::commit_message(
+        previous.map(|previous| previous.playlists.as_slice()),
+        &root.playlists,
+    );
+    match git::commit(output_dir, &message, args.git_push).map_err(ConverterError::Failed)? {
+        Some(hash) => console::success(format!(
+            "Committed{}: {} {}",
+            if args.git_push { " and pushed" } else { "" },
+            hash,
+            message.lines().next().unwrap_or_default()
+        )),
+        None => println!("Note: nothing changed since the last commit; not committing"),
+    }
+    Ok(())
+}
+
+/// Whether the command line itself gives the option with long name `long`,
+/// or one of its aliases
+fn given_on_command_line(command: &clap::Command, given: &clap::ArgMatches, long: &str) -> bool {
+    command
+        .get_arguments()
+        .find(|arg| {
+            arg.get_long() == Some(long)
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&long))
+        })
+        .is_some_and(|arg| {
+            given.value_source(arg.get_id().as_str())
+                == Some(clap::parser::ValueSource::CommandLine)
+        })
+}
 
-    html.push_str("    </div>\n");
+/// Prints progress, to stderr when stdout carries the converted document
+fn progress(stdout_document: bool, message: &str) {
+    if stdout_document {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Lists, grouped by field, what lenient parsing filled in or ignored in an
+/// export that didn't match the expected format
+fn report_field_issues(stdout_document: bool, input: &str, issues: &[FieldIssue]) {
+    if issues.is_empty() {
+        return;
+    }
+    progress(
+        stdout_document,
+        &format!(
+            "Note: {} field(s) of {} don't match the expected export format (--strict fails on them instead):",
+            issues.len(),
+            input
+        ),
+    );
+    for line in export_check::summarize(issues) {
+        console::warning(line);
+    }
+}
 
-    // Floating back to top button
-    html.push_str("    <a href=\"#\" class=\"back-to-top\">↑ Top</a>\n");
+/// Orders names containing numbers by the numbers' values, so
+/// `Playlist2.json` comes before `Playlist10.json`
+fn natural_key(name: &str) -> Vec<Result<u64, String>> {
+    let mut key = Vec::new();
+    let mut rest = name;
+    while let Some(first) = rest.chars().next() {
+        let digits = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        key.push(match chunk.parse() {
+            Ok(number) if digits => Ok(number),
+            _ => Err(chunk.to_string()),
+        });
+        rest = tail;
+    }
+    key
+}
 
-    html.push_str("</body>\n</html>");
+/// The files to convert: each `--input` as given, or the files a glob
+/// pattern in it matches, in natural order. A file given twice is read once.
+fn expand_inputs(patterns: &[String]) -> Result<Vec<String>, String> {
+    let mut inputs: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let mut files = if pattern.contains(['*', '?', '[']) {
+            let mut matches: Vec<String> = glob::glob(pattern)
+                .map_err(|e| format!("invalid glob '{}': {}", pattern, e))?
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .map(|path| path.display().to_string())
+                .collect();
+            if matches.is_empty() {
+                return Err(format!("no files match '{}'", pattern));
+            }
+            matches.sort_by_cached_key(|file| natural_key(file));
+            matches
+        } else {
+            vec![pattern.clone()]
+        };
+        files.retain(|file| !inputs.contains(file));
+        inputs.extend(files);
+    }
+    Ok(inputs)
+}
 
-    html
+/// The file a scheduled run converts: the input itself, or the most recently
+/// modified `.json` or `.zip` file when the input is a directory
+fn scheduled_input(input: &Path) -> io::Result<Option<PathBuf>> {
+    if !input.is_dir() {
+        return Ok(input.is_file().then(|| input.to_path_buf()));
+    }
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for entry in fs::read_dir(input)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_none_or(|ext| ext != "json" && ext != "zip")
+            || !path.is_file()
+        {
+            continue;
+        }
+        let modified = fs::metadata(&path)?.modified()?;
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, path));
+        }
+    }
+    Ok(newest.map(|(_, path)| path))
 }
 
-fn generate_index_markdown(playlists: &[Playlist], filenames: &[String]) -> String {
-    let mut md = String::new();
+/// Runs the conversion now and then at every scheduled time, each time as a
+/// separate process with the same options, so a failed run is reported and
+/// the next one still happens. A run is skipped when the input file is the
+/// same, unmodified one the last successful run converted.
+fn run_scheduled(input: &str, schedule: &schedule::Schedule) -> io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let argv: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+    let mut converted: Option<(PathBuf, std::time::SystemTime)> = None;
+    let mut next = None;
+
+    loop {
+        if let Some(at) = next {
+            println!("Next run at {}", schedule::format_utc(at));
+            while metrics::unix_now() < at {
+                // Short naps so a suspended machine catches up when it wakes
+                std::thread::sleep(std::time::Duration::from_secs(
+                    (at - metrics::unix_now()).clamp(1, 60),
+                ));
+            }
+        }
+        next = schedule.next_after(metrics::unix_now());
+        let now = schedule::format_utc(metrics::unix_now());
 
-    md.push_str("# My Spotify Playlists\n\n");
+        // An input renamed or removed while it's being copied in only costs
+        // this run
+        if let Err(e) = scheduled_run(&exe, &argv, Path::new(input), &mut converted, &now) {
+            console::failure(format!(
+                "[{}] {}; retrying at the next scheduled time",
+                now, e
+            ));
+        }
 
-    let total_tracks: usize = playlists.iter().map(|p| p.items.len()).sum();
-    md.push_str(&format!("**Total Playlists:** {}\n\n", playlists.len()));
-    md.push_str(&format!("**Total Tracks:** {}\n\n", total_tracks));
+        if next.is_none() {
+            return Ok(());
+        }
+    }
+}
 
-    md.push_str("## Playlists\n\n");
+/// One scheduled conversion of the newest input, unless the last successful
+/// run already converted it as it is
+fn scheduled_run(
+    exe: &Path,
+    argv: &[std::ffi::OsString],
+    input: &Path,
+    converted: &mut Option<(PathBuf, std::time::SystemTime)>,
+    now: &str,
+) -> io::Result<()> {
+    let Some(file) = scheduled_input(input)? else {
+        println!("[{}] No input found at {}, skipping", now, input.display());
+        return Ok(());
+    };
+    let modified = fs::metadata(&file)?.modified()?;
+    if converted.as_ref() == Some(&(file.clone(), modified)) {
+        println!("[{}] {} is unchanged, skipping", now, file.display());
+        return Ok(());
+    }
 
-    for (playlist, filename) in playlists.iter().zip(filenames.iter()) {
-        md.push_str(&format!(
-            "- [**{}**]({}) - {} tracks, {} followers\n",
-            playlist.name,
-            filename,
-            playlist.items.len(),
-            playlist.number_of_followers
+    println!("[{}] Converting {}", now, file.display());
+    // Given last, the file overrides any earlier --input
+    let status = std::process::Command::new(exe)
+        .args(argv)
+        .arg("--input")
+        .arg(&file)
+        .env(schedule::SCHEDULED_RUN_ENV, "1")
+        .status()?;
+    if status.success() {
+        *converted = Some((file, modified));
+    } else {
+        console::failure(format!(
+            "Run failed ({}); retrying at the next scheduled time",
+            status
         ));
     }
-
-    md
+    Ok(())
 }
 
-fn generate_index_html(playlists: &[Playlist], filenames: &[String]) -> String {
-    let mut html = String::new();
+/// Reads one line from stdin after printing a prompt
+fn prompt(label: &str) -> io::Result<String> {
+    print!("{}: ", label);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
 
-    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
-    html.push_str("    <meta charset=\"UTF-8\">\n");
-    html.push_str(
-        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
-    );
-    html.push_str("    <title>My Spotify Playlists</title>\n");
-    html.push_str("    <style>\n");
-    html.push_str(get_common_styles());
-    html.push_str("        .stats {\n");
-    html.push_str("            display: flex;\n");
-    html.push_str("            gap: 30px;\n");
-    html.push_str("            margin-bottom: 30px;\n");
-    html.push_str("        }\n");
-    html.push_str("        .stat-card {\n");
-    html.push_str("            background-color: #f9f9f9;\n");
-    html.push_str("            padding: 20px;\n");
-    html.push_str("            border-radius: 8px;\n");
-    html.push_str("            flex: 1;\n");
-    html.push_str("        }\n");
-    html.push_str("        .stat-card h3 {\n");
-    html.push_str("            margin: 0 0 10px 0;\n");
-    html.push_str("            color: #666;\n");
-    html.push_str("            font-size: 14px;\n");
-    html.push_str("            text-transform: uppercase;\n");
-    html.push_str("        }\n");
-    html.push_str("        .stat-card p {\n");
-    html.push_str("            margin: 0;\n");
-    html.push_str("            font-size: 32px;\n");
-    html.push_str("            font-weight: bold;\n");
-    html.push_str("            color: #1db954;\n");
-    html.push_str("        }\n");
-    html.push_str("        .playlist-grid {\n");
-    html.push_str("            display: grid;\n");
-    html.push_str("            grid-template-columns: repeat(auto-fill, minmax(300px, 1fr));\n");
-    html.push_str("            gap: 20px;\n");
-    html.push_str("        }\n");
-    html.push_str("        .playlist-card {\n");
-    html.push_str("            background-color: #f9f9f9;\n");
-    html.push_str("            padding: 20px;\n");
-    html.push_str("            border-radius: 8px;\n");
-    html.push_str("            transition: transform 0.2s, box-shadow 0.2s;\n");
-    html.push_str("        }\n");
-    html.push_str("        .playlist-card:hover {\n");
-    html.push_str("            transform: translateY(-2px);\n");
-    html.push_str("            box-shadow: 0 4px 12px rgba(0,0,0,0.15);\n");
-    html.push_str("        }\n");
-    html.push_str("        .playlist-card h3 {\n");
-    html.push_str("            margin: 0 0 10px 0;\n");
-    html.push_str("            color: #333;\n");
-    html.push_str("        }\n");
-    html.push_str("        .playlist-card h3 a {\n");
-    html.push_str("            color: #333;\n");
-    html.push_str("        }\n");
-    html.push_str("        .playlist-meta {\n");
-    html.push_str("            color: #666;\n");
-    html.push_str("            font-size: 14px;\n");
-    html.push_str("        }\n");
-    html.push_str("    </style>\n");
-    html.push_str("</head>\n<body>\n");
-    html.push_str("    <div class=\"container\">\n");
-
-    html.push_str("        <h1>My Spotify Playlists</h1>\n");
-
-    // Stats
-    let total_tracks: usize = playlists.iter().map(|p| p.items.len()).sum();
-    html.push_str("        <div class=\"stats\">\n");
-    html.push_str("            <div class=\"stat-card\">\n");
-    html.push_str("                <h3>Total Playlists</h3>\n");
-    html.push_str(&format!("                <p>{}</p>\n", playlists.len()));
-    html.push_str("            </div>\n");
-    html.push_str("            <div class=\"stat-card\">\n");
-    html.push_str("                <h3>Total Tracks</h3>\n");
-    html.push_str(&format!("                <p>{}</p>\n", total_tracks));
-    html.push_str("            </div>\n");
-    html.push_str("        </div>\n");
-
-    // Playlist grid
-    html.push_str("        <h2>Playlists</h2>\n");
-    html.push_str("        <div class=\"playlist-grid\">\n");
-
-    for (playlist, filename) in playlists.iter().zip(filenames.iter()) {
-        html.push_str("            <div class=\"playlist-card\">\n");
-        html.push_str(&format!(
-            "                <h3><a href=\"{}\">{}</a></h3>\n",
-            escape_html(filename),
-            escape_html(&playlist.name)
-        ));
-        html.push_str("                <div class=\"playlist-meta\">\n");
-        html.push_str(&format!(
-            "                    {} tracks<br>\n",
-            playlist.items.len()
-        ));
-        html.push_str(&format!(
-            "                    {} followers\n",
-            playlist.number_of_followers
-        ));
-        html.push_str("                </div>\n");
-        html.push_str("            </div>\n");
-    }
-
-    html.push_str("        </div>\n");
-    html.push_str("    </div>\n");
-    html.push_str("</body>\n</html>");
-
-    html
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+fn auth(action: &AuthAction, offline: bool, profile: Option<&str>) -> Result<(), ConverterError> {
+    match action {
+        AuthAction::Login { client_id } => {
+            require_network(offline, "auth login")?;
+            let client_id = match client_id {
+                Some(client_id) => client_id.clone(),
+                None => prompt("Client ID")?,
+            };
+            // Taken from the environment if set, so it needn't be typed or
+            // appear in shell history
+            let client_secret = match std::env::var("SPOTIFY_CLIENT_SECRET") {
+                Ok(secret) if !secret.is_empty() => secret,
+                _ => prompt("Client secret")?,
+            };
+            if client_id.is_empty() || client_secret.is_empty() {
+                return Err(ConverterError::usage(
+                    "both a client ID and a client secret are needed",
+                ));
+            }
+            let credentials = spotify::Credentials {
+                client_id,
+                client_secret,
+            };
+            spotify::Client::connect(&credentials).map_err(ConverterError::Failed)?;
+            let path = credentials.store(profile).map_err(ConverterError::Failed)?;
+            console::success(format!("Saved: credentials in {}", path.display()));
+        }
+        AuthAction::Status => {
+            let (credentials, source) = match spotify::Credentials::from_env() {
+                Ok(credentials) => (credentials, "environment".to_string()),
+                Err(_) => match spotify::Credentials::stored(profile) {
+                    Some(credentials) => (
+                        credentials,
+                        config::credentials_path(profile)
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_default(),
+                    ),
+                    None => {
+                        println!(
+                            "Not logged in: run `auth login` or set SPOTIFY_CLIENT_ID and SPOTIFY_CLIENT_SECRET"
+                        );
+                        return Ok(());
+                    }
+                },
+            };
+            println!("Client ID: {} (from {})", credentials.client_id, source);
+            if offline {
+                return Ok(());
+            }
+            spotify::Client::connect(&credentials).map_err(ConverterError::Failed)?;
+            console::success("Checked: Spotify accepts these credentials");
+        }
+        AuthAction::Logout => match config::credentials_path(profile) {
+            Some(path) if path.is_file() => {
+                fs::remove_file(&path)?;
+                console::success(format!("Removed: {}", path.display()));
+            }
+            _ => println!("No saved credentials to remove"),
+        },
+    }
+    Ok(())
+}
 
-    // Validate format
+fn write_digest(
+    args: &DigestArgs,
+    offline: bool,
+    localizer: &l10n::Localizer,
+) -> Result<(), ConverterError> {
     let format = args.format.to_lowercase();
-    if format != "markdown" && format != "html" {
-        eprintln!("Error: format must be either 'markdown' or 'html'");
-        std::process::exit(1);
+    if format != "markdown" && format != "html" && format != "email" {
+        return Err(ConverterError::usage(
+            "digest format must be one of 'markdown', 'html' or 'email'",
+        ));
     }
+    let delivery = email_delivery(&args.email, &format, offline)?;
+    let (year, month) = dates::parse_month(&args.month).map_err(ConverterError::Usage)?;
 
-    let extension = if format == "html" { "html" } else { "md" };
-
-    // Read and parse JSON
     println!("Reading JSON file: {}", args.input);
-    let json_content = fs::read_to_string(&args.input)?;
-    let root: Root = serde_json::from_str(&json_content)?;
+    let (root, _) = read_export(&args.input, ParseMode::Lenient)?;
+    let sections = digest::collect_additions(&root.playlists, year, month);
 
-    // Create output directory
-    fs::create_dir_all(&args.output)?;
-    println!("Output directory: {}", args.output);
-    println!("Output format: {}", format);
+    let output_dir = output::prepare_output_dir(&args.output)
+        .map_err(|e| ConverterError::output(&args.output, e))?;
 
-    let mut filenames = Vec::new();
+    if format == "email" {
+        let filename = format!("digest-{:04}-{:02}.eml", year, month);
+        let message = email::digest_email(&sections, &root.playlists, year, month, localizer);
+        deliver_email(
+            &message,
+            &output_dir.join(&filename),
+            &args.email.send_to,
+            &delivery,
+        )?;
+        console::success(format!("Created: {}", filename));
+        return Ok(());
+    }
 
-    // Process each playlist
-    println!("\nProcessing {} playlists...", root.playlists.len());
-    for playlist in &root.playlists {
-        let filename = format!("{}.{}", sanitize_filename(&playlist.name), extension);
-        let filepath = Path::new(&args.output).join(&filename);
+    let (filename, content) = if format == "html" {
+        (
+            format!("digest-{:04}-{:02}.html", year, month),
+            digest::generate_digest_html(&sections, &root.playlists, year, month, localizer),
+        )
+    } else {
+        (
+            format!("digest-{:04}-{:02}.md", year, month),
+            digest::generate_digest_markdown(&sections, &root.playlists, year, month, localizer),
+        )
+    };
+    let filepath = output_dir.join(&filename);
+    output::write_file(&filepath, content)?;
 
-        let content = if format == "html" {
-            generate_html(playlist)
-        } else {
-            generate_markdown(playlist)
-        };
+    let total: usize = sections.iter().map(|section| section.items.len()).sum();
+    console::success(format!(
+        "Created: {} ({} tracks from {} playlists)",
+        filename,
+        total,
+        sections.len()
+    ));
+    Ok(())
+}
+
+fn check_links(args: &CheckLinksArgs, offline: bool) -> Result<(), ConverterError> {
+    let dir = Path::new(&args.dir);
+    if !dir.is_dir() {
+        return Err(ConverterError::usage(format!(
+            "{} is not a directory",
+            args.dir
+        )));
+    }
+    if args.rate_limit <= 0.0 {
+        return Err(ConverterError::usage("--rate-limit must be greater than 0"));
+    }
+    if args.external {
+        require_network(offline, "--external")?;
+    }
 
-        fs::write(&filepath, content)?;
-        filenames.push(filename.clone());
+    println!("Checking links in {}", dir.display());
+    let (broken, external) = links::check_internal_links(dir)?;
+    for link in &broken {
+        console::failure(format!(
+            "{}: {} ({})",
+            link.file.strip_prefix(dir).unwrap_or(&link.file).display(),
+            link.target,
+            link.reason
+        ));
+    }
+    let mut failures = broken.len();
 
+    if args.external {
         println!(
-            "  ✓ Created: {} ({} tracks)",
-            filename,
-            playlist.items.len()
+            "Checking {} external links ({} per second)...",
+            external.len(),
+            args.rate_limit
         );
+        let external_failures = links::check_external_links(&external, args.rate_limit);
+        for (link, reason) in &external_failures {
+            console::failure(format!("{} ({})", link, reason));
+        }
+        failures += external_failures.len();
     }
 
-    // Generate index file
-    let index_filename = format!("index.{}", extension);
-    let index_filepath = Path::new(&args.output).join(&index_filename);
+    if failures > 0 {
+        return Err(ConverterError::failed(format!(
+            "found {} broken links",
+            failures
+        )));
+    }
 
-    let index_content = if format == "html" {
-        generate_index_html(&root.playlists, &filenames)
-    } else {
-        generate_index_markdown(&root.playlists, &filenames)
+    console::success("Checked: no broken links found");
+    Ok(())
+}
+
+/// File format of a report such as changes or duplicates
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+/// Files of one playlist as rendered by a worker thread, before HTML assets
+/// are externalized
+struct PlaylistPages {
+    basename: String,
+    /// Leads from the page up to the output directory
+    root_path: String,
+    preview: Option<TrackPreview>,
+    content: String,
+    full_content: Option<String>,
+    embed_content: Option<String>,
+}
+
+fn parse_report_formats(value: &str) -> Result<Vec<ReportFormat>, String> {
+    let mut formats = Vec::new();
+    for name in value.split(',').map(str::trim) {
+        let report_format = match name.to_lowercase().as_str() {
+            "md" | "markdown" => ReportFormat::Markdown,
+            "html" => ReportFormat::Html,
+            "json" => ReportFormat::Json,
+            _ => {
+                return Err(format!(
+                    "report formats must be a comma-separated list of 'md', 'html' and 'json' (got '{}')",
+                    name
+                ));
+            }
+        };
+        if !formats.contains(&report_format) {
+            formats.push(report_format);
+        }
+    }
+    Ok(formats)
+}
+
+/// Moves inline styles and scripts of a complete HTML page into external
+/// assets when generating CSP-compatible output. `root_path` leads from the
+/// page up to the output directory, empty for pages directly in it.
+fn finish_html(html: String, assets: &mut Option<csp::AssetStore>, root_path: &str) -> String {
+    match assets {
+        Some(store) => store.externalize(&html, root_path),
+        None => html,
+    }
+}
+
+/// Writes a report as `<name>.<extension>` once per format, taking the
+/// content for each from `render`, and returns the filenames written
+fn write_report(
+    output_dir: &Path,
+    name: &str,
+    formats: &[ReportFormat],
+    assets: &mut Option<csp::AssetStore>,
+    written: &mut Vec<PathBuf>,
+    mut render: impl FnMut(ReportFormat) -> String,
+) -> Result<String, ConverterError> {
+    let mut filenames = Vec::new();
+    for &report_format in formats {
+        let filename = format!("{}.{}", name, report_format.extension());
+        let mut content = render(report_format);
+        if report_format == ReportFormat::Html {
+            content = finish_html(content, assets, "");
+        }
+        let filepath = output_dir.join(&filename);
+        output::write_file(&filepath, content)?;
+        written.push(filepath);
+        filenames.push(filename);
+    }
+    Ok(filenames.join(", "))
+}
+
+/// Runs the `--postprocess` command, if any, over each generated file
+fn postprocess(command: Option<&str>, files: &[PathBuf]) -> Result<(), ConverterError> {
+    let Some(command) = command else {
+        return Ok(());
     };
+    for file in files {
+        hooks::run_postprocess(command, file).map_err(ConverterError::Failed)?;
+    }
+    console::success(format!("Postprocessed: {} files", files.len()));
+    Ok(())
+}
 
-    fs::write(&index_filepath, index_content)?;
-    println!("\n  ✓ Created: {}", index_filename);
+/// Applies `--file-mode` and `--mtime-from-modified` to the generated files,
+/// after everything, `--postprocess` included, is done writing them
+fn set_file_attributes(
+    files: &[PathBuf],
+    mode: Option<u32>,
+    modified: impl Fn(&Path) -> Option<dates::Date>,
+) -> Result<(), ConverterError> {
+    for file in files {
+        if let Some(mode) = mode {
+            output::set_file_mode(file, mode).map_err(|e| ConverterError::output(file, e))?;
+        }
+        if let Some(date) = modified(file) {
+            output::set_modified(file, date).map_err(|e| ConverterError::output(file, e))?;
+        }
+    }
+    Ok(())
+}
 
-    println!(
-        "\nDone! Generated {} {} files plus index.",
-        root.playlists.len(),
-        format
-    );
+/// Counts the files written into the summary, prints it and, with
+/// `--report-format json`, also writes it to `summary_file`. Its time is
+/// the run's own, so `--mtime-from-modified` leaves it alone.
+fn report_summary(
+    summary: &mut summary::RunSummary,
+    files: &[PathBuf],
+    summary_file: Option<PathBuf>,
+    file_mode: Option<u32>,
+) -> Result<(), ConverterError> {
+    let sizes: Vec<u64> = files
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .collect();
+    summary.files = sizes.len();
+    summary.output_bytes = sizes.iter().sum();
+    print!("\n{}", summary.to_table());
+    if let Some(path) = summary_file {
+        output::write_file(&path, json_report(summary.to_json()))?;
+        set_file_attributes(std::slice::from_ref(&path), file_mode, |_| None)?;
+        console::success(format!("Created: {}", summary::SUMMARY_FILENAME));
+    }
+    Ok(())
+}
+
+fn json_report(value: Value) -> String {
+    let mut json = serde_json::to_string_pretty(&value).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            console::error(&e);
+            if let Some(hint) = e.hint() {
+                eprintln!("Note: {}", hint);
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run() -> Result<(), ConverterError> {
+    let mut argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let given = Args::command().get_matches_from(argv.clone());
+    let mut args = Args::from_arg_matches(&given).unwrap_or_else(|e| e.exit());
+    if args.no_color {
+        console::disable_color();
+    }
+
+    // A profile's options go in front of the command line's. Options the
+    // command line gives replace the profile's, lists included.
+    if let Some(profile) = &args.profile {
+        let profile = config::parse_profile_name(profile).map_err(ConverterError::Usage)?;
+        let profile_args = config::profile_args(&profile).map_err(ConverterError::Usage)?;
+        let command = Args::command();
+        let kept: Vec<String> = profile_args
+            .into_iter()
+            .filter(|(long, _)| !given_on_command_line(&command, &given, long))
+            .flat_map(|(_, option_args)| option_args)
+            .collect();
+        argv.splice(1..1, kept.into_iter().map(Into::into));
+        args = Args::parse_from(argv);
+        if args.no_color {
+            console::disable_color();
+        }
+    }
+
+    let locale = args
+        .locale
+        .as_deref()
+        .map(collation::parse_locale)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+
+    match &args.command {
+        Some(Command::CheckLinks(check_args)) => return check_links(check_args, args.offline),
+        Some(Command::Digest(digest_args)) => {
+            return write_digest(digest_args, args.offline, &l10n::Localizer::new(locale));
+        }
+        Some(Command::Serve(serve_args)) => return serve(serve_args),
+        Some(Command::Schema { shape }) => {
+            let shape = shape
+                .as_deref()
+                .map(archive::parse_schema_shape)
+                .transpose()
+                .map_err(ConverterError::Usage)?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&archive::input_schema(shape))
+                    .map_err(|e| ConverterError::failed(e.to_string()))?
+            );
+            return Ok(());
+        }
+        Some(Command::Auth { action }) => {
+            return auth(action, args.offline, args.profile.as_deref());
+        }
+        None => {}
+    }
+    if args.input.is_empty() {
+        return Err(ConverterError::usage(
+            "--input is required, on the command line or in the profile",
+        ));
+    }
+    let run_started = std::time::Instant::now();
+
+    if let Some(value) = &args.schedule
+        && std::env::var_os(schedule::SCHEDULED_RUN_ENV).is_none()
+    {
+        let [input] = args.input.as_slice() else {
+            return Err(ConverterError::usage(
+                "--schedule takes a single --input, a file or a directory",
+            ));
+        };
+        let schedule = schedule::parse_schedule(value).map_err(ConverterError::Usage)?;
+        if schedule.next_after(metrics::unix_now()).is_none() {
+            return Err(ConverterError::usage(format!(
+                "schedule '{}' never matches",
+                value
+            )));
+        }
+        run_scheduled(input, &schedule)?;
+        return Ok(());
+    }
+
+    if args.git_push && !args.git_commit {
+        return Err(ConverterError::usage("--git-push needs --git-commit"));
+    }
+    if args.git_push {
+        require_network(args.offline, "--git-push")?;
+    }
+    if args.git_commit && (args.stdout || args.dated) {
+        return Err(ConverterError::usage(
+            "--git-commit cannot be combined with --stdout, which writes no files, or --dated, which keeps history in directories instead",
+        ));
+    }
+    if args.dated && args.stdout {
+        return Err(ConverterError::usage(
+            "--dated cannot be combined with --stdout, which writes no files",
+        ));
+    }
+    let dated = args.dated.then(|| {
+        let base = PathBuf::from(&args.output);
+        let name = dates::Date::today().to_string();
+        args.output = base.join(&name).display().to_string();
+        (base, name)
+    });
+
+    if args.swap {
+        if args.stdout {
+            return Err(ConverterError::usage(
+                "--swap cannot be combined with --stdout, which writes no files",
+            ));
+        }
+        let workspace = output::Workspace::create(&args.output)
+            .map_err(|e| ConverterError::output(&args.output, e))?;
+        convert(args, locale, run_started, Some(&workspace))?;
+        let destination = workspace.destination().to_path_buf();
+        workspace
+            .swap()
+            .map_err(|e| ConverterError::output(&destination, e))?;
+        console::success(format!(
+            "Swapped: this run's output is now in {}",
+            destination.display()
+        ));
+    } else {
+        convert(args, locale, run_started, None)?;
+    }
+
+    // Only a run that got this far moves `latest` on to its directory
+    if let Some((base, name)) = dated {
+        output::update_latest_link(&base, &name)
+            .map_err(|e| ConverterError::output(base.join(output::LATEST_LINK), e))?;
+        console::success(format!(
+            "Linked: {} now points at {}",
+            base.join(output::LATEST_LINK).display(),
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Converts the inputs into the output directory, or with `--swap` into the
+/// workspace that replaces it
+fn convert(
+    mut args: Args,
+    locale: Option<icu_locale_core::Locale>,
+    run_started: std::time::Instant,
+    workspace: Option<&output::Workspace>,
+) -> Result<(), ConverterError> {
+    // A scheduled run is given the file to convert as its last --input
+    if std::env::var_os(schedule::SCHEDULED_RUN_ENV).is_some() && args.input.len() > 1 {
+        args.input.drain(..args.input.len() - 1);
+    }
+    let inputs = expand_inputs(&args.input).map_err(ConverterError::Usage)?;
+
+    // Validate format
+    let csv_options = csv::CsvOptions {
+        delimiter: csv::parse_delimiter(&args.delimiter).map_err(ConverterError::Usage)?,
+        quote_style: csv::parse_quote_style(&args.quote_style).map_err(ConverterError::Usage)?,
+    };
+    let mut renderers = builtin_renderers(csv_options);
+    if args.fragment {
+        renderers.insert("html".to_string(), Box::new(HtmlFragmentRenderer));
+    }
+    let format = args.format.to_lowercase();
+    let mut format_plugin = None;
+    if !renderers.contains_key(&format) && format != "email" && format != "archive" {
+        let Some(path) = plugin::find_plugin(Path::new(&args.plugin_dir), &format) else {
+            let names: Vec<String> = renderers
+                .keys()
+                .map(String::as_str)
+                .chain(["email"])
+                .map(|name| format!("'{}'", name))
+                .collect();
+            return Err(ConverterError::usage(format!(
+                "format must be one of {} or 'archive', or a plugin in {}/",
+                names.join(", "),
+                args.plugin_dir
+            )));
+        };
+        format_plugin = Some(plugin::Plugin::load(&path).map_err(ConverterError::Failed)?);
+    }
+    let delivery = email_delivery(&args.email, &format, args.offline)?;
+
+    if args.flat && format != "csv" {
+        return Err(ConverterError::usage(
+            "--flat is only supported with the csv format",
+        ));
+    }
+
+    if args.jobs == Some(0) {
+        return Err(ConverterError::usage("--jobs must be at least 1"));
+    }
+    let max_memory = args
+        .max_memory
+        .as_deref()
+        .map(parallel::parse_memory_size)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+
+    let file_mode = args
+        .file_mode
+        .as_deref()
+        .map(output::parse_file_mode)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+
+    if let Some(command) = &args.postprocess {
+        if format == "email" {
+            return Err(ConverterError::usage(
+                "--postprocess cannot be combined with the email format",
+            ));
+        }
+        if !command.contains(hooks::FILE_PLACEHOLDER) {
+            return Err(ConverterError::usage(format!(
+                "--postprocess must contain {} where the file path goes",
+                hooks::FILE_PLACEHOLDER
+            )));
+        }
+    }
+
+    if args.fragment && format != "html" {
+        return Err(ConverterError::usage(
+            "--fragment is only supported with the html format",
+        ));
+    }
+
+    if args.csp && format != "html" {
+        return Err(ConverterError::usage(
+            "--csp is only supported with the html format",
+        ));
+    }
+
+    if args.preview_rows.is_some() && (args.fragment || (format != "markdown" && format != "html"))
+    {
+        return Err(ConverterError::usage(
+            "--preview-rows is only supported with full markdown and html pages",
+        ));
+    }
+
+    if args.embed && format != "html" {
+        return Err(ConverterError::usage(
+            "--embed is only supported with the html format",
+        ));
+    }
+
+    let mut index_grouping = args
+        .group_index_by
+        .as_deref()
+        .map(parse_index_grouping)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+    let custom_kinds: Vec<kinds::KindRule> = args
+        .playlist_kind
+        .iter()
+        .map(|value| kinds::parse_kind_rule(value))
+        .collect::<Result<_, _>>()
+        .map_err(ConverterError::Usage)?;
+    match &mut index_grouping {
+        Some(IndexGrouping::Kind(rules)) => *rules = kinds::rules_with_defaults(custom_kinds),
+        _ if !custom_kinds.is_empty() => {
+            return Err(ConverterError::usage(
+                "--playlist-kind is only supported with --group-index-by kind",
+            ));
+        }
+        _ => {}
+    }
+    if index_grouping.is_some() && matches!(format.as_str(), "csv" | "m3u" | "xspf") {
+        return Err(ConverterError::usage(
+            "--group-index-by is only supported with the markdown and html formats",
+        ));
+    }
+    let sort_index_by_name = match args.sort_index.to_lowercase().as_str() {
+        "export" => false,
+        "name" => true,
+        _ => {
+            return Err(ConverterError::usage(
+                "--sort-index must be either 'export' or 'name'",
+            ));
+        }
+    };
+    if sort_index_by_name && matches!(format.as_str(), "csv" | "m3u" | "xspf") {
+        return Err(ConverterError::usage(
+            "--sort-index is only supported with the markdown and html formats",
+        ));
+    }
+    if args.numbered && sort_index_by_name {
+        return Err(ConverterError::usage(
+            "--numbered cannot be combined with --sort-index name, which reorders the index",
+        ));
+    }
+
+    if args.numbered && index_grouping.is_some() {
+        return Err(ConverterError::usage(
+            "--numbered cannot be combined with --group-index-by, which reorders the index",
+        ));
+    }
+    if args.numbered && args.filename_template.is_some() {
+        return Err(ConverterError::usage(
+            "--numbered cannot be combined with --filename-template; use {{index}} in the template instead",
+        ));
+    }
+
+    if args.tags && matches!(format.as_str(), "csv" | "m3u" | "xspf") {
+        return Err(ConverterError::usage(
+            "--tags is only supported with the markdown and html formats",
+        ));
+    }
+
+    let track_anchors = parse_track_anchors(&args.track_anchors).map_err(ConverterError::Usage)?;
+
+    let markdown_flavor =
+        parse_markdown_flavor(&args.markdown_flavor).map_err(ConverterError::Usage)?;
+    let front_matter =
+        front_matter::parse_front_matter(&args.front_matter).map_err(ConverterError::Usage)?;
+    if front_matter != front_matter::FrontMatter::None && format != "markdown" {
+        return Err(ConverterError::usage(
+            "--front-matter is only supported with the markdown format",
+        ));
+    }
+
+    if args.reference_links && format != "markdown" {
+        return Err(ConverterError::usage(
+            "--reference-links is only supported with the markdown format",
+        ));
+    }
+    if markdown_flavor != MarkdownFlavor::Gfm && format != "markdown" {
+        return Err(ConverterError::usage(
+            "--markdown-flavor is only supported with the markdown format",
+        ));
+    }
+
+    if args.stdout {
+        if !matches!(format.as_str(), "markdown" | "csv" | "archive") {
+            return Err(ConverterError::usage(
+                "--stdout is only supported with the markdown, csv and archive formats",
+            ));
+        }
+        if args.preview_rows.is_some()
+            || args.reference_links
+            || front_matter != front_matter::FrontMatter::None
+            || args.postprocess.is_some()
+        {
+            return Err(ConverterError::usage(
+                "--stdout cannot be combined with --preview-rows, --reference-links, --front-matter or --postprocess",
+            ));
+        }
+    }
+
+    let uri_style = parse_uri_style(&args.uri_style).map_err(ConverterError::Usage)?;
+
+    let mut render_options = RenderOptions {
+        track_anchors,
+        uri_style,
+        sticky_header: args.sticky_header,
+        auto_hide_back_to_top: args.auto_hide_back_to_top,
+        copy_buttons: args.copy_buttons,
+        index_filters: args.index_filters,
+        index_top_artists: args.index_top_artists,
+        keyboard_shortcuts: args.keyboard_shortcuts,
+        lazy_rows: args.lazy_rows.filter(|&n| n > 0),
+        index_grouping,
+        tags: args.tags,
+        markdown_flavor,
+        reference_links: args.reference_links,
+        front_matter,
+        sort_index_by_name,
+        collation: collation::Collation::new(locale.clone()),
+        localizer: l10n::Localizer::new(locale),
+        track_preview: None,
+        track_changes: None,
+        root_path: String::new(),
+        preview_audio: Arc::default(),
+        section_id: None,
+    };
+
+    if args.no_js {
+        let disabled = render_options.disable_scripts();
+        if !disabled.is_empty() {
+            progress(
+                args.stdout,
+                &format!(
+                    "Note: --no-js ignores {} (they need JavaScript)",
+                    disabled.join(", ")
+                ),
+            );
+        }
+    }
+
+    let market = args
+        .market
+        .as_deref()
+        .map(spotify::parse_market)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+    if market.is_some() && !args.check_availability {
+        return Err(ConverterError::usage(
+            "--market is only supported with --check-availability",
+        ));
+    }
+
+    if args.title_case && !args.normalize {
+        return Err(ConverterError::usage(
+            "--title-case is only supported with --normalize",
+        ));
+    }
+
+    if args.scrub_uris && args.check_availability {
+        return Err(ConverterError::usage(
+            "--scrub-uris cannot be combined with --check-availability",
+        ));
+    }
+
+    if args.relink && !args.check_availability {
+        return Err(ConverterError::usage(
+            "--relink is only supported with --check-availability",
+        ));
+    }
+
+    if args.check_availability {
+        require_network(args.offline, "--check-availability")?;
+    }
+
+    if args.preview_audio {
+        if format != "html" || args.fragment {
+            return Err(ConverterError::usage(
+                "--preview-audio is only supported with the html format",
+            ));
+        }
+        if args.scrub_uris {
+            return Err(ConverterError::usage(
+                "--scrub-uris cannot be combined with --preview-audio",
+            ));
+        }
+        require_network(args.offline, "--preview-audio")?;
+    }
+
+    let api_client = (args.check_availability || args.preview_audio)
+        .then(|| {
+            spotify::Credentials::load(args.profile.as_deref())
+                .and_then(|credentials| spotify::Client::connect(&credentials))
+        })
+        .transpose()
+        .map_err(ConverterError::Failed)?;
+
+    let stale_after = args
+        .stale_after
+        .as_deref()
+        .map(dates::parse_span)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+
+    let export_date = args
+        .export_date
+        .as_deref()
+        .map(|value| {
+            dates::Date::parse(value).ok_or_else(|| {
+                ConverterError::usage("--export-date must be a date like 2024-06-01")
+            })
+        })
+        .transpose()?;
+    if export_date.is_some() && !args.tenure {
+        return Err(ConverterError::usage(
+            "--export-date is only supported with --tenure",
+        ));
+    }
+
+    let report_formats = match args.report_format.as_deref() {
+        Some(value) => parse_report_formats(value).map_err(ConverterError::Usage)?,
+        None if format == "html" => vec![ReportFormat::Html],
+        None => vec![ReportFormat::Markdown],
+    };
+
+    let extension = renderers
+        .get(&format)
+        .map_or("md", |renderer| renderer.extension());
+
+    // Read and parse JSON, merging the playlists of every input
+    let parse_mode = if args.strict {
+        ParseMode::Strict
+    } else {
+        ParseMode::Lenient
+    };
+    let mut root = Root::default();
+    let mut field_issues = 0;
+    for input in &inputs {
+        progress(args.stdout, &format!("Reading JSON file: {}", input));
+        let mut json_content = export_zip::read_input(Path::new(input))?;
+        if let Some(command) = &args.pre_filter {
+            progress(args.stdout, &format!("Running pre-filter: {}", command));
+            json_content =
+                hooks::run_pre_filter(command, &json_content).map_err(ConverterError::Failed)?;
+        }
+        let (part, issues) = parse_export_with(&json_content, parse_mode)
+            .map_err(|e| ConverterError::parse(input.as_str(), e))?;
+        report_field_issues(args.stdout, input, &issues);
+        field_issues += issues.len();
+        root.playlists.extend(part.playlists);
+    }
+    if inputs.len() > 1 {
+        progress(
+            args.stdout,
+            &format!(
+                "Merged {} playlists from {} files",
+                root.playlists.len(),
+                inputs.len()
+            ),
+        );
+    }
+    if args.scrub_uris {
+        scrub_uris(&mut root);
+    }
+    if args.anonymize_collaborators {
+        anonymize_collaborators(&mut root);
+    }
+    if args.normalize {
+        normalize::normalize(&mut root, args.title_case);
+    }
+    let mut summary = summary::RunSummary::new(run_started);
+    if !args.playlist.is_empty() {
+        let mut selected = vec![false; root.playlists.len()];
+        for query in &args.playlist {
+            let (found, kind) = select::find_playlists(&root.playlists, query);
+            let Some(kind) = kind else {
+                let suggestions = select::suggest_playlists(&root.playlists, query);
+                return Err(ConverterError::usage(format!(
+                    "--playlist \"{}\" matches no playlist; closest: {}",
+                    query,
+                    suggestions
+                        .iter()
+                        .map(|name| format!("\"{}\"", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            };
+            if kind != select::NameMatch::Exact || found.len() > 1 {
+                progress(
+                    args.stdout,
+                    &format!(
+                        "--playlist \"{}\" matched {} playlist(s):",
+                        query,
+                        found.len()
+                    ),
+                );
+                for &idx in &found {
+                    progress(args.stdout, &format!("  {}", root.playlists[idx].name));
+                }
+            }
+            for idx in found {
+                selected[idx] = true;
+            }
+        }
+        summary.skipped += root
+            .playlists
+            .iter()
+            .zip(&selected)
+            .filter(|(_, selected)| !**selected)
+            .map(|(playlist, _)| playlist.items.len())
+            .sum::<usize>();
+        let mut selected = selected.into_iter();
+        root.playlists.retain(|_| selected.next().unwrap_or(false));
+    }
+    if args.exclude_generated {
+        let before = root.playlists.len();
+        summary.skipped += root
+            .playlists
+            .iter()
+            .filter(|playlist| source::is_generated(playlist))
+            .map(|playlist| playlist.items.len())
+            .sum::<usize>();
+        root.playlists
+            .retain(|playlist| !source::is_generated(playlist));
+        progress(
+            args.stdout,
+            &format!(
+                "Excluded {} Spotify-generated playlists",
+                before - root.playlists.len()
+            ),
+        );
+    }
+
+    let data_warnings = if args.validate_on_convert {
+        let warnings = lint::check_export(&root.playlists, dates::Date::today());
+        for warning in &warnings {
+            console::warning(lint::describe(warning, &root.playlists));
+        }
+        progress(
+            args.stdout,
+            &format!("Validated input: {} warning(s)", warnings.len()),
+        );
+        warnings
+    } else {
+        Vec::new()
+    };
+    summary.playlists = root.playlists.len();
+    summary.tracks = root.playlists.iter().map(|p| p.items.len()).sum();
+    summary.warnings = data_warnings.len() + field_issues;
+    summary.end_phase("Reading input");
+
+    // Changes are tracked against the previous run's output
+    let tracks_changes = args.annotate_changes || args.changelog;
+    let writes_pages = !(args.stdout
+        || args.flat
+        || matches!(format.as_str(), "archive" | "email")
+        || format_plugin.is_some());
+    if tracks_changes && !writes_pages {
+        return Err(ConverterError::usage(format!(
+            "{} compares with the previous run's playlist pages, which --stdout, --flat, the archive and email formats and format plugins don't write",
+            if args.annotate_changes {
+                "--annotate-changes"
+            } else {
+                "--changelog"
+            }
+        )));
+    }
+
+    // One document for a pipeline; nothing is written to the output directory
+    if args.stdout {
+        let document = match format.as_str() {
+            "csv" => csv::generate_flat_csv(&root.playlists, &csv_options),
+            "archive" => archive::generate_archive(&root),
+            _ => {
+                let mut ids = slug::UniqueIds::new();
+                let sections: Vec<String> = root
+                    .playlists
+                    .iter()
+                    .map(|playlist| {
+                        let options = RenderOptions {
+                            section_id: Some(ids.id_for("playlist", &playlist.name)),
+                            ..render_options.clone()
+                        };
+                        generate_markdown(playlist, &options)
+                    })
+                    .collect();
+                sections.join("\n")
+            }
+        };
+        io::stdout().write_all(document.as_bytes())?;
+        return Ok(());
+    }
+
+    // Create output directory
+    let output_dir = match workspace {
+        Some(workspace) => workspace.dir().to_path_buf(),
+        None => output::prepare_output_dir(&args.output)
+            .map_err(|e| ConverterError::output(&args.output, e))?,
+    };
+    output::check_writable(&output_dir).map_err(|e| ConverterError::output(&output_dir, e))?;
+    // Every file goes through this, so no playlist name can place one
+    // outside the output directory
+    let output_file = |filename: &str| {
+        output::output_file(&output_dir, filename).map_err(|e| ConverterError::output(filename, e))
+    };
+    metrics::RunMetrics::record_start(&output_dir)?;
+    let modified_date = |playlist: &Playlist| {
+        dates::Date::parse(&playlist.last_modified_date).filter(|_| args.mtime_from_modified)
+    };
+    // Files that aren't one playlist's are dated by the latest change to any
+    let latest_modified = root.playlists.iter().filter_map(modified_date).max();
+    let mut playlist_dates: HashMap<PathBuf, dates::Date> = HashMap::new();
+    let summary_file = report_formats
+        .contains(&ReportFormat::Json)
+        .then(|| output_file(summary::SUMMARY_FILENAME))
+        .transpose()?;
+    println!("Output directory: {}", args.output);
+    println!("Output format: {}", format);
+
+    if format == "archive" {
+        let filepath = output_file(archive::ARCHIVE_FILENAME)?;
+        output::write_file(&filepath, archive::generate_archive(&root))?;
+        let total_tracks: usize = root.playlists.iter().map(|p| p.items.len()).sum();
+        println!();
+        console::success(format!(
+            "Created: {} ({} tracks from {} playlists)",
+            archive::ARCHIVE_FILENAME,
+            total_tracks,
+            root.playlists.len()
+        ));
+        postprocess(args.postprocess.as_deref(), std::slice::from_ref(&filepath))?;
+        set_file_attributes(std::slice::from_ref(&filepath), file_mode, |_| {
+            latest_modified
+        })?;
+        record_success(&output_dir, run_started, root.playlists.len(), 1)?;
+        summary.end_phase("Writing output");
+        report_summary(&mut summary, &[filepath], summary_file, file_mode)?;
+        git_commit(&args, &output_dir, &root, None)?;
+        println!("\nDone!");
+        return Ok(());
+    }
+
+    if args.flat {
+        let filename = format!("tracks.{}", extension);
+        let filepath = output_file(&filename)?;
+        output::write_file(
+            &filepath,
+            csv::generate_flat_csv(&root.playlists, &csv_options),
+        )?;
+
+        let total_tracks: usize = root.playlists.iter().map(|p| p.items.len()).sum();
+        println!();
+        console::success(format!(
+            "Created: {} ({} tracks from {} playlists)",
+            filename,
+            total_tracks,
+            root.playlists.len()
+        ));
+        postprocess(args.postprocess.as_deref(), std::slice::from_ref(&filepath))?;
+        set_file_attributes(std::slice::from_ref(&filepath), file_mode, |_| {
+            latest_modified
+        })?;
+        record_success(&output_dir, run_started, root.playlists.len(), 1)?;
+        summary.end_phase("Writing output");
+        report_summary(&mut summary, &[filepath], summary_file, file_mode)?;
+        git_commit(&args, &output_dir, &root, None)?;
+        println!("\nDone!");
+        return Ok(());
+    }
+
+    let emoji_shortcodes = match args.emoji.to_lowercase().as_str() {
+        "keep" => false,
+        "shortcode" => true,
+        _ => {
+            return Err(ConverterError::usage(
+                "--emoji must be either 'keep' or 'shortcode'",
+            ));
+        }
+    };
+
+    // Wide enough that every number has the same length, so filenames sort
+    // in export order
+    let numbered_template = args.numbered.then(|| {
+        format!(
+            "{{index:0{}}} - {{name}}",
+            root.playlists.len().to_string().len().max(3)
+        )
+    });
+    let filename_template = args
+        .filename_template
+        .as_deref()
+        .or(numbered_template.as_deref())
+        .map(template::parse_filename_template)
+        .transpose()
+        .map_err(ConverterError::Usage)?;
+    let style = FilenameStyle {
+        emoji_shortcodes,
+        slug: args.slug,
+    };
+    let names = playlist_basenames(&root.playlists, style);
+    for (playlist, name) in root.playlists.iter().zip(&names) {
+        if *name != playlist_basename(&playlist.name, style) {
+            println!(
+                "Note: playlist '{}' has no letters or digits {}to name a file by; writing it as {}",
+                playlist.name,
+                if args.slug { "in ASCII " } else { "" },
+                name
+            );
+        }
+    }
+    let wanted: Vec<String> = root
+        .playlists
+        .iter()
+        .zip(names)
+        .enumerate()
+        .map(|(idx, (playlist, name))| {
+            let Some(template) = &filename_template else {
+                return name;
+            };
+            let rendered = template.render(playlist, idx, &name, &format);
+            let path = if args.slug {
+                sanitize_path(
+                    &rendered
+                        .split('/')
+                        .map(|part| slug::ascii_slug(part, emoji_shortcodes))
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                )
+            } else {
+                sanitize_path(&rendered)
+            };
+            if path.is_empty() { name } else { path }
+        })
+        .collect();
+    let basenames = unique_basenames(wanted.clone(), style);
+    for ((playlist, wanted), basename) in root.playlists.iter().zip(&wanted).zip(&basenames) {
+        if wanted != basename {
+            summary
+                .renamed
+                .push((playlist.name.clone(), basename.clone()));
+        }
+    }
+
+    if format == "email" {
+        println!("\nProcessing {} playlists...", root.playlists.len());
+        let mut email_files = Vec::new();
+        for (idx, playlist) in root.playlists.iter().enumerate() {
+            let filename = format!("{}.eml", basenames[idx]);
+            let filepath = output_file(&filename)?;
+            deliver_email(
+                &email::playlist_email(playlist, &render_options.localizer),
+                &filepath,
+                &args.email.send_to,
+                &delivery,
+            )?;
+            if let Some(date) = modified_date(playlist) {
+                playlist_dates.insert(filepath.clone(), date);
+            }
+            email_files.push(filepath);
+            console::success(format!(
+                "Created: {} ({} tracks)",
+                filename,
+                playlist.items.len()
+            ));
+        }
+        set_file_attributes(&email_files, file_mode, |path| {
+            playlist_dates.get(path).copied().or(latest_modified)
+        })?;
+        record_success(
+            &output_dir,
+            run_started,
+            root.playlists.len(),
+            root.playlists.len(),
+        )?;
+        summary.end_phase("Writing emails");
+        report_summary(&mut summary, &email_files, summary_file, file_mode)?;
+        git_commit(&args, &output_dir, &root, None)?;
+        println!("\nDone!");
+        return Ok(());
+    }
+
+    if let Some(format_plugin) = &mut format_plugin {
+        println!("\nProcessing {} playlists...", root.playlists.len());
+        let mut plugin_files = Vec::new();
+        for (idx, playlist) in root.playlists.iter().enumerate() {
+            let content = format_plugin
+                .render(playlist)
+                .map_err(ConverterError::Failed)?;
+            let filename = format!("{}.{}", basenames[idx], format_plugin.extension());
+            let filepath = output_file(&filename)?;
+            output::write_file(&filepath, content)?;
+            if let Some(date) = modified_date(playlist) {
+                playlist_dates.insert(filepath.clone(), date);
+            }
+            plugin_files.push(filepath);
+            console::success(format!(
+                "Created: {} ({} tracks)",
+                filename,
+                playlist.items.len()
+            ));
+        }
+        postprocess(args.postprocess.as_deref(), &plugin_files)?;
+        set_file_attributes(&plugin_files, file_mode, |path| {
+            playlist_dates.get(path).copied().or(latest_modified)
+        })?;
+        record_success(
+            &output_dir,
+            run_started,
+            root.playlists.len(),
+            plugin_files.len(),
+        )?;
+        summary.end_phase("Rendering pages");
+        report_summary(&mut summary, &plugin_files, summary_file, file_mode)?;
+        git_commit(&args, &output_dir, &root, None)?;
+        println!("\nDone!");
+        return Ok(());
+    }
+
+    if let Some(client) = api_client.as_ref().filter(|_| args.preview_audio) {
+        println!("\nDownloading preview clips...");
+        let clips =
+            previews::download_previews(client, &root.playlists, market.as_deref(), &output_dir)
+                .map_err(ConverterError::Failed)?;
+        console::success(format!(
+            "Downloaded: {} preview clips into {}/",
+            clips.len(),
+            previews::previews_dir()
+        ));
+        render_options.preview_audio = Arc::new(clips);
+        summary.end_phase("Downloading previews");
+    }
+
+    // The playlists the previous run rendered, and the tracks added and
+    // removed since
+    let previous = (tracks_changes || args.git_commit)
+        .then(|| previous_snapshot(&output_dir))
+        .flatten();
+    let track_changes = match &previous {
+        Some(previous) if args.annotate_changes => {
+            diff::track_changes(&previous.playlists, &root.playlists)
+        }
+        _ => Vec::new(),
+    };
+
+    let mut filenames = Vec::new();
+    let mut assets = args.csp.then(csp::AssetStore::new);
+    let mut written: Vec<PathBuf> = Vec::new();
+
+    // Process each playlist: render on worker threads, then write in order.
+    // With --max-memory only one batch of rendered pages is held at a time.
+    println!("\nProcessing {} playlists...", root.playlists.len());
+    let jobs = parallel::job_count(args.jobs, args.nice);
+    // Email, archive and plugin formats have returned by now
+    let renderer = &renderers[&format];
+    let render_pages = |idx: usize, playlist: &Playlist| {
+        let basename = basenames[idx].clone();
+        // A filename template can put pages in subdirectories
+        let root_path = "../".repeat(basename.matches('/').count());
+        let page_name = basename.rsplit('/').next().unwrap_or(&basename);
+
+        // Playlists too long to preview get a full listing next to the page
+        let preview = args
+            .preview_rows
+            .filter(|&rows| playlist.items.len() > rows)
+            .map(|rows| TrackPreview {
+                rows,
+                full_listing: format!("{}.full.{}", page_name, extension),
+            });
+        let full_options = RenderOptions {
+            track_changes: track_changes.get(idx).cloned().flatten(),
+            root_path: root_path.clone(),
+            ..render_options.clone()
+        };
+        let page_options = RenderOptions {
+            track_preview: preview.clone(),
+            ..full_options.clone()
+        };
+
+        let content = renderer.render_playlist(playlist, &page_options);
+        let full_content = preview
+            .is_some()
+            .then(|| renderer.render_playlist(playlist, &full_options));
+        let embed_content = args
+            .embed
+            .then(|| generate_embed_html(playlist, &render_options));
+
+        PlaylistPages {
+            basename,
+            root_path,
+            preview,
+            content,
+            full_content,
+            embed_content,
+        }
+    };
+
+    let track_counts: Vec<usize> = root.playlists.iter().map(|p| p.items.len()).collect();
+    for batch in parallel::batches(&track_counts, max_memory) {
+        let playlists = &root.playlists[batch.clone()];
+        let pages = parallel::map(playlists, jobs, |idx, playlist| {
+            render_pages(batch.start + idx, playlist)
+        });
+        for (playlist, pages) in playlists.iter().zip(pages) {
+            let filename = format!("{}.{}", pages.basename, extension);
+            let filepath = output_file(&filename)?;
+            let content = if format == "html" && !args.fragment {
+                finish_html(pages.content, &mut assets, &pages.root_path)
+            } else {
+                pages.content
+            };
+
+            output::write_file(&filepath, content)?;
+            let modified = modified_date(playlist);
+            if let Some(date) = modified {
+                playlist_dates.insert(filepath.clone(), date);
+            }
+            written.push(filepath);
+            filenames.push(filename.clone());
+
+            match &pages.preview {
+                Some(preview) => console::success(format!(
+                    "Created: {} ({} of {} tracks)",
+                    filename,
+                    preview.rows,
+                    playlist.items.len()
+                )),
+                None => console::success(format!(
+                    "Created: {} ({} tracks)",
+                    filename,
+                    playlist.items.len()
+                )),
+            }
+
+            if let Some(full_content) = pages.full_content {
+                let full_content = if format == "html" {
+                    finish_html(full_content, &mut assets, &pages.root_path)
+                } else {
+                    full_content
+                };
+                let full_filename = format!("{}.full.{}", pages.basename, extension);
+                let full_filepath = output_file(&full_filename)?;
+                output::write_file(&full_filepath, full_content)?;
+                if let Some(date) = modified {
+                    playlist_dates.insert(full_filepath.clone(), date);
+                }
+                written.push(full_filepath);
+                console::success(format!(
+                    "Created: {} ({} tracks)",
+                    full_filename,
+                    playlist.items.len()
+                ));
+            }
+
+            if let Some(embed_content) = pages.embed_content {
+                let embed_filename = format!("{}.embed.html", pages.basename);
+                let embed_filepath = output_file(&embed_filename)?;
+                output::write_file(
+                    &embed_filepath,
+                    finish_html(embed_content, &mut assets, &pages.root_path),
+                )?;
+                if let Some(date) = modified {
+                    playlist_dates.insert(embed_filepath.clone(), date);
+                }
+                written.push(embed_filepath);
+                console::success(format!("Created: {}", embed_filename));
+            }
+
+            if args.nice {
+                std::thread::sleep(parallel::NICE_WRITE_PAUSE);
+            }
+        }
+    }
+
+    summary.end_phase("Rendering pages");
+
+    // Generate per-tag index pages
+    if render_options.tags && !args.fragment {
+        for (tag, members) in tags::collect_tags(&root.playlists, &render_options.collation) {
+            let tag_filename = tags::tag_filename(&tag, extension);
+            let tag_content = if format == "html" {
+                finish_html(
+                    generate_tag_html(&tag, &members, &root.playlists, &filenames, &render_options),
+                    &mut assets,
+                    "",
+                )
+            } else {
+                generate_tag_markdown(&tag, &members, &root.playlists, &filenames, &render_options)
+            };
+            let tag_filepath = output_file(&tag_filename)?;
+            output::write_file(&tag_filepath, tag_content)?;
+            written.push(tag_filepath);
+            console::success(format!(
+                "Created: {} ({} playlists)",
+                tag_filename,
+                members.len()
+            ));
+        }
+    }
+
+    // Generate changes report against an earlier export
+    if let Some(previous) = &args.compare {
+        let (mut previous_root, issues) = read_export(previous, parse_mode)?;
+        report_field_issues(args.stdout, previous, &issues);
+        if args.scrub_uris {
+            scrub_uris(&mut previous_root);
+        }
+        if args.normalize {
+            normalize::normalize(&mut previous_root, args.title_case);
+        }
+        let changes = diff::diff_exports(&previous_root.playlists, &root.playlists);
+        let changes_files = write_report(
+            &output_dir,
+            "changes",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => diff::generate_diff_markdown(
+                    &changes,
+                    &previous_root.playlists,
+                    &root.playlists,
+                    &filenames,
+                ),
+                ReportFormat::Html => diff::generate_diff_html(
+                    &changes,
+                    &previous_root.playlists,
+                    &root.playlists,
+                    &filenames,
+                ),
+                ReportFormat::Json => json_report(diff::generate_diff_json(
+                    &changes,
+                    &previous_root.playlists,
+                    &root.playlists,
+                    &filenames,
+                )),
+            },
+        )?;
+        console::success(format!(
+            "Created: {} ({} added, {} removed, {} renamed)",
+            changes_files,
+            changes.added.len(),
+            changes.removed.len(),
+            changes
+                .renamed(&previous_root.playlists, &root.playlists)
+                .count()
+        ));
+    }
+
+    // Generate the changelog, this run's changes added to those of earlier ones
+    let mut history = Vec::new();
+    if args.changelog {
+        history = changelog::load_history(&output_dir);
+        let today = dates::Date::today().to_string();
+        let generation = match &previous {
+            Some(previous) => changelog::Generation::from_diff(
+                today,
+                &diff::diff_exports(&previous.playlists, &root.playlists),
+                &previous.playlists,
+                &root.playlists,
+            ),
+            None => changelog::Generation::first(today, root.playlists.len()),
+        };
+        let recorded = !generation.is_empty();
+        if recorded {
+            history.push(generation);
+        }
+        let changelog_files = write_report(
+            &output_dir,
+            "changelog",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => {
+                    changelog::generate_changelog_markdown(&history, &root.playlists, &filenames)
+                }
+                ReportFormat::Html => {
+                    changelog::generate_changelog_html(&history, &root.playlists, &filenames)
+                }
+                ReportFormat::Json => json_report(changelog::generate_changelog_json(
+                    &history,
+                    &root.playlists,
+                    &filenames,
+                )),
+            },
+        )?;
+        console::success(format!(
+            "Created: {} ({} generations{})",
+            changelog_files,
+            history.len(),
+            if recorded {
+                ""
+            } else {
+                ", nothing changed this run"
+            }
+        ));
+    }
+
+    // Generate unavailable tracks report through the Spotify API
+    if let Some(client) = api_client.as_ref().filter(|_| args.check_availability) {
+        println!("\nChecking track availability...");
+        let mut journal = journal::Journal::open(&output_dir, market.as_deref().unwrap_or(""))?;
+        if !journal.is_empty() {
+            println!(
+                "  Resuming: {} lookups from an interrupted run",
+                journal.len()
+            );
+        }
+        let availability = availability::check_availability(
+            client,
+            &root.playlists,
+            market.as_deref(),
+            &mut journal,
+        )
+        .map_err(ConverterError::Failed)?;
+        let unavailable = &availability.unavailable;
+        let availability_files = write_report(
+            &output_dir,
+            "unavailable",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => availability::generate_availability_markdown(
+                    unavailable,
+                    &root.playlists,
+                    &filenames,
+                    market.as_deref(),
+                ),
+                ReportFormat::Html => availability::generate_availability_html(
+                    unavailable,
+                    &root.playlists,
+                    &filenames,
+                    market.as_deref(),
+                ),
+                ReportFormat::Json => json_report(availability::generate_availability_json(
+                    unavailable,
+                    &root.playlists,
+                    &filenames,
+                    market.as_deref(),
+                )),
+            },
+        )?;
+        console::success(format!(
+            "Created: {} ({} unavailable tracks)",
+            availability_files,
+            unavailable.len()
+        ));
+
+        if args.relink {
+            let replacements = availability::find_replacements(
+                client,
+                &root.playlists,
+                &availability,
+                market.as_deref(),
+                &mut journal,
+            )
+            .map_err(ConverterError::Failed)?;
+            let remediation_files = write_report(
+                &output_dir,
+                "remediation",
+                &report_formats,
+                &mut assets,
+                &mut written,
+                |report_format| match report_format {
+                    ReportFormat::Markdown => availability::generate_remediation_markdown(
+                        &replacements,
+                        &root.playlists,
+                        &filenames,
+                    ),
+                    ReportFormat::Html => availability::generate_remediation_html(
+                        &replacements,
+                        &root.playlists,
+                        &filenames,
+                    ),
+                    ReportFormat::Json => json_report(availability::generate_remediation_json(
+                        &replacements,
+                        &root.playlists,
+                        &filenames,
+                    )),
+                },
+            )?;
+            console::success(format!(
+                "Created: {} ({} suggested replacements)",
+                remediation_files,
+                replacements.len()
+            ));
+        }
+
+        journal.finish()?;
+    }
+
+    // Generate data warnings report
+    if args.validate_on_convert {
+        let validation_files = write_report(
+            &output_dir,
+            "validation",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => {
+                    lint::generate_validation_markdown(&data_warnings, &root.playlists, &filenames)
+                }
+                ReportFormat::Html => {
+                    lint::generate_validation_html(&data_warnings, &root.playlists, &filenames)
+                }
+                ReportFormat::Json => json_report(lint::generate_validation_json(
+                    &data_warnings,
+                    &root.playlists,
+                    &filenames,
+                )),
+            },
+        )?;
+        console::success(format!(
+            "Created: {} ({} warnings)",
+            validation_files,
+            data_warnings.len()
+        ));
+    }
+
+    // Generate stale playlists report
+    if let Some(span) = stale_after {
+        let today = dates::Date::today();
+        let stale = stale::stale_playlists(&root.playlists, span, today);
+        let stale_files = write_report(
+            &output_dir,
+            "stale",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => {
+                    stale::generate_stale_markdown(&stale, &root.playlists, &filenames, span, today)
+                }
+                ReportFormat::Html => {
+                    stale::generate_stale_html(&stale, &root.playlists, &filenames, span, today)
+                }
+                ReportFormat::Json => json_report(stale::generate_stale_json(
+                    &stale,
+                    &root.playlists,
+                    &filenames,
+                    span,
+                    today,
+                )),
+            },
+        )?;
+        console::success(format!(
+            "Created: {} ({} stale playlists)",
+            stale_files,
+            stale.len()
+        ));
+    }
+
+    // Generate track tenure report
+    if args.tenure {
+        let as_of = export_date
+            .or_else(|| tenure::latest_export_date(&root.playlists))
+            .unwrap_or_else(dates::Date::today);
+        let tenure = tenure::track_tenure(&root.playlists, as_of);
+        let tenure_files = write_report(
+            &output_dir,
+            "tenure",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => {
+                    tenure::generate_tenure_markdown(&tenure, &root.playlists, &filenames, as_of)
+                }
+                ReportFormat::Html => {
+                    tenure::generate_tenure_html(&tenure, &root.playlists, &filenames, as_of)
+                }
+                ReportFormat::Json => json_report(tenure::generate_tenure_json(
+                    &tenure,
+                    &root.playlists,
+                    &filenames,
+                    as_of,
+                )),
+            },
+        )?;
+        console::success(format!("Created: {} (as of {})", tenure_files, as_of));
+    }
+
+    if args.duplicates {
+        let duplicates = duplicates::find_duplicates(&root.playlists);
+        let duplicates_files = write_report(
+            &output_dir,
+            "duplicates",
+            &report_formats,
+            &mut assets,
+            &mut written,
+            |report_format| match report_format {
+                ReportFormat::Markdown => duplicates::generate_duplicates_markdown(
+                    &duplicates,
+                    &root.playlists,
+                    &filenames,
+                ),
+                ReportFormat::Html => {
+                    duplicates::generate_duplicates_html(&duplicates, &root.playlists, &filenames)
+                }
+                ReportFormat::Json => json_report(duplicates::generate_duplicates_json(
+                    &duplicates,
+                    &root.playlists,
+                    &filenames,
+                )),
+            },
+        )?;
+        let groups: usize = duplicates.iter().map(|entry| entry.groups.len()).sum();
+        console::success(format!(
+            "Created: {} ({} duplicate groups in {} playlists)",
+            duplicates_files,
+            groups,
+            duplicates.len()
+        ));
+    }
+
+    if args.match_keys {
+        let match_keys = match_keys::generate_match_keys(&root.playlists);
+        let match_keys_filepath = output_file(match_keys::MATCH_KEYS_FILENAME)?;
+        output::write_file(&match_keys_filepath, &match_keys)?;
+        written.push(match_keys_filepath);
+        console::success(format!(
+            "Created: {} ({} tracks)",
+            match_keys::MATCH_KEYS_FILENAME,
+            match_keys.lines().count()
+        ));
+    }
+
+    if args.beets {
+        let beets_filepath = output_file(beets::BEETS_FILENAME)?;
+        output::write_file(
+            &beets_filepath,
+            beets::generate_beets_config(&root.playlists, &basenames),
+        )?;
+        written.push(beets_filepath);
+        console::success(format!("Created: {}", beets::BEETS_FILENAME));
+    }
+
+    if args.kodi {
+        let kodi_dir = output_file(kodi::KODI_DIR)?;
+        fs::create_dir_all(&kodi_dir)?;
+        let mut count = 0;
+        for (idx, playlist) in root.playlists.iter().enumerate() {
+            let Some(xsp) = kodi::generate_xsp(playlist) else {
+                continue;
+            };
+            let xsp_filepath = output_file(&format!("{}/{}.xsp", kodi::KODI_DIR, basenames[idx]))?;
+            output::write_file(&xsp_filepath, xsp)?;
+            written.push(xsp_filepath);
+            count += 1;
+        }
+        console::success(format!(
+            "Created: {} smart playlists in {}/",
+            count,
+            kodi::KODI_DIR
+        ));
+    }
+
+    // Stats only exist as a report in machine-readable form; the human
+    // formats show them on each playlist page
+    if report_formats.contains(&ReportFormat::Json) {
+        let stats_filepath = output_file("stats.json")?;
+        output::write_file(
+            &stats_filepath,
+            json_report(analysis::generate_stats_json(&root.playlists, &filenames)),
+        )?;
+        written.push(stats_filepath);
+        console::success(format!(
+            "Created: stats.json ({} playlists)",
+            root.playlists.len()
+        ));
+    }
+
+    summary.end_phase("Reports");
+
+    // Generate index file
+    let index_filename = format!("index.{}", extension);
+    let index_filepath = output_file(&index_filename)?;
+
+    let index_content = renderer.render_index(&root.playlists, &filenames, &render_options);
+    let index_content = if format == "html" && !args.fragment {
+        finish_html(index_content, &mut assets, "")
+    } else {
+        index_content
+    };
+
+    output::write_file(&index_filepath, index_content)?;
+    written.push(index_filepath.clone());
+    println!();
+    console::success(format!("Created: {}", index_filename));
+
+    // Per-playlist tables are for spreadsheets; the combined one loads into
+    // pandas and friends in one go
+    if format == "csv" {
+        let all_tracks_filename = format!("all_tracks.{}", extension);
+        let all_tracks_filepath = output_file(&all_tracks_filename)?;
+        output::write_file(
+            &all_tracks_filepath,
+            csv::generate_flat_csv(&root.playlists, &csv_options),
+        )?;
+        written.push(all_tracks_filepath);
+        let total_tracks: usize = root.playlists.iter().map(|p| p.items.len()).sum();
+        console::success(format!(
+            "Created: {} ({} tracks)",
+            all_tracks_filename, total_tracks
+        ));
+    }
+
+    if let Some(assets) = &assets {
+        assets.write_to(&output_dir)?;
+        console::success(format!(
+            "Created: {} assets in {}/ and content-security-policy.txt",
+            assets.asset_count(),
+            csp::ASSETS_DIR
+        ));
+    }
+
+    summary.end_phase("Index and assets");
+
+    postprocess(args.postprocess.as_deref(), &written)?;
+    set_file_attributes(&written, file_mode, |path| {
+        playlist_dates.get(path).copied().or(latest_modified)
+    })?;
+
+    if args.check_output {
+        let mut problems = 0;
+        for path in written
+            .iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        {
+            let errors = validate::check_html(&fs::read_to_string(path)?);
+            for error in &errors {
+                console::failure(format!("{}: {}", path.display(), error));
+            }
+            problems += errors.len();
+        }
+        if problems > 0 {
+            eprintln!();
+            return Err(ConverterError::failed(format!(
+                "generated HTML has {} problem(s)",
+                problems
+            )));
+        }
+        console::success("Checked: generated HTML is well-formed");
+    }
+
+    // Written last, so a failed run doesn't move what the next one compares to
+    if tracks_changes || args.git_commit {
+        output::write_file(
+            output_dir.join(diff::SNAPSHOT_FILENAME),
+            archive::generate_archive(&root),
+        )?;
+    }
+    if args.changelog {
+        changelog::save_history(&output_dir, &history)
+            .map_err(|e| ConverterError::output(changelog::HISTORY_FILENAME, e))?;
+    }
+    record_success(
+        &output_dir,
+        run_started,
+        root.playlists.len(),
+        written.len(),
+    )?;
+    summary.end_phase("Postprocessing and checks");
+    report_summary(&mut summary, &written, summary_file, file_mode)?;
+    git_commit(&args, &output_dir, &root, previous.as_ref())?;
+    println!(
+        "\nDone! Generated {} {} files plus index.",
+        root.playlists.len(),
+        format
+    );
+    let index_filepath = match workspace {
+        Some(workspace) => workspace.destination().join(&index_filename),
+        None => index_filepath,
+    };
     println!("Open {} to get started!", index_filepath.display());
 
     Ok(())
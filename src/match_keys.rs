@@ -0,0 +1,59 @@
+use crate::{Playlist, Track, analysis, duplicates};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// File the match keys are written to, in the output directory
+pub const MATCH_KEYS_FILENAME: &str = "match-keys.jsonl";
+
+/// Artist and title reduced to the form tools matching local audio can
+/// compare against: lowercase letters and digits only, with version suffixes
+/// such as "- Remastered 2011" or "(Live)" dropped from the title. A tool that
+/// fingerprints a file with Chromaprint and looks it up on AcoustID normalizes
+/// the artist and title it gets back the same way to find the export's track.
+pub fn match_key(artist_name: &str, track_name: &str) -> (String, String) {
+    (
+        duplicates::simplify(&analysis::artist_key(artist_name)),
+        duplicates::title_key(track_name),
+    )
+}
+
+/// One JSON object per line for every distinct track of the export, with its
+/// match key, metadata and the playlists it is on
+pub fn generate_match_keys(playlists: &[Playlist]) -> String {
+    let mut order: Vec<(String, String)> = Vec::new();
+    let mut tracks: HashMap<(String, String), (&Track, Vec<&str>)> = HashMap::new();
+
+    for playlist in playlists {
+        for item in &playlist.items {
+            let track = &item.track;
+            let key = match_key(&track.artist_name, &track.track_name);
+            if key.0.is_empty() && key.1.is_empty() {
+                continue;
+            }
+            let entry = tracks.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                (track, Vec::new())
+            });
+            if !entry.1.contains(&playlist.name.as_str()) {
+                entry.1.push(&playlist.name);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for key in order {
+        let (track, playlist_names) = &tracks[&key];
+        let line = json!({
+            "artist_key": key.0,
+            "title_key": key.1,
+            "track_name": track.track_name,
+            "artist_name": track.artist_name,
+            "album_name": track.album_name,
+            "track_uri": track.track_uri,
+            "playlists": playlist_names,
+        });
+        out.push_str(&line.to_string());
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,205 @@
+use crate::output;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Record of conversion runs, kept in the output directory across runs
+pub const RUN_METRICS_FILENAME: &str = ".run-metrics.json";
+
+/// Outcome of the conversion runs into one output directory. A run records
+/// its start before doing any work and its success at the very end, so a
+/// failed or killed run shows as a start without a matching success.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunMetrics {
+    pub runs_started: u64,
+    pub runs_succeeded: u64,
+    /// Unix time the last run started
+    pub last_start: u64,
+    /// Unix time the last successful run finished
+    pub last_success: Option<u64>,
+    pub last_duration_seconds: f64,
+    /// Playlists converted by the last successful run
+    pub last_playlists: usize,
+    /// Files written by the last successful run
+    pub last_files: usize,
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+impl RunMetrics {
+    /// The record in `output_dir`, `None` if no run has written one
+    pub fn load(output_dir: &Path) -> Option<Self> {
+        let text = fs::read_to_string(output_dir.join(RUN_METRICS_FILENAME)).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn save(&self, output_dir: &Path) -> io::Result<()> {
+        fs::write(
+            output::output_file(output_dir, RUN_METRICS_FILENAME).map_err(io::Error::other)?,
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    pub fn record_start(output_dir: &Path) -> io::Result<()> {
+        let mut metrics = Self::load(output_dir).unwrap_or_default();
+        metrics.runs_started += 1;
+        metrics.last_start = unix_now();
+        metrics.save(output_dir)
+    }
+
+    pub fn record_success(
+        output_dir: &Path,
+        duration_seconds: f64,
+        playlists: usize,
+        files: usize,
+    ) -> io::Result<()> {
+        let mut metrics = Self::load(output_dir).unwrap_or_default();
+        metrics.runs_succeeded += 1;
+        metrics.last_success = Some(unix_now());
+        metrics.last_duration_seconds = duration_seconds;
+        metrics.last_playlists = playlists;
+        metrics.last_files = files;
+        metrics.save(output_dir)
+    }
+}
+
+/// Counters of a running server
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    /// Responses by status code
+    responses: Mutex<BTreeMap<u16, u64>>,
+    /// Playlists rendered on request, by format
+    renders: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl ServerMetrics {
+    pub fn count_response(&self, status: u16) {
+        if let Ok(mut responses) = self.responses.lock() {
+            *responses.entry(status).or_default() += 1;
+        }
+    }
+
+    pub fn count_render(&self, format: &'static str) {
+        if let Ok(mut renders) = self.renders.lock() {
+            *renders.entry(format).or_default() += 1;
+        }
+    }
+}
+
+fn push_metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, String)]) {
+    let _ = writeln!(out, "# HELP spotify_converter_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE spotify_converter_{} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "spotify_converter_{}{} {}", name, labels, value);
+    }
+}
+
+/// Metrics in the Prometheus text exposition format. Without a run record
+/// only the server's own counters are included.
+pub fn render_prometheus(runs: Option<&RunMetrics>, server: &ServerMetrics) -> String {
+    let mut out = String::new();
+    let single = |value: String| vec![(String::new(), value)];
+
+    if let Some(runs) = runs {
+        push_metric(
+            &mut out,
+            "runs_started_total",
+            "counter",
+            "Conversion runs started into the served directory.",
+            &single(runs.runs_started.to_string()),
+        );
+        push_metric(
+            &mut out,
+            "runs_succeeded_total",
+            "counter",
+            "Conversion runs that finished successfully.",
+            &single(runs.runs_succeeded.to_string()),
+        );
+        push_metric(
+            &mut out,
+            "last_run_start_timestamp_seconds",
+            "gauge",
+            "Unix time the last conversion run started.",
+            &single(runs.last_start.to_string()),
+        );
+        if let Some(last_success) = runs.last_success {
+            push_metric(
+                &mut out,
+                "last_success_timestamp_seconds",
+                "gauge",
+                "Unix time the last successful conversion run finished.",
+                &single(last_success.to_string()),
+            );
+        }
+        push_metric(
+            &mut out,
+            "last_run_duration_seconds",
+            "gauge",
+            "Duration of the last successful conversion run.",
+            &single(format!("{:.3}", runs.last_duration_seconds)),
+        );
+        push_metric(
+            &mut out,
+            "last_run_playlists",
+            "gauge",
+            "Playlists converted by the last successful run.",
+            &single(runs.last_playlists.to_string()),
+        );
+        push_metric(
+            &mut out,
+            "last_run_files",
+            "gauge",
+            "Files written by the last successful run.",
+            &single(runs.last_files.to_string()),
+        );
+    }
+
+    let responses: Vec<(String, String)> = server
+        .responses
+        .lock()
+        .map(|responses| {
+            responses
+                .iter()
+                .map(|(status, count)| (format!("{{code=\"{}\"}}", status), count.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    push_metric(
+        &mut out,
+        "http_responses_total",
+        "counter",
+        "HTTP responses sent by the server, by status code.",
+        &responses,
+    );
+
+    let renders: Vec<(String, String)> = server
+        .renders
+        .lock()
+        .map(|renders| {
+            renders
+                .iter()
+                .map(|(format, count)| (format!("{{format=\"{}\"}}", format), count.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    push_metric(
+        &mut out,
+        "renders_total",
+        "counter",
+        "Playlists rendered on request through the API, by format.",
+        &renders,
+    );
+
+    out
+}
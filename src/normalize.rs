@@ -0,0 +1,82 @@
+use crate::Root;
+
+/// Spellings of "featuring" that are rewritten to `feat.`
+const FEATURING: &[&str] = &["feat.", "feat", "ft.", "featuring", "featuring."];
+
+/// Words left lowercase by title casing unless they start the text
+const SMALL_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "of", "on", "or", "the", "to",
+];
+
+/// Cleans up track, artist and album names before anything is rendered or
+/// counted: whitespace is trimmed and collapsed, and "ft."/"featuring" become
+/// "feat.". With `title_case`, names are also title-cased.
+pub fn normalize(root: &mut Root, title_case: bool) {
+    for playlist in &mut root.playlists {
+        for item in &mut playlist.items {
+            let track = &mut item.track;
+            for field in [
+                &mut track.track_name,
+                &mut track.artist_name,
+                &mut track.album_name,
+            ] {
+                let mut normalized = unify_featuring(&collapse_whitespace(field));
+                if title_case {
+                    normalized = to_title_case(&normalized);
+                }
+                *field = normalized;
+            }
+        }
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Rewrites featuring credits to `feat.`. A bare "ft" only counts right after
+/// an opening bracket, since it is also a word in its own right.
+fn unify_featuring(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let core = word.trim_start_matches(['(', '[']);
+            let bracket = &word[..word.len() - core.len()];
+            let lower = core.to_lowercase();
+            if FEATURING.contains(&lower.as_str()) || (lower == "ft" && !bracket.is_empty()) {
+                format!("{}feat.", bracket)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Capitalizes each word and lowercases the rest of it, keeping `feat.` and
+/// short connecting words other than the first and last lowercase
+fn to_title_case(text: &str) -> String {
+    let last = text.split(' ').count() - 1;
+    text.split(' ')
+        .enumerate()
+        .map(|(idx, word)| {
+            let lower = word.to_lowercase();
+            let bare = lower.trim_start_matches(['(', '[']);
+            let inner = idx > 0 && idx < last;
+            if (inner && SMALL_WORDS.contains(&bare)) || bare == "feat." {
+                return lower;
+            }
+            let mut titled = String::with_capacity(word.len());
+            let mut capitalized = false;
+            for c in lower.chars() {
+                if !capitalized && c.is_alphanumeric() {
+                    titled.extend(c.to_uppercase());
+                    capitalized = true;
+                } else {
+                    titled.push(c);
+                }
+            }
+            titled
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,263 @@
+use crate::dates::Date;
+use crate::error::ConverterError;
+use crate::{MAX_BASENAME_BYTES, changelog, diff, journal, metrics, platform, previews};
+use std::fs::{self, File};
+use std::io;
+use std::path::{self, Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// The output directory, created if needed and resolved to its canonical
+/// path, so the files written are checked against where they actually go.
+/// Deeply nested directories work on Windows too, see [`platform::long_path`].
+pub fn prepare_output_dir(path: &str) -> io::Result<PathBuf> {
+    let path = platform::long_path(&path::absolute(path)?);
+    fs::create_dir_all(&path)?;
+    fs::canonicalize(&path).map(|canonical| platform::long_path(&canonical))
+}
+
+/// Files of earlier runs that a run reads back, copied into a [`Workspace`]
+/// so it carries on from them: run metrics, the enrichment journal, the
+/// downloaded preview clips, the snapshot and history changes are tracked
+/// with, and the repository `--git-commit` commits to
+fn carried_over() -> [String; 7] {
+    [
+        metrics::RUN_METRICS_FILENAME.to_string(),
+        journal::JOURNAL_FILENAME.to_string(),
+        previews::previews_dir(),
+        diff::SNAPSHOT_FILENAME.to_string(),
+        changelog::HISTORY_FILENAME.to_string(),
+        ".git".to_string(),
+        ".gitignore".to_string(),
+    ]
+}
+
+/// A directory next to the output directory that a `--swap` run generates
+/// into. Only once the run has succeeded does it take the output
+/// directory's place; otherwise it is removed when dropped, leaving the
+/// previous generation as it was.
+pub struct Workspace {
+    dir: PathBuf,
+    destination: PathBuf,
+}
+
+impl Workspace {
+    /// Creates the workspace for the output directory `output`, removing any
+    /// left behind by an interrupted run. The enrichment journal of the
+    /// newest of those is kept, so the run resumes where it stopped.
+    pub fn create(output: &str) -> io::Result<Self> {
+        let destination = prepare_output_dir(output)?;
+        let (Some(parent), Some(name)) = (destination.parent(), destination.file_name()) else {
+            return Err(io::Error::other(
+                "--swap needs an output directory below another one",
+            ));
+        };
+        let prefix = format!(".{}.swap-", name.to_string_lossy());
+        let mut leftover_journal: Option<(std::time::SystemTime, Vec<u8>)> = None;
+        for entry in fs::read_dir(parent)?.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let journal = entry.path().join(journal::JOURNAL_FILENAME);
+                if let Ok(modified) = fs::metadata(&journal).and_then(|m| m.modified())
+                    && leftover_journal
+                        .as_ref()
+                        .is_none_or(|(newest, _)| modified > *newest)
+                    && let Ok(contents) = fs::read(&journal)
+                {
+                    leftover_journal = Some((modified, contents));
+                }
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        let dir = parent.join(format!("{}{}", prefix, std::process::id()));
+        fs::create_dir(&dir)?;
+        let workspace = Workspace { dir, destination };
+        for name in carried_over() {
+            let from = workspace.destination.join(&name);
+            if from.exists() {
+                copy_all(&from, &workspace.dir.join(&name))?;
+            }
+        }
+        // The interrupted run's journal started as a copy of the output
+        // directory's and only grew from there
+        if let Some((_, contents)) = leftover_journal {
+            fs::write(workspace.dir.join(journal::JOURNAL_FILENAME), contents)?;
+        }
+        Ok(workspace)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The output directory, canonical, that the workspace replaces
+    pub fn destination(&self) -> &Path {
+        &self.destination
+    }
+
+    /// Puts the workspace in the output directory's place and removes the
+    /// previous generation. The two renames this takes leave the output
+    /// directory missing only between them; if the second fails, the
+    /// previous generation is put back.
+    pub fn swap(self) -> io::Result<()> {
+        let previous = self.dir.with_file_name(format!(
+            ".{}.previous-{}",
+            self.destination
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            std::process::id()
+        ));
+        fs::rename(&self.destination, &previous)?;
+        if let Err(e) = fs::rename(&self.dir, &self.destination) {
+            let _ = fs::rename(&previous, &self.destination);
+            return Err(e);
+        }
+        fs::remove_dir_all(&previous)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Copies a file, or a directory with everything in it
+fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return fs::copy(from, to).map(drop);
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_all(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Name of the link that `--dated` keeps pointing at the newest output
+pub const LATEST_LINK: &str = "latest";
+
+/// Points the `latest` link in `base` at its subdirectory `target`, after a
+/// run into it succeeded. The new link is made under another name and
+/// renamed over the old one, so `latest` always leads to a complete
+/// generation; Windows can't rename over a junction, so there the old one
+/// is removed first.
+pub fn update_latest_link(base: &Path, target: &str) -> io::Result<()> {
+    let link = base.join(LATEST_LINK);
+    if fs::symlink_metadata(&link).is_ok_and(|metadata| metadata.is_dir()) {
+        return Err(io::Error::other(format!(
+            "{} is a directory, not a link; move it away to have it kept up to date",
+            link.display()
+        )));
+    }
+    if cfg!(windows) {
+        if link.exists() || fs::symlink_metadata(&link).is_ok() {
+            fs::remove_dir(&link)?;
+        }
+        return platform::link_dir(Path::new(target), &link);
+    }
+    let staged = base.join(format!(".{}-{}", LATEST_LINK, std::process::id()));
+    let _ = fs::remove_file(&staged);
+    platform::link_dir(Path::new(target), &staged)?;
+    fs::rename(&staged, &link).inspect_err(|_| {
+        let _ = fs::remove_file(&staged);
+    })
+}
+
+/// Creates, reopens and removes a file with a name as long as the longest
+/// one a run writes, so a directory that can't take them fails before
+/// anything is converted rather than halfway through
+pub fn check_writable(output_dir: &Path) -> Result<(), String> {
+    let name = format!(
+        ".write-check-{}.embed.html",
+        "x".repeat(MAX_BASENAME_BYTES - ".write-check-".len())
+    );
+    let path = output_dir.join(name);
+    let result = fs::write(&path, b"").and_then(|_| File::open(&path).map(drop));
+    let _ = fs::remove_file(&path);
+    // The caller names the directory
+    result.map_err(|e| format!("cannot create files in the output directory: {}", e))
+}
+
+/// Where `filename`, relative to `output_dir`, is written. Names that could
+/// leave the directory are refused: absolute paths, `.` and `..`, anything
+/// but plain names separated by `/`, and paths through existing symlinks,
+/// which could point anywhere.
+pub fn output_file(output_dir: &Path, filename: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(filename);
+    if filename.is_empty()
+        || filename.contains('\\')
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(format!(
+            "refusing to write '{}': not a plain file name within the output directory",
+            filename
+        ));
+    }
+
+    let mut path = output_dir.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return Err(format!(
+                "refusing to write through the symlink {}",
+                path.display()
+            ));
+        }
+    }
+    Ok(path)
+}
+
+/// Writes a file of the output, creating the subdirectory a filename
+/// template may have put it in, and naming it in the error
+pub fn write_file(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<(), ConverterError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| ConverterError::output(parent, e))?;
+    }
+    fs::write(path, contents).map_err(|e| ConverterError::output(path, e))
+}
+
+/// Parses a permission mode in octal, as `chmod` takes it, e.g. 644 or 0640
+pub fn parse_file_mode(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value, 8)
+        .ok()
+        .filter(|&mode| mode <= 0o7777)
+        .ok_or_else(|| format!("invalid file mode '{}': expected octal, e.g. 644", value))
+}
+
+/// Sets the permission bits of a generated file; other platforms have none
+/// to set
+pub fn set_file_mode(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+}
+
+/// Sets the modification time of a generated file to midnight UTC of `date`,
+/// so listings sorted by date follow the playlists
+pub fn set_modified(path: &Path, date: Date) -> io::Result<()> {
+    let Ok(days) = u64::try_from(date.days()) else {
+        return Ok(());
+    };
+    File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(days * 86_400))
+}
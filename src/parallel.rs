@@ -0,0 +1,108 @@
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::thread;
+use std::time::Duration;
+
+/// Rough size of one track's rendered output across a playlist's files, used
+/// to keep rendered-but-unwritten pages under `--max-memory`
+const BYTES_PER_TRACK: usize = 2048;
+
+/// Pause after each playlist's files in `--nice` mode, spreading disk writes
+/// out so small NAS boxes stay responsive
+pub const NICE_WRITE_PAUSE: Duration = Duration::from_millis(50);
+
+/// Worker threads to use: the requested number, one in nice mode, and
+/// otherwise one per CPU
+pub fn job_count(requested: Option<usize>, nice: bool) -> usize {
+    match requested {
+        Some(jobs) => jobs,
+        None if nice => 1,
+        None => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+    }
+}
+
+/// Applies `f` to every item on up to `jobs` threads and returns the results
+/// in item order
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(usize, &T) -> R + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| f(idx, item))
+            .collect();
+    }
+
+    let f = &f;
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|worker| {
+                scope.spawn(move || {
+                    items
+                        .iter()
+                        .enumerate()
+                        .skip(worker)
+                        .step_by(jobs)
+                        .map(|(idx, item)| (idx, f(idx, item)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("render thread panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Parses a memory size such as `512M`, `2G` or `800K` (or a plain byte count)
+pub fn parse_memory_size(value: &str) -> Result<usize, String> {
+    let value = value.trim();
+    let error = || {
+        format!(
+            "memory size must be a number optionally followed by K, M or G, e.g. 512M (got '{}')",
+            value
+        )
+    };
+    let (digits, multiplier) = match value.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&value[..value.len() - 1], 1 << 10),
+        Some('M') => (&value[..value.len() - 1], 1 << 20),
+        Some('G') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    let count: usize = digits.parse().map_err(|_| error())?;
+    count
+        .checked_mul(multiplier)
+        .filter(|&bytes| bytes > 0)
+        .ok_or_else(error)
+}
+
+/// Splits playlists, given their track counts, into consecutive batches whose
+/// rendered output should fit in `budget` bytes. A playlist larger than the
+/// budget gets a batch of its own; without a budget there is one batch.
+pub fn batches(track_counts: &[usize], budget: Option<usize>) -> Vec<Range<usize>> {
+    let budget = budget.unwrap_or(usize::MAX);
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut size: usize = 0;
+    for (idx, &tracks) in track_counts.iter().enumerate() {
+        let playlist_size = (tracks + 1).saturating_mul(BYTES_PER_TRACK);
+        if idx > start && size.saturating_add(playlist_size) > budget {
+            batches.push(start..idx);
+            start = idx;
+            size = 0;
+        }
+        size = size.saturating_add(playlist_size);
+    }
+    if start < track_counts.len() {
+        batches.push(start..track_counts.len());
+    }
+    batches
+}
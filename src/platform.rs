@@ -0,0 +1,65 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Device names Windows reserves in every directory, with or without an
+/// extension: `con.md` can't be created any more than `CON`
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Whether Windows refuses `name` as a file name, as it does any name whose
+/// part before the first dot is a device name. Output written elsewhere is
+/// often copied to Windows later, so this is checked on every platform.
+pub fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// `path` in a form that isn't limited to 260 characters: on Windows an
+/// absolute path gets the `\\?\` prefix (`\\?\UNC\` for network shares),
+/// which turns off that limit. Other platforms have no such limit, and
+/// their paths are returned as they are.
+pub fn long_path(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        let text = path.to_string_lossy();
+        if path.is_absolute() && !text.starts_with(r"\\?\") {
+            // Verbatim paths are passed on unparsed, so `/` must already be `\`
+            let text = text.replace('/', r"\");
+            return match text.strip_prefix(r"\\") {
+                Some(share) => PathBuf::from(format!(r"\\?\UNC\{}", share)),
+                None => PathBuf::from(format!(r"\\?\{}", text)),
+            };
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Creates `link` as a link to the directory `target`: a symlink, or on
+/// Windows a junction, which unlike a symlink needs no special privileges
+/// and which only takes an absolute target
+pub fn link_dir(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(windows)]
+    {
+        let target = link.parent().unwrap_or(Path::new(".")).join(target);
+        let status = std::process::Command::new("cmd")
+            .args(["/C", "mklink", "/J"])
+            .arg(link)
+            .arg(target)
+            .stdout(std::process::Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "mklink /J exited with {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    {
+        std::os::unix::fs::symlink(target, link)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::Playlist;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasmi::{Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+/// Directory searched for plugins unless `--plugin-dir` says otherwise
+pub const DEFAULT_PLUGIN_DIR: &str = "plugins";
+
+/// An output format implemented by a WebAssembly module, `<format>.wasm` in
+/// the plugin directory. Plugins run sandboxed in an interpreter and get no
+/// imports: no files, network or clock, only the playlist they are handed.
+///
+/// A plugin exports:
+///
+/// - `memory`: its linear memory
+/// - `alloc(len: i32) -> i32`: reserves `len` bytes for the host to write into
+/// - `extension() -> i64`: the file extension, as a UTF-8 string
+/// - `render(ptr: i32, len: i32) -> i64`: renders the playlist whose JSON (in
+///   the export's own shape) the host wrote at `ptr`, and returns the bytes of
+///   the output file
+///
+/// Strings and byte buffers are returned as an `i64` holding the pointer in
+/// the upper and the length in the lower 32 bits.
+pub struct Plugin {
+    name: String,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    render: TypedFunc<(i32, i32), i64>,
+    extension: String,
+}
+
+/// Path of the plugin implementing `format`, if there is one
+pub fn find_plugin(dir: &Path, format: &str) -> Option<PathBuf> {
+    let path = dir.join(format!("{}.wasm", format));
+    path.is_file().then_some(path)
+}
+
+fn split_buffer(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+impl Plugin {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let name = path.display().to_string();
+        let fail = |e: &dyn std::fmt::Display| format!("plugin {}: {}", name, e);
+
+        let bytes = fs::read(path).map_err(|e| fail(&e))?;
+        let engine = Engine::default();
+        let module = Module::new(&engine, bytes).map_err(|e| fail(&e))?;
+        let mut store = Store::new(&engine, ());
+        let instance: Instance = Linker::new(&engine)
+            .instantiate_and_start(&mut store, &module)
+            .map_err(|e| fail(&e))?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| fail(&"does not export its memory"))?;
+        let alloc = instance
+            .get_typed_func(&store, "alloc")
+            .map_err(|e| fail(&e))?;
+        let render = instance
+            .get_typed_func(&store, "render")
+            .map_err(|e| fail(&e))?;
+        let extension_func: TypedFunc<(), i64> = instance
+            .get_typed_func(&store, "extension")
+            .map_err(|e| fail(&e))?;
+
+        let packed = extension_func.call(&mut store, ()).map_err(|e| fail(&e))?;
+        let mut plugin = Self {
+            name,
+            store,
+            memory,
+            alloc,
+            render,
+            extension: String::new(),
+        };
+        let extension = String::from_utf8(plugin.read(packed)?)
+            .map_err(|_| plugin.error("extension is not UTF-8"))?;
+        if extension.is_empty() || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(plugin.error(format!("'{}' is not a file extension", extension)));
+        }
+        plugin.extension = extension;
+        Ok(plugin)
+    }
+
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// The output file for one playlist
+    pub fn render(&mut self, playlist: &Playlist) -> Result<Vec<u8>, String> {
+        let input = serde_json::to_vec(playlist).map_err(|e| self.error(e))?;
+        let len = i32::try_from(input.len()).map_err(|_| self.error("playlist is too large"))?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| self.error(e))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|e| self.error(e))?;
+        let packed = self
+            .render
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| self.error(e))?;
+        self.read(packed)
+    }
+
+    fn read(&self, packed: i64) -> Result<Vec<u8>, String> {
+        let (ptr, len) = split_buffer(packed);
+        let mut buffer = vec![0; len];
+        self.memory
+            .read(&self.store, ptr, &mut buffer)
+            .map_err(|e| self.error(e))?;
+        Ok(buffer)
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> String {
+        format!("plugin {}: {}", self.name, message)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{Playlist, console, csp, spotify, uri};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Directory, relative to the output directory, holding downloaded previews
+pub fn previews_dir() -> String {
+    format!("{}/previews", csp::ASSETS_DIR)
+}
+
+/// Downloads the 30-second preview clip of every track that has one into
+/// the previews directory and returns the clip's relative path by track URI.
+/// Clips already on disk from an earlier run are kept, not downloaded again;
+/// a clip that fails to download is reported and left out.
+pub fn download_previews(
+    client: &spotify::Client,
+    playlists: &[Playlist],
+    market: Option<&str>,
+    output_dir: &Path,
+) -> Result<HashMap<String, String>, String> {
+    let mut ids: Vec<&str> = playlists
+        .iter()
+        .flat_map(|playlist| &playlist.items)
+        .filter_map(|item| uri::track_id(&item.track.track_uri))
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let dir = output_dir.join(previews_dir());
+    fs::create_dir_all(&dir).map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+
+    let mut previews = HashMap::new();
+    for batch in ids.chunks(spotify::TRACKS_PER_REQUEST) {
+        let tracks = client.tracks(batch, market)?;
+        for (id, track) in batch.iter().zip(tracks) {
+            let Some(preview_url) = track.and_then(|track| track.preview_url) else {
+                continue;
+            };
+            let filename = format!("{}.mp3", id);
+            let path = dir.join(&filename);
+            if !path.is_file()
+                && let Err(e) = download_clip(client, &preview_url, &path)
+            {
+                console::warning(format!("Skipping the preview of {}: {}", id, e));
+                continue;
+            }
+            previews.insert(
+                format!("spotify:track:{}", id),
+                format!("{}/{}", previews_dir(), filename),
+            );
+        }
+    }
+
+    Ok(previews)
+}
+
+/// Downloads one clip to a temporary file renamed into place once complete,
+/// so an interrupted download isn't taken for a finished clip next run
+fn download_clip(client: &spotify::Client, url: &str, path: &Path) -> Result<(), String> {
+    let clip = client.download(url)?;
+    let partial = path.with_extension(format!("mp3.part-{}", std::process::id()));
+    fs::write(&partial, clip)
+        .and_then(|()| fs::rename(&partial, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            format!("could not write {}: {}", path.display(), e)
+        })
+}
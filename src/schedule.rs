@@ -0,0 +1,132 @@
+use crate::dates::Date;
+
+/// Set in the environment of the runs a scheduled process starts, so they
+/// convert once instead of scheduling again
+pub const SCHEDULED_RUN_ENV: &str = "SPOTIFY_CONVERTER_SCHEDULED_RUN";
+
+/// Times from a five-field cron expression (minute, hour, day of month,
+/// month, day of week), in UTC. Fields take `*`, numbers, ranges `a-b`,
+/// steps `*/n` or `a-b/n` and comma-separated lists; day of week runs from
+/// 0 (Sunday) to 6, with 7 also Sunday. As in cron, when both day fields are
+/// restricted a day matching either one matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Bit set of the values a field allows
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("invalid {} field '{}' in schedule", name, field);
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value = range.parse().map_err(|_| invalid())?;
+            // `5/15` means from 5 to the end in steps of 15
+            (value, if part.contains('/') { max } else { value })
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(format!(
+                "{} field '{}' must stay within {}-{}",
+                name, field, min, max
+            ));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+pub fn parse_schedule(value: &str) -> Result<Schedule, String> {
+    let fields: Vec<&str> = value.split_whitespace().collect();
+    let [minute, hour, day, month, weekday] = fields[..] else {
+        return Err(format!(
+            "schedule must have five fields, minute hour day month weekday, like \"0 3 * * *\" (got '{}')",
+            value
+        ));
+    };
+    let mut weekdays = parse_field(weekday, "day of week", 0, 7)?;
+    if weekdays & (1 << 7) != 0 {
+        weekdays |= 1;
+    }
+    Ok(Schedule {
+        minutes: parse_field(minute, "minute", 0, 59)?,
+        hours: parse_field(hour, "hour", 0, 23)?,
+        days: parse_field(day, "day of month", 1, 31)?,
+        months: parse_field(month, "month", 1, 12)?,
+        weekdays,
+        days_restricted: day != "*",
+        weekdays_restricted: weekday != "*",
+    })
+}
+
+impl Schedule {
+    fn matches_day(&self, date: Date) -> bool {
+        if self.months & (1 << date.month) == 0 {
+            return false;
+        }
+        // 1970-01-01 was a Thursday
+        let weekday = (date.days() + 4).rem_euclid(7);
+        let day_ok = self.days & (1 << date.day) != 0;
+        let weekday_ok = self.weekdays & (1 << weekday) != 0;
+        if self.days_restricted && self.weekdays_restricted {
+            day_ok || weekday_ok
+        } else {
+            day_ok && weekday_ok
+        }
+    }
+
+    /// The first matching minute strictly after `unix_seconds`, as Unix
+    /// seconds; `None` if nothing matches in the next eight years, as with
+    /// February 31
+    pub fn next_after(&self, unix_seconds: u64) -> Option<u64> {
+        let start_minute = unix_seconds / 60 + 1;
+        let first_day = (start_minute / 1440) as i64;
+        for day in first_day..first_day + 8 * 366 {
+            if !self.matches_day(Date::from_days(day)) {
+                continue;
+            }
+            for hour in 0..24u64 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+                for minute in 0..60u64 {
+                    let at = day as u64 * 1440 + hour * 60 + minute;
+                    if at >= start_minute && self.minutes & (1 << minute) != 0 {
+                        return Some(at * 60);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Unix seconds as `YYYY-MM-DD HH:MM UTC`
+pub fn format_utc(unix_seconds: u64) -> String {
+    let date = Date::from_days((unix_seconds / 86_400) as i64);
+    let minute_of_day = unix_seconds % 86_400 / 60;
+    format!(
+        "{} {:02}:{:02} UTC",
+        date,
+        minute_of_day / 60,
+        minute_of_day % 60
+    )
+}
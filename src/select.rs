@@ -0,0 +1,86 @@
+use crate::{Playlist, similarity};
+
+/// Names at least this similar to the one asked for, once both are reduced
+/// to their matching key, count as a match
+const NAME_SIMILARITY: f64 = 0.75;
+
+/// How many near misses an unmatched name is answered with
+const SUGGESTIONS: usize = 3;
+
+/// How a name given on the command line matched a playlist
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameMatch {
+    Exact,
+    /// Equal once case, emoji and punctuation are left out
+    Loose,
+    /// The name asked for is part of the playlist's
+    Partial,
+    Fuzzy,
+}
+
+/// A playlist name lowercased, without emoji, punctuation or repeated
+/// whitespace, so `"🔥 Summer  Hits!"` is matched by `summer hits`
+fn name_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn matches(kind: NameMatch, key: &str, query: &str) -> bool {
+    match kind {
+        NameMatch::Exact | NameMatch::Loose => key == query,
+        NameMatch::Partial => key.contains(query),
+        NameMatch::Fuzzy => similarity::similarity(key, query) >= NAME_SIMILARITY,
+    }
+}
+
+/// Indices of the playlists `query` names, with how they matched: those of
+/// the closest kind of match only, so an exact name never also picks up
+/// every playlist it's part of
+pub fn find_playlists(playlists: &[Playlist], query: &str) -> (Vec<usize>, Option<NameMatch>) {
+    let exact: Vec<usize> = (0..playlists.len())
+        .filter(|&idx| playlists[idx].name == query)
+        .collect();
+    if !exact.is_empty() {
+        return (exact, Some(NameMatch::Exact));
+    }
+
+    let query = name_key(query);
+    if query.is_empty() {
+        return (Vec::new(), None);
+    }
+    let keys: Vec<String> = playlists.iter().map(|p| name_key(&p.name)).collect();
+    for kind in [NameMatch::Loose, NameMatch::Partial, NameMatch::Fuzzy] {
+        let found: Vec<usize> = (0..keys.len())
+            .filter(|&idx| matches(kind, &keys[idx], &query))
+            .collect();
+        if !found.is_empty() {
+            return (found, Some(kind));
+        }
+    }
+    (Vec::new(), None)
+}
+
+/// Names of the playlists most like `query`, for when nothing matched it
+pub fn suggest_playlists<'a>(playlists: &'a [Playlist], query: &str) -> Vec<&'a str> {
+    let query = name_key(query);
+    let mut scored: Vec<(f64, &str)> = playlists
+        .iter()
+        .map(|p| {
+            (
+                similarity::similarity(&name_key(&p.name), &query),
+                p.name.as_str(),
+            )
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored
+        .into_iter()
+        .take(SUGGESTIONS)
+        .map(|(_, name)| name)
+        .collect()
+}
@@ -0,0 +1,677 @@
+use crate::console;
+use crate::graphql::{self, ArchiveSchema};
+use crate::metrics::{self, RunMetrics, ServerMetrics};
+use crate::{
+    Playlist, RenderOptions, csv, generate_html, generate_markdown, slug, track_json, uri,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::{Value, json};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Longest request head (request line and headers) the server reads
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+
+/// Largest request body accepted, for GraphQL queries
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+/// Most tracks a search returns
+const MAX_SEARCH_RESULTS: usize = 200;
+
+/// Which credentials the server asks clients for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthMode {
+    /// HTTP basic auth, which browsers prompt for
+    Basic,
+    /// A shared secret sent as `Authorization: Bearer TOKEN`, for scripts
+    Token,
+}
+
+pub fn parse_auth_mode(value: &str) -> Result<AuthMode, String> {
+    match value {
+        "basic" => Ok(AuthMode::Basic),
+        "token" => Ok(AuthMode::Token),
+        _ => Err(format!(
+            "auth must be one of 'basic' or 'token' (got '{}')",
+            value
+        )),
+    }
+}
+
+/// Credentials every request must carry, read from `SERVE_USERNAME` and
+/// `SERVE_PASSWORD` for basic auth or `SERVE_TOKEN` for token auth
+#[derive(Debug, Clone)]
+pub enum Auth {
+    Basic { username: String, password: String },
+    Token(String),
+}
+
+impl Auth {
+    pub fn from_env(mode: AuthMode) -> Result<Self, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        match mode {
+            AuthMode::Basic => match (var("SERVE_USERNAME"), var("SERVE_PASSWORD")) {
+                (Some(username), Some(password)) => Ok(Self::Basic { username, password }),
+                _ => Err(
+                    "--auth basic needs SERVE_USERNAME and SERVE_PASSWORD to be set".to_string(),
+                ),
+            },
+            AuthMode::Token => var("SERVE_TOKEN")
+                .map(Self::Token)
+                .ok_or_else(|| "--auth token needs SERVE_TOKEN to be set".to_string()),
+        }
+    }
+
+    /// Whether an `Authorization` header value carries the expected credentials
+    fn accepts(&self, authorization: Option<&str>) -> bool {
+        let Some((scheme, credentials)) = authorization.and_then(|value| value.split_once(' '))
+        else {
+            return false;
+        };
+        let credentials = credentials.trim();
+        match self {
+            Self::Basic { username, password } => {
+                scheme.eq_ignore_ascii_case("Basic")
+                    && STANDARD.decode(credentials).is_ok_and(|decoded| {
+                        constant_time_eq(&decoded, format!("{}:{}", username, password).as_bytes())
+                    })
+            }
+            Self::Token(token) => {
+                scheme.eq_ignore_ascii_case("Bearer")
+                    && constant_time_eq(credentials.as_bytes(), token.as_bytes())
+            }
+        }
+    }
+
+    /// `WWW-Authenticate` challenge sent with 401 responses
+    fn challenge(&self) -> &'static str {
+        match self {
+            Self::Basic { .. } => "Basic realm=\"spotify_converter\", charset=\"UTF-8\"",
+            Self::Token(_) => "Bearer realm=\"spotify_converter\"",
+        }
+    }
+}
+
+/// Compares secrets without returning early at the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The parsed export as the server sees it, with a URL slug per playlist
+pub struct Archive {
+    playlists: Vec<Playlist>,
+    slugs: Vec<String>,
+    /// Generated files served for paths outside `/api/`
+    static_dir: Option<PathBuf>,
+}
+
+impl Archive {
+    pub fn new(playlists: Vec<Playlist>, static_dir: Option<PathBuf>) -> Self {
+        let mut ids = slug::UniqueIds::new();
+        let slugs = playlists
+            .iter()
+            .map(|playlist| {
+                let slug = slug::slugify(&playlist.name);
+                ids.claim(if slug.is_empty() {
+                    "playlist".to_string()
+                } else {
+                    slug
+                })
+            })
+            .collect();
+        Self {
+            playlists,
+            slugs,
+            static_dir,
+        }
+    }
+
+    /// Every playlist with its slug, in export order
+    pub fn playlists(&self) -> impl Iterator<Item = (&Playlist, &str)> {
+        self.playlists
+            .iter()
+            .zip(self.slugs.iter().map(String::as_str))
+    }
+
+    /// The playlist with this slug, along with the slug as the archive holds it
+    pub fn find(&self, slug: &str) -> Option<(&Playlist, &str)> {
+        self.playlists().find(|(_, candidate)| *candidate == slug)
+    }
+
+    /// Tracks whose name, artist or album contains every word of the query,
+    /// ignoring case, as playlist, slug and item index. At most `limit` hits
+    /// are returned; the flag tells whether more tracks matched.
+    pub fn search(&self, query: &str, limit: usize) -> (Vec<(&Playlist, &str, usize)>, bool) {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut hits = Vec::new();
+
+        for (playlist, slug) in self.playlists() {
+            for (idx, item) in playlist.items.iter().enumerate() {
+                let haystack = format!("{}\n{}\n{}", item.title(), item.creator(), item.album())
+                    .to_lowercase();
+                if !words.iter().all(|word| haystack.contains(word)) {
+                    continue;
+                }
+                if hits.len() == limit {
+                    return (hits, true);
+                }
+                hits.push((playlist, slug, idx));
+            }
+        }
+        (hits, false)
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Declared body length, 0 without a `Content-Length` header
+    fn content_length(&self) -> u64 {
+        self.header("Content-Length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Value of a query string parameter, with `+` and `%XX` decoded
+    fn query(&self, name: &str) -> Option<String> {
+        let (_, query) = self.path.split_once('?')?;
+        query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (key == name).then(|| percent_decode(&value.replace('+', " ")))?
+        })
+    }
+}
+
+/// Representations of a playlist the API can render on request
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Representation {
+    Json,
+    Markdown,
+    Html,
+    Csv,
+}
+
+impl Representation {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+            Self::Csv => "csv",
+        }
+    }
+
+    fn from_format(format: &str) -> Option<Self> {
+        match format {
+            "json" => Some(Self::Json),
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" | "application/*" | "*/*" => Some(Self::Json),
+            "text/markdown" => Some(Self::Markdown),
+            "text/html" | "text/*" => Some(Self::Html),
+            "text/csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    /// The representation the `Accept` header prefers most, JSON when the
+    /// header is missing and `None` when it allows nothing the API renders
+    fn negotiate(accept: Option<&str>) -> Option<Self> {
+        let Some(accept) = accept else {
+            return Some(Self::Json);
+        };
+        let mut ranges: Vec<(&str, f32)> = accept
+            .split(',')
+            .map(|range| {
+                let mut params = range.split(';');
+                let media_type = params.next().unwrap_or("").trim();
+                let quality = params
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                (media_type, quality)
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges
+            .into_iter()
+            .find_map(|(media_type, _)| Self::from_media_type(media_type))
+    }
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec_pretty(value).unwrap_or_default(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &json!({ "error": message }))
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("jsonl") => "application/jsonl",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("tsv") => "text/tab-separated-values; charset=utf-8",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("xml" | "xsp") => "application/xml",
+        Some("yaml") => "application/yaml",
+        Some("mp3") => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Decodes `%XX` escapes in a URL path; `None` if they don't form UTF-8
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn playlist_summary(playlist: &Playlist, slug: &str) -> Value {
+    json!({
+        "slug": slug,
+        "name": playlist.name,
+        "url": format!("/api/playlists/{}", slug),
+        "tracks": playlist.items.len(),
+        "followers": playlist.number_of_followers,
+        "last_modified": playlist.last_modified_date,
+        "uri": playlist.uri,
+        "web_url": playlist.uri.as_deref().and_then(uri::web_url),
+    })
+}
+
+fn playlist_detail(playlist: &Playlist, slug: &str) -> Value {
+    let mut detail = playlist_summary(playlist, slug);
+    detail["description"] = json!(playlist.description);
+    detail["items"] = (0..playlist.items.len())
+        .map(|idx| playlist_detail_item(playlist, idx))
+        .collect();
+    detail
+}
+
+fn playlist_detail_item(playlist: &Playlist, idx: usize) -> Value {
+    let item = &playlist.items[idx];
+    let mut track = track_json(playlist, idx);
+    track["web_url"] = json!(uri::web_url(item.uri()));
+    track["added_date"] = json!(item.added_date);
+    track
+}
+
+/// A playlist in the representation picked by `?format=` or, without it,
+/// by the `Accept` header
+fn render_playlist(
+    playlist: &Playlist,
+    slug: &str,
+    request: &Request,
+    metrics: &ServerMetrics,
+) -> Response {
+    let representation = match request.query("format") {
+        Some(format) => match Representation::from_format(&format) {
+            Some(representation) => representation,
+            None => {
+                return Response::error(400, "format must be one of json, md, html or csv");
+            }
+        },
+        None => match Representation::negotiate(request.header("Accept")) {
+            Some(representation) => representation,
+            None => {
+                return Response::error(
+                    406,
+                    "available as application/json, text/markdown, text/html or text/csv",
+                );
+            }
+        },
+    };
+
+    metrics.count_render(representation.name());
+    let options = RenderOptions::default();
+    let (content_type, body) = match representation {
+        Representation::Json => return Response::json(200, &playlist_detail(playlist, slug)),
+        Representation::Markdown => (
+            "text/markdown; charset=utf-8",
+            generate_markdown(playlist, &options),
+        ),
+        Representation::Html => (
+            "text/html; charset=utf-8",
+            generate_html(playlist, &options),
+        ),
+        Representation::Csv => (
+            "text/csv; charset=utf-8",
+            csv::generate_csv(playlist, &csv::CsvOptions::default()),
+        ),
+    };
+    Response {
+        status: 200,
+        content_type,
+        body: body.into_bytes(),
+    }
+}
+
+fn search_tracks(archive: &Archive, query: &str) -> Value {
+    let (hits, truncated) = archive.search(query, MAX_SEARCH_RESULTS);
+    let results: Vec<Value> = hits
+        .into_iter()
+        .map(|(playlist, slug, idx)| {
+            let mut result = playlist_detail_item(playlist, idx);
+            result["playlist"] = json!({
+                "slug": slug,
+                "name": playlist.name,
+                "url": format!("/api/playlists/{}", slug),
+            });
+            result
+        })
+        .collect();
+
+    json!({
+        "query": query,
+        "results": results,
+        "truncated": truncated,
+    })
+}
+
+/// A file below the static directory; paths trying to leave it or reach a
+/// dot-file are refused
+fn static_file(dir: &Path, path: &str) -> Response {
+    let Some(decoded) = percent_decode(path) else {
+        return Response::error(400, "malformed path");
+    };
+    let relative = Path::new(decoded.trim_start_matches('/'));
+    // Dot-files are run state and version control (`.git/config` may hold a
+    // remote's credentials), never pages
+    if relative.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => true,
+    }) {
+        return Response::error(404, "not found");
+    }
+    let mut file = dir.join(relative);
+    if file.is_dir() {
+        file = file.join("index.html");
+    }
+    match std::fs::read(&file) {
+        Ok(body) => Response {
+            status: 200,
+            content_type: content_type(&file),
+            body,
+        },
+        Err(_) => Response::error(404, "not found"),
+    }
+}
+
+/// Runs a GraphQL query sent as JSON in a POST body or as `query` and
+/// `variables` parameters of a GET. A GET without a query returns the schema.
+fn graphql_query(schema: &ArchiveSchema, request: &Request) -> Response {
+    let query = if request.method == "POST" {
+        match serde_json::from_slice::<async_graphql::Request>(&request.body) {
+            Ok(query) => query,
+            Err(e) => return Response::error(400, &format!("invalid GraphQL request: {}", e)),
+        }
+    } else {
+        let Some(query) = request.query("query") else {
+            return Response {
+                status: 200,
+                content_type: "text/plain; charset=utf-8",
+                body: schema.sdl().into_bytes(),
+            };
+        };
+        let variables = match request.query("variables") {
+            Some(variables) => match serde_json::from_str(&variables) {
+                Ok(variables) => async_graphql::Variables::from_json(variables),
+                Err(e) => return Response::error(400, &format!("invalid variables: {}", e)),
+            },
+            None => async_graphql::Variables::default(),
+        };
+        async_graphql::Request::new(query).variables(variables)
+    };
+
+    let response = pollster::block_on(schema.execute(query));
+    Response {
+        status: 200,
+        content_type: "application/json",
+        body: serde_json::to_vec(&response).unwrap_or_default(),
+    }
+}
+
+fn route(service: &Service, request: &Request) -> Response {
+    let archive = &service.archive;
+    let path = request.path.split(['?', '#']).next().unwrap_or("/");
+
+    if path == "/api/graphql" {
+        return match &service.graphql {
+            Some(_) if !matches!(request.method.as_str(), "GET" | "HEAD" | "POST") => {
+                Response::error(405, "only GET, HEAD and POST are supported")
+            }
+            Some(schema) => graphql_query(schema, request),
+            None => Response::error(404, "GraphQL is not enabled; start serve with --graphql"),
+        };
+    }
+    if request.method != "GET" && request.method != "HEAD" {
+        return Response::error(405, "only GET and HEAD are supported");
+    }
+
+    if path == "/metrics" {
+        // Read on every scrape so runs regenerating the directory show up
+        let runs = archive.static_dir.as_deref().and_then(RunMetrics::load);
+        return Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4; charset=utf-8",
+            body: metrics::render_prometheus(runs.as_ref(), &service.metrics).into_bytes(),
+        };
+    }
+
+    if path == "/api/playlists" {
+        let playlists: Vec<Value> = archive
+            .playlists()
+            .map(|(playlist, slug)| playlist_summary(playlist, slug))
+            .collect();
+        return Response::json(200, &json!({ "playlists": playlists }));
+    }
+    if let Some(slug) = path.strip_prefix("/api/playlists/") {
+        return match archive.find(slug) {
+            Some((playlist, slug)) => render_playlist(playlist, slug, request, &service.metrics),
+            None => Response::error(404, "no playlist with this slug"),
+        };
+    }
+    if path == "/api/tracks" {
+        return match request.query("q") {
+            Some(query) if !query.trim().is_empty() => {
+                Response::json(200, &search_tracks(archive, &query))
+            }
+            _ => Response::error(400, "search with /api/tracks?q=WORDS"),
+        };
+    }
+    if path.starts_with("/api/") {
+        return Response::error(404, "unknown API endpoint");
+    }
+
+    match &archive.static_dir {
+        Some(dir) => static_file(dir, path),
+        None => Response::error(404, "not found"),
+    }
+}
+
+/// Reads the request head and, up to `MAX_BODY_BYTES`, its body. Bodies
+/// declared larger are left unread and the request is answered with 413.
+fn read_request(stream: &TcpStream) -> io::Result<Option<Request>> {
+    let mut reader = BufReader::new(io::Read::take(stream, MAX_HEAD_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            request
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let length = request.content_length();
+    if length <= MAX_BODY_BYTES {
+        // Part of the body may already sit in the buffer behind the head
+        let buffered = reader.buffer().len() as u64;
+        reader.get_mut().set_limit(length.saturating_sub(buffered));
+        io::Read::read_to_end(&mut reader, &mut request.body)?;
+        request.body.truncate(length as usize);
+    }
+    Ok(Some(request))
+}
+
+fn handle(service: &Service, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let Some(request) = read_request(&stream)? else {
+        return Ok(());
+    };
+    let authorized = service
+        .auth
+        .as_ref()
+        .is_none_or(|auth| auth.accepts(request.header("Authorization")));
+    let response = if !authorized {
+        Response::error(401, "authentication required")
+    } else if request.content_length() > MAX_BODY_BYTES {
+        Response::error(413, "request body too large")
+    } else {
+        route(service, &request)
+    };
+    service.metrics.count_response(response.status);
+    println!("{} {} {}", request.method, request.path, response.status);
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nVary: Accept\r\n",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    if let Some(auth) = service.auth.as_ref().filter(|_| response.status == 401) {
+        write!(stream, "WWW-Authenticate: {}\r\n", auth.challenge())?;
+    }
+    write!(stream, "Connection: close\r\n\r\n")?;
+    if request.method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+/// What the server answers from: the archive and, when enabled, a GraphQL
+/// schema over it and the credentials requests must carry
+struct Service {
+    archive: Arc<Archive>,
+    graphql: Option<ArchiveSchema>,
+    auth: Option<Auth>,
+    metrics: ServerMetrics,
+}
+
+/// Answers requests until the process is stopped, one thread per connection.
+/// With `graphql`, queries are also accepted at `/api/graphql`; with `auth`,
+/// every request without the credentials is refused with 401.
+pub fn serve(
+    listener: TcpListener,
+    archive: Archive,
+    graphql: bool,
+    auth: Option<Auth>,
+) -> io::Result<()> {
+    let archive = Arc::new(archive);
+    let service = Arc::new(Service {
+        graphql: graphql.then(|| graphql::schema(Arc::clone(&archive))),
+        archive,
+        auth,
+        metrics: ServerMetrics::default(),
+    });
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                console::failure(format!("connection failed: {}", e));
+                continue;
+            }
+        };
+        let service = Arc::clone(&service);
+        thread::spawn(move || {
+            if let Err(e) = handle(&service, stream) {
+                console::failure(format!("request failed: {}", e));
+            }
+        });
+    }
+    Ok(())
+}
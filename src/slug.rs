@@ -0,0 +1,262 @@
+use icu_normalizer::DecomposingNormalizerBorrowed;
+use std::collections::HashSet;
+
+/// Shortcodes for the emoji most commonly found in playlist names. Anything not
+/// listed falls back to its code point, e.g. `u1f9a9`.
+const EMOJI_SHORTCODES: &[(char, &str)] = &[
+    ('🔥', "fire"),
+    ('❤', "heart"),
+    ('💔', "broken_heart"),
+    ('💕', "two_hearts"),
+    ('💖', "sparkling_heart"),
+    ('💙', "blue_heart"),
+    ('💚', "green_heart"),
+    ('💛', "yellow_heart"),
+    ('💜', "purple_heart"),
+    ('🖤', "black_heart"),
+    ('🤍', "white_heart"),
+    ('🧡', "orange_heart"),
+    ('✨', "sparkles"),
+    ('⭐', "star"),
+    ('🌟', "star2"),
+    ('💫', "dizzy"),
+    ('🌙', "crescent_moon"),
+    ('☀', "sunny"),
+    ('🌞', "sun_with_face"),
+    ('🌈', "rainbow"),
+    ('🌊', "ocean"),
+    ('🌧', "cloud_with_rain"),
+    ('❄', "snowflake"),
+    ('🌸', "cherry_blossom"),
+    ('🌹', "rose"),
+    ('🌻', "sunflower"),
+    ('🌴', "palm_tree"),
+    ('🍂', "fallen_leaf"),
+    ('🎵', "musical_note"),
+    ('🎶', "notes"),
+    ('🎧', "headphones"),
+    ('🎤', "microphone"),
+    ('🎸', "guitar"),
+    ('🎹', "musical_keyboard"),
+    ('🎷', "saxophone"),
+    ('🎺', "trumpet"),
+    ('🥁', "drum"),
+    ('🎻', "violin"),
+    ('📻', "radio"),
+    ('💿', "cd"),
+    ('📀', "dvd"),
+    ('🎉', "tada"),
+    ('🎊', "confetti_ball"),
+    ('🥳', "partying_face"),
+    ('🍾', "champagne"),
+    ('🍻', "beers"),
+    ('🍷', "wine_glass"),
+    ('☕', "coffee"),
+    ('🏃', "runner"),
+    ('🏋', "weight_lifting"),
+    ('💪', "muscle"),
+    ('🚗', "car"),
+    ('🚀', "rocket"),
+    ('✈', "airplane"),
+    ('🏖', "beach_umbrella"),
+    ('🏠', "house"),
+    ('🌃', "night_with_stars"),
+    ('🌆', "city_sunset"),
+    ('💤', "zzz"),
+    ('😴', "sleeping"),
+    ('😀', "grinning"),
+    ('😁', "grin"),
+    ('😂', "joy"),
+    ('😊', "blush"),
+    ('😍', "heart_eyes"),
+    ('😎', "sunglasses"),
+    ('😢', "cry"),
+    ('😭', "sob"),
+    ('🥺', "pleading_face"),
+    ('🤘', "metal"),
+    ('👍', "thumbsup"),
+    ('👋', "wave"),
+    ('🙏', "pray"),
+    ('💃', "dancer"),
+    ('🕺', "man_dancing"),
+    ('👻', "ghost"),
+    ('🎃', "jack_o_lantern"),
+    ('🎄', "christmas_tree"),
+    ('🎅', "santa"),
+    ('💀', "skull"),
+    ('👽', "alien"),
+    ('🤖', "robot"),
+    ('🐶', "dog"),
+    ('🐱', "cat"),
+    ('🦋', "butterfly"),
+    ('🍕', "pizza"),
+    ('🍑', "peach"),
+    ('🍓', "strawberry"),
+    ('💎', "gem"),
+    ('💯', "100"),
+    ('⚡', "zap"),
+    ('☁', "cloud"),
+    ('🌍', "earth_africa"),
+    ('🌎', "earth_americas"),
+    ('🌏', "earth_asia"),
+];
+
+/// Code points that only modify a neighbouring emoji and carry no meaning of
+/// their own: variation selectors, zero width joiners and skin tones.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xFE0E | 0xFE0F | 0x200D | 0x20E3 | 0x1F3FB..=0x1F3FF
+    )
+}
+
+pub fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x2300..=0x23FF
+    )
+}
+
+pub fn emoji_shortcode(c: char) -> String {
+    EMOJI_SHORTCODES
+        .iter()
+        .find(|(emoji, _)| *emoji == c)
+        .map(|(_, code)| code.to_string())
+        .unwrap_or_else(|| format!("u{:x}", c as u32))
+}
+
+/// Replaces emoji with their shortcode names, separated from surrounding text
+/// by spaces, so `"🔥 Hits"` becomes `"fire Hits"` and `"🔥🔥"` becomes `"fire fire"`.
+pub fn replace_emoji(text: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for c in text.chars() {
+        if is_emoji_modifier(c) {
+            continue;
+        }
+        if is_emoji(c) {
+            if !current.trim().is_empty() {
+                words.push(current.trim().to_string());
+            }
+            current.clear();
+            words.push(emoji_shortcode(c));
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.trim().is_empty() {
+        words.push(current.trim().to_string());
+    }
+
+    words.join(" ")
+}
+
+/// Lowercase ASCII letters, digits and single hyphens, e.g. "road-trip".
+/// Emoji are spelled out as their shortcodes; may be empty.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+
+    for c in replace_emoji(text).chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Letters that don't decompose into an ASCII letter and accents, with how
+/// they're spelled in ASCII
+const ASCII_SPELLINGS: &[(char, &str)] = &[
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('Æ', "AE"),
+    ('œ', "oe"),
+    ('Œ', "OE"),
+    ('ø', "o"),
+    ('Ø', "O"),
+    ('đ', "d"),
+    ('Đ', "D"),
+    ('ð', "d"),
+    ('Ð', "D"),
+    ('ł', "l"),
+    ('Ł', "L"),
+    ('þ', "th"),
+    ('Þ', "TH"),
+    ('ı', "i"),
+];
+
+/// Text in ASCII: accents are taken off, so "Été à Tōkyō" becomes
+/// "Ete a Tokyo", letters such as `ß` are spelled out, and whatever has no
+/// ASCII spelling, emoji and other scripts included, is left out
+pub fn transliterate(text: &str) -> String {
+    let mut ascii = String::new();
+    for c in DecomposingNormalizerBorrowed::new_nfkd()
+        .normalize(text)
+        .chars()
+    {
+        if c.is_ascii() {
+            ascii.push(c);
+        } else if let Some((_, spelling)) = ASCII_SPELLINGS.iter().find(|(letter, _)| *letter == c)
+        {
+            ascii.push_str(spelling);
+        }
+    }
+    ascii
+}
+
+/// A lowercase ASCII slug for a filename, e.g. "ete-a-tokyo": transliterated,
+/// see [`transliterate`], with emoji spelled out as their shortcodes or left
+/// out. May be empty.
+pub fn ascii_slug(text: &str, emoji_shortcodes: bool) -> String {
+    if emoji_shortcodes {
+        slugify(&transliterate(&replace_emoji(text)))
+    } else {
+        slugify(&transliterate(text))
+    }
+}
+
+/// Turns arbitrary text into a valid HTML id / URL fragment: lowercase ASCII
+/// letters, digits and hyphens, starting with a letter. Emoji are spelled out
+/// as their shortcodes so emoji-only names still produce meaningful ids.
+pub fn anchor_id(text: &str) -> String {
+    let id = slugify(text);
+    if id.is_empty() {
+        "section".to_string()
+    } else if id.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("id-{}", id)
+    } else {
+        id
+    }
+}
+
+/// Hands out ids that are unique within one document, suffixing repeats with
+/// `-2`, `-3`, ...
+#[derive(Debug, Default)]
+pub struct UniqueIds {
+    used: HashSet<String>,
+}
+
+impl UniqueIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id_for(&mut self, prefix: &str, text: &str) -> String {
+        self.claim(format!("{}-{}", prefix, anchor_id(text)))
+    }
+
+    /// Reserves `base` as-is, or the first free suffixed variant of it
+    pub fn claim(&mut self, base: String) -> String {
+        let mut id = base.clone();
+        let mut n = 2;
+        while !self.used.insert(id.clone()) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        id
+    }
+}
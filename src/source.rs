@@ -0,0 +1,51 @@
+use crate::Playlist;
+use serde_json::{Map, Value};
+
+/// Spotify's own user id, owning its editorial and algorithmic playlists
+const SPOTIFY_OWNER: &str = "spotify";
+
+/// Name of a user object: `display_name`/`displayName`, falling back to `id`
+pub(crate) fn user_object_name(user: &Map<String, Value>) -> Option<String> {
+    ["display_name", "displayName", "id"]
+        .iter()
+        .filter_map(|key| user.get(*key).and_then(Value::as_str))
+        .find(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Name of a user given either as a plain string or as a user object
+fn user_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) if !name.is_empty() => Some(name.clone()),
+        Value::Object(user) => user_object_name(user),
+        _ => None,
+    }
+}
+
+fn user_id(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(id) => Some(id),
+        Value::Object(user) => user.get("id").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// Who owns the playlist, when the export includes it
+pub fn owner_name(playlist: &Playlist) -> Option<String> {
+    user_name(&playlist.owner)
+}
+
+/// Who a Spotify-generated playlist (Discover Weekly, Daily Mix, Blend) was
+/// made for, when the export includes it
+pub fn made_for_name(playlist: &Playlist) -> Option<String> {
+    user_name(&playlist.made_for)
+}
+
+/// Whether the export marks the playlist as made by Spotify rather than a
+/// person: it has a `madeFor` listener or is owned by the `spotify` user
+pub fn is_generated(playlist: &Playlist) -> bool {
+    let owned_by_spotify = user_id(&playlist.owner)
+        .is_some_and(|id| id.eq_ignore_ascii_case(SPOTIFY_OWNER))
+        || owner_name(playlist).is_some_and(|name| name.eq_ignore_ascii_case(SPOTIFY_OWNER));
+    !playlist.made_for.is_null() || owned_by_spotify
+}
@@ -0,0 +1,269 @@
+use crate::config;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+const TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+const API_URL: &str = "https://api.spotify.com/v1";
+
+/// Most track ids the several-tracks endpoint accepts per request
+pub const TRACKS_PER_REQUEST: usize = 50;
+
+/// Largest file `Client::download` accepts; preview clips are well under this
+const MAX_DOWNLOAD_BYTES: u64 = 10 * 1024 * 1024;
+
+/// App credentials for the client credentials flow, read from
+/// `SPOTIFY_CLIENT_ID` and `SPOTIFY_CLIENT_SECRET` or saved by `auth login`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl Credentials {
+    pub fn from_env() -> Result<Self, String> {
+        match (
+            std::env::var("SPOTIFY_CLIENT_ID"),
+            std::env::var("SPOTIFY_CLIENT_SECRET"),
+        ) {
+            (Ok(client_id), Ok(client_secret))
+                if !client_id.is_empty() && !client_secret.is_empty() =>
+            {
+                Ok(Self {
+                    client_id,
+                    client_secret,
+                })
+            }
+            _ => Err(
+                "Spotify API access needs SPOTIFY_CLIENT_ID and SPOTIFY_CLIENT_SECRET to be set"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Credentials from the environment, or else the ones saved by
+    /// `auth login` for the profile
+    pub fn load(profile: Option<&str>) -> Result<Self, String> {
+        Self::from_env().or_else(|e| match Self::stored(profile) {
+            Some(credentials) => Ok(credentials),
+            None => Err(format!("{}, or saved with `auth login`", e)),
+        })
+    }
+
+    /// Credentials saved by `auth login` for the profile, if any
+    pub fn stored(profile: Option<&str>) -> Option<Self> {
+        let path = config::credentials_path(profile)?;
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+
+    /// Saves the credentials to the user's config directory, readable only by
+    /// the user where the platform supports it
+    pub fn store(&self, profile: Option<&str>) -> Result<PathBuf, String> {
+        let path =
+            config::credentials_path(profile).ok_or("no home or config directory to save to")?;
+        let fail = |e: std::io::Error| format!("could not save {}: {}", path.display(), e);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(fail)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path).map_err(fail)?;
+        std::io::Write::write_all(&mut file, json.as_bytes()).map_err(fail)?;
+        Ok(path)
+    }
+}
+
+/// Parses an ISO 3166-1 alpha-2 country code, as the API expects for `market`
+pub fn parse_market(value: &str) -> Result<String, String> {
+    if value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(value.to_ascii_uppercase())
+    } else {
+        Err(format!(
+            "invalid market '{}': expected a two-letter country code such as SE",
+            value
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Restrictions {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiArtist {
+    pub name: String,
+}
+
+/// The fields of a Web API track object the converter uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiTrack {
+    pub uri: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub artists: Vec<ApiArtist>,
+    /// The track originally requested, when Spotify relinked it to another
+    /// release playable in the requested market
+    pub linked_from: Option<serde::de::IgnoredAny>,
+    /// Only present when a market was requested
+    pub is_playable: Option<bool>,
+    /// Only present when no market was requested
+    pub available_markets: Option<Vec<String>>,
+    pub restrictions: Option<Restrictions>,
+    /// 30-second MP3 clip, for the tracks and apps Spotify still offers one to
+    #[serde(default)]
+    pub preview_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TracksResponse {
+    tracks: Vec<Option<ApiTrack>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchPage {
+    items: Vec<ApiTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    tracks: SearchPage,
+}
+
+/// Percent-encodes a query parameter value
+fn encode_query(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub struct Client {
+    agent: ureq::Agent,
+    token: String,
+}
+
+impl Client {
+    /// Requests an access token for the app credentials
+    pub fn connect(credentials: &Credentials) -> Result<Self, String> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(30))
+            .build();
+        let basic = STANDARD.encode(format!(
+            "{}:{}",
+            credentials.client_id, credentials.client_secret
+        ));
+        let response: TokenResponse = agent
+            .post(TOKEN_URL)
+            .set("Authorization", &format!("Basic {}", basic))
+            .send_form(&[("grant_type", "client_credentials")])
+            .map_err(|e| format!("Spotify authentication failed: {}", e))?
+            .into_json()
+            .map_err(|e| format!("unexpected Spotify token response: {}", e))?;
+
+        Ok(Self {
+            agent,
+            token: response.access_token,
+        })
+    }
+
+    /// GETs an API path, waiting out rate limiting (HTTP 429) as instructed
+    /// by the `Retry-After` header
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}{}", API_URL, path);
+        loop {
+            match self
+                .agent
+                .get(&url)
+                .set("Authorization", &format!("Bearer {}", self.token))
+                .call()
+            {
+                Ok(response) => {
+                    return response
+                        .into_json()
+                        .map_err(|e| format!("unexpected Spotify API response: {}", e));
+                }
+                Err(ureq::Error::Status(429, response)) => {
+                    let wait = response
+                        .header("Retry-After")
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or(1);
+                    thread::sleep(Duration::from_secs(wait));
+                }
+                Err(e) => return Err(format!("Spotify API request failed: {}", e)),
+            }
+        }
+    }
+
+    /// Looks up to [`TRACKS_PER_REQUEST`] tracks by id. Ids Spotify no longer
+    /// knows come back as `None`, in the order they were requested.
+    pub fn tracks(
+        &self,
+        ids: &[&str],
+        market: Option<&str>,
+    ) -> Result<Vec<Option<ApiTrack>>, String> {
+        let mut path = format!("/tracks?ids={}", ids.join(","));
+        if let Some(market) = market {
+            path.push_str(&format!("&market={}", market));
+        }
+        let response: TracksResponse = self.get(&path)?;
+        Ok(response.tracks)
+    }
+
+    /// Downloads a file the API linked to, such as a preview clip
+    pub fn download(&self, url: &str) -> Result<Vec<u8>, String> {
+        let response = self
+            .agent
+            .get(url)
+            .call()
+            .map_err(|e| format!("download of {} failed: {}", url, e))?;
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .take(MAX_DOWNLOAD_BYTES)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("download of {} failed: {}", url, e))?;
+        Ok(data)
+    }
+
+    /// Searches the catalogue for tracks by title and artist
+    pub fn search_tracks(
+        &self,
+        track_name: &str,
+        artist_name: &str,
+        market: Option<&str>,
+    ) -> Result<Vec<ApiTrack>, String> {
+        let query = format!(
+            "track:{} artist:{}",
+            track_name.replace('"', ""),
+            artist_name.replace('"', "")
+        );
+        let mut path = format!("/search?type=track&limit=5&q={}", encode_query(&query));
+        if let Some(market) = market {
+            path.push_str(&format!("&market={}", market));
+        }
+        let response: SearchResponse = self.get(&path)?;
+        Ok(response.tracks.items)
+    }
+}
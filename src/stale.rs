@@ -0,0 +1,187 @@
+use crate::dates::{self, Date, Span};
+use crate::{
+    Playlist, bidi_html, escape_html, escape_markdown, get_common_styles, markdown_link_target,
+};
+use serde_json::{Value, json};
+
+/// Playlists last modified before `today - span`, oldest first. Playlists
+/// without a parseable date are left out, since their age is unknown.
+pub fn stale_playlists(playlists: &[Playlist], span: Span, today: Date) -> Vec<(usize, Date)> {
+    let cutoff = today.minus(span);
+    let mut stale: Vec<(usize, Date)> = playlists
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, playlist)| {
+            Date::parse(&playlist.last_modified_date).map(|date| (idx, date))
+        })
+        .filter(|(_, date)| *date < cutoff)
+        .collect();
+    stale.sort_by_key(|&(idx, date)| (date, idx));
+    stale
+}
+
+pub fn generate_stale_markdown(
+    stale: &[(usize, Date)],
+    playlists: &[Playlist],
+    filenames: &[String],
+    span: Span,
+    today: Date,
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Stale Playlists\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+
+    let total_tracks: usize = stale
+        .iter()
+        .map(|&(idx, _)| playlists[idx].items.len())
+        .sum();
+    md.push_str(&format!("- **Not Modified In:** {}\n", span));
+    md.push_str(&format!("- **Stale Playlists:** {}\n", stale.len()));
+    md.push_str(&format!(
+        "- **Tracks In Stale Playlists:** {}\n\n",
+        total_tracks
+    ));
+
+    if !stale.is_empty() {
+        md.push_str("| Playlist | Last Modified | Age | Tracks | Followers |\n");
+        md.push_str("|----------|---------------|-----|--------|-----------|\n");
+        for &(idx, date) in stale {
+            let playlist = &playlists[idx];
+            md.push_str(&format!(
+                "| [{}]({}) | {} | {} | {} | {} |\n",
+                escape_markdown(&playlist.name),
+                markdown_link_target(&filenames[idx]),
+                date,
+                dates::describe_days(date.days_until(today)),
+                playlist.items.len(),
+                playlist.number_of_followers
+            ));
+        }
+    }
+
+    md
+}
+
+pub fn generate_stale_html(
+    stale: &[(usize, Date)],
+    playlists: &[Playlist],
+    filenames: &[String],
+    span: Span,
+    today: Date,
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Stale Playlists</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        table {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            border-collapse: collapse;\n");
+    html.push_str("        }\n");
+    html.push_str("        th {\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            text-align: start;\n");
+    html.push_str("        }\n");
+    html.push_str("        td {\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            border-bottom: 1px solid #ddd;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Stale Playlists</h1>\n");
+
+    let total_tracks: usize = stale
+        .iter()
+        .map(|&(idx, _)| playlists[idx].items.len())
+        .sum();
+    html.push_str("        <ul>\n");
+    html.push_str(&format!(
+        "            <li><strong>Not Modified In:</strong> {}</li>\n",
+        span
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Stale Playlists:</strong> {}</li>\n",
+        stale.len()
+    ));
+    html.push_str(&format!(
+        "            <li><strong>Tracks In Stale Playlists:</strong> {}</li>\n",
+        total_tracks
+    ));
+    html.push_str("        </ul>\n");
+
+    if !stale.is_empty() {
+        html.push_str("        <table>\n");
+        html.push_str("            <thead>\n");
+        html.push_str("                <tr>\n");
+        for heading in ["Playlist", "Last Modified", "Age", "Tracks", "Followers"] {
+            html.push_str(&format!("                    <th>{}</th>\n", heading));
+        }
+        html.push_str("                </tr>\n");
+        html.push_str("            </thead>\n");
+        html.push_str("            <tbody>\n");
+        for &(idx, date) in stale {
+            let playlist = &playlists[idx];
+            html.push_str("                <tr>\n");
+            html.push_str(&format!(
+                "                    <td><a href=\"{}\">{}</a></td>\n",
+                escape_html(&filenames[idx]),
+                bidi_html(&playlist.name)
+            ));
+            html.push_str(&format!("                    <td>{}</td>\n", date));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                dates::describe_days(date.days_until(today))
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                playlist.items.len()
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                playlist.number_of_followers
+            ));
+            html.push_str("                </tr>\n");
+        }
+        html.push_str("            </tbody>\n");
+        html.push_str("        </table>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_stale_json(
+    stale: &[(usize, Date)],
+    playlists: &[Playlist],
+    filenames: &[String],
+    span: Span,
+    today: Date,
+) -> Value {
+    json!({
+        "not_modified_in": span.to_string(),
+        "as_of": today.to_string(),
+        "playlists": stale.iter().map(|&(idx, date)| {
+            let playlist = &playlists[idx];
+            json!({
+                "name": playlist.name,
+                "file": filenames[idx],
+                "last_modified": date.to_string(),
+                "age_days": date.days_until(today),
+                "tracks": playlist.items.len(),
+                "followers": playlist.number_of_followers,
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
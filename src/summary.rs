@@ -0,0 +1,122 @@
+use serde_json::{Value, json};
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// Summary of the run, written with `--report-format json`
+pub const SUMMARY_FILENAME: &str = "run-summary.json";
+
+/// What a conversion run did, printed as a table at the end so a problem
+/// in a big run doesn't hide behind "Done!"
+pub struct RunSummary {
+    pub playlists: usize,
+    pub tracks: usize,
+    /// Items left out of the output, those of excluded playlists
+    pub skipped: usize,
+    pub warnings: usize,
+    pub files: usize,
+    pub output_bytes: u64,
+    /// Playlists whose file name had to be changed to not overwrite another
+    /// file, with the base filename they got
+    pub renamed: Vec<(String, String)>,
+    /// Time each phase took, in the order they ran
+    phases: Vec<(&'static str, Duration)>,
+    phase_started: Instant,
+}
+
+impl RunSummary {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            playlists: 0,
+            tracks: 0,
+            skipped: 0,
+            warnings: 0,
+            files: 0,
+            output_bytes: 0,
+            renamed: Vec::new(),
+            phases: Vec::new(),
+            phase_started: started,
+        }
+    }
+
+    /// Ends the phase running since the previous one ended, or since the
+    /// run started
+    pub fn end_phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.phase_started));
+        self.phase_started = now;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    pub fn to_table(&self) -> String {
+        let mut rows = vec![
+            ("Playlists", self.playlists.to_string()),
+            ("Tracks", self.tracks.to_string()),
+            ("Skipped items", self.skipped.to_string()),
+            ("Warnings", self.warnings.to_string()),
+            (
+                "Files written",
+                format!("{} ({})", self.files, format_size(self.output_bytes)),
+            ),
+        ];
+        rows.extend(
+            self.phases
+                .iter()
+                .map(|(name, duration)| (*name, format_duration(*duration))),
+        );
+        rows.push(("Total time", format_duration(self.total())));
+
+        let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let mut table = String::from("Summary:\n");
+        for (label, value) in rows {
+            let _ = writeln!(table, "  {:<width$}  {}", label, value, width = width);
+        }
+        if !self.renamed.is_empty() {
+            table.push_str("Renamed to avoid overwriting another file:\n");
+            for (playlist, basename) in &self.renamed {
+                let _ = writeln!(table, "  \"{}\" -> {}", playlist, basename);
+            }
+        }
+        table
+    }
+
+    pub fn to_json(&self) -> Value {
+        let phases: Vec<Value> = self
+            .phases
+            .iter()
+            .map(|(name, duration)| json!({ "phase": name, "seconds": duration.as_secs_f64() }))
+            .collect();
+        let renamed: Vec<Value> = self
+            .renamed
+            .iter()
+            .map(|(playlist, basename)| json!({ "playlist": playlist, "basename": basename }))
+            .collect();
+        json!({
+            "playlists": self.playlists,
+            "tracks": self.tracks,
+            "skipped_items": self.skipped,
+            "warnings": self.warnings,
+            "files": self.files,
+            "output_bytes": self.output_bytes,
+            "renamed": renamed,
+            "phases": phases,
+            "total_seconds": self.total().as_secs_f64(),
+        })
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+/// A byte count with the K, M and G units `--max-memory` takes
+fn format_size(bytes: u64) -> String {
+    const UNITS: [(u64, &str); 3] = [(1 << 30, "G"), (1 << 20, "M"), (1 << 10, "K")];
+    UNITS
+        .iter()
+        .find(|(unit, _)| bytes >= *unit)
+        .map(|(unit, suffix)| format!("{:.1}{}", bytes as f64 / *unit as f64, suffix))
+        .unwrap_or_else(|| format!("{} bytes", bytes))
+}
@@ -0,0 +1,80 @@
+use crate::Playlist;
+use crate::collation::Collation;
+use std::collections::BTreeMap;
+
+/// Extracts `#hashtags` and `[bracketed]` tags from a playlist's name and
+/// description. Tags are lowercased and deduplicated, in order of appearance.
+pub fn extract_tags(playlist: &Playlist) -> Vec<String> {
+    let mut tags = Vec::new();
+
+    let description = playlist.description.as_deref().unwrap_or("");
+    for text in [playlist.name.as_str(), description] {
+        for tag in hashtags(text).into_iter().chain(bracketed(text)) {
+            let tag = tag.trim().to_lowercase();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    tags
+}
+
+fn hashtags(text: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut prev = ' ';
+
+    for (start, c) in text.char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            let rest = &text[start + 1..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            if end > 0 {
+                found.push(&rest[..end]);
+            }
+        }
+        prev = c;
+    }
+
+    found
+}
+
+fn bracketed(text: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = text;
+
+    while let Some(open) = rest.find('[') {
+        let after = &rest[open + 1..];
+        match after.find(']') {
+            Some(close) => {
+                found.push(&after[..close]);
+                rest = &after[close + 1..];
+            }
+            None => break,
+        }
+    }
+
+    found
+}
+
+pub fn tag_filename(tag: &str, extension: &str) -> String {
+    format!("tag-{}.{}", crate::sanitize_filename(tag), extension)
+}
+
+/// All tags across the given playlists, sorted by name, with the indices of
+/// the playlists carrying each one.
+pub fn collect_tags(playlists: &[Playlist], collation: &Collation) -> Vec<(String, Vec<usize>)> {
+    let mut tags: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+    for (idx, playlist) in playlists.iter().enumerate() {
+        for tag in extract_tags(playlist) {
+            tags.entry(tag).or_default().push(idx);
+        }
+    }
+
+    let mut tags: Vec<(String, Vec<usize>)> = tags.into_iter().collect();
+    let compare = collation.comparator();
+    tags.sort_by(|(a, _), (b, _)| compare(a, b));
+    tags
+}
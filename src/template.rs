@@ -0,0 +1,102 @@
+use crate::dates::Date;
+use crate::{Playlist, slug};
+
+/// Fields a filename template can refer to
+const FIELDS: &[&str] = &["name", "slug", "index", "year", "track_count", "format"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// A field, with the zero-padded width of `{index:03}`
+    Field {
+        name: String,
+        width: usize,
+    },
+}
+
+/// A parsed `--filename-template`, e.g. `{index:03}-{slug}`; a `/` puts the
+/// files in subdirectories, as `{name}/{format}` does
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilenameTemplate {
+    segments: Vec<Segment>,
+}
+
+pub fn parse_filename_template(value: &str) -> Result<FilenameTemplate, String> {
+    let mut segments = Vec::new();
+    let mut rest = value;
+
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            segments.push(Segment::Literal(rest[..open].to_string()));
+        }
+        let after = &rest[open + 1..];
+        let close = after
+            .find('}')
+            .ok_or_else(|| format!("unclosed '{{' in filename template '{}'", value))?;
+        let (name, width) = match after[..close].split_once(':') {
+            Some((name, width)) => (
+                name,
+                width.parse().map_err(|_| {
+                    format!(
+                        "field width must be a number, e.g. {{index:03}} (got '{}')",
+                        &after[..close]
+                    )
+                })?,
+            ),
+            None => (&after[..close], 0),
+        };
+        if !FIELDS.contains(&name) {
+            return Err(format!(
+                "unknown filename template field '{}' (expected one of {})",
+                name,
+                FIELDS.join(", ")
+            ));
+        }
+        segments.push(Segment::Field {
+            name: name.to_string(),
+            width,
+        });
+        rest = &after[close + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest.to_string()));
+    }
+
+    if segments.is_empty() {
+        return Err("filename template must not be empty".to_string());
+    }
+    Ok(FilenameTemplate { segments })
+}
+
+impl FilenameTemplate {
+    /// Fills in the template for the playlist at `index` (zero-based) in the
+    /// export. `name` is the playlist's usual base filename, `format` the
+    /// output format, e.g. `html`.
+    pub fn render(&self, playlist: &Playlist, index: usize, name: &str, format: &str) -> String {
+        let mut rendered = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Field { name: field, width } => {
+                    let value = match field.as_str() {
+                        "name" => name.to_string(),
+                        "format" => format.to_string(),
+                        "slug" => match slug::slugify(&playlist.name) {
+                            slug if slug.is_empty() => "playlist".to_string(),
+                            slug => slug,
+                        },
+                        "index" => (index + 1).to_string(),
+                        "year" => Date::parse(&playlist.last_modified_date)
+                            .map(|date| date.year.to_string())
+                            .unwrap_or_else(|| "unknown".to_string()),
+                        _ => playlist.items.len().to_string(),
+                    };
+                    rendered.push_str(&format!("{:0>width$}", value, width = *width));
+                }
+            }
+        }
+
+        rendered
+    }
+}
@@ -0,0 +1,226 @@
+use crate::dates::{self, Date};
+use crate::{
+    Playlist, bidi_html, escape_html, escape_markdown, get_common_styles, markdown_link_target,
+    track_json,
+};
+use serde_json::{Value, json};
+
+/// Oldest residents listed per playlist
+pub const OLDEST_RESIDENTS: usize = 5;
+
+/// How long the tracks of one playlist have been on it, in days
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistTenure {
+    pub playlist: usize,
+    pub median_days: i64,
+    /// Item index and tenure of the longest-standing tracks, oldest first
+    pub oldest: Vec<(usize, i64)>,
+}
+
+/// The latest date the export mentions, a stand-in for when it was taken
+/// since the export itself carries no timestamp
+pub fn latest_export_date(playlists: &[Playlist]) -> Option<Date> {
+    playlists
+        .iter()
+        .flat_map(|playlist| {
+            std::iter::once(playlist.last_modified_date.as_str())
+                .chain(playlist.items.iter().map(|item| item.added_date.as_str()))
+        })
+        .filter_map(Date::parse)
+        .max()
+}
+
+/// Tenure of every dated track as of `export_date`. Playlists without any
+/// dated tracks are left out.
+pub fn track_tenure(playlists: &[Playlist], export_date: Date) -> Vec<PlaylistTenure> {
+    playlists
+        .iter()
+        .enumerate()
+        .filter_map(|(playlist_idx, playlist)| {
+            let mut tenures: Vec<(usize, i64)> = playlist
+                .items
+                .iter()
+                .enumerate()
+                .filter_map(|(item_idx, item)| {
+                    Date::parse(&item.added_date)
+                        .map(|added| (item_idx, added.days_until(export_date).max(0)))
+                })
+                .collect();
+            if tenures.is_empty() {
+                return None;
+            }
+
+            tenures.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            let median_days = tenures[tenures.len() / 2].1;
+            tenures.truncate(OLDEST_RESIDENTS);
+
+            Some(PlaylistTenure {
+                playlist: playlist_idx,
+                median_days,
+                oldest: tenures,
+            })
+        })
+        .collect()
+}
+
+pub fn generate_tenure_markdown(
+    tenure: &[PlaylistTenure],
+    playlists: &[Playlist],
+    filenames: &[String],
+    export_date: Date,
+) -> String {
+    let mut md = String::new();
+
+    md.push_str("# Track Tenure\n\n");
+    md.push_str("[← Back to Index](index.md)\n\n");
+    md.push_str(&format!("- **As Of:** {}\n\n", export_date));
+
+    for entry in tenure {
+        let playlist = &playlists[entry.playlist];
+        md.push_str(&format!(
+            "## [{}]({})\n\n",
+            escape_markdown(&playlist.name),
+            markdown_link_target(&filenames[entry.playlist])
+        ));
+        md.push_str(&format!(
+            "- **Median Tenure:** {}\n\n",
+            dates::describe_days(entry.median_days)
+        ));
+        md.push_str("| # | Track Name | Artist | Added Date | On Playlist For |\n");
+        md.push_str("|---|------------|--------|------------|-----------------|\n");
+        for &(item_idx, days) in &entry.oldest {
+            let item = &playlist.items[item_idx];
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                item_idx + 1,
+                escape_markdown(&item.title()),
+                escape_markdown(&item.creator()),
+                item.added_date,
+                dates::describe_days(days)
+            ));
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+pub fn generate_tenure_html(
+    tenure: &[PlaylistTenure],
+    playlists: &[Playlist],
+    filenames: &[String],
+    export_date: Date,
+) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("    <meta charset=\"UTF-8\">\n");
+    html.push_str(
+        "    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+    );
+    html.push_str("    <title>Track Tenure</title>\n");
+    html.push_str("    <style>\n");
+    html.push_str(get_common_styles());
+    html.push_str("        table {\n");
+    html.push_str("            width: 100%;\n");
+    html.push_str("            border-collapse: collapse;\n");
+    html.push_str("        }\n");
+    html.push_str("        th {\n");
+    html.push_str("            background-color: #1db954;\n");
+    html.push_str("            color: white;\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            text-align: start;\n");
+    html.push_str("        }\n");
+    html.push_str("        td {\n");
+    html.push_str("            padding: 12px;\n");
+    html.push_str("            border-bottom: 1px solid #ddd;\n");
+    html.push_str("        }\n");
+    html.push_str("        tbody tr:first-child {\n");
+    html.push_str("            font-weight: bold;\n");
+    html.push_str("        }\n");
+    html.push_str("    </style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str("    <div class=\"container\">\n");
+    html.push_str("        <a href=\"index.html\" class=\"nav-link\">← Back to Index</a>\n");
+    html.push_str("        <h1>Track Tenure</h1>\n");
+    html.push_str(&format!(
+        "        <p><strong>As Of:</strong> {}</p>\n",
+        export_date
+    ));
+
+    for entry in tenure {
+        let playlist = &playlists[entry.playlist];
+        html.push_str(&format!(
+            "        <h2><a href=\"{}\">{}</a></h2>\n",
+            escape_html(&filenames[entry.playlist]),
+            bidi_html(&playlist.name)
+        ));
+        html.push_str(&format!(
+            "        <p><strong>Median Tenure:</strong> {}</p>\n",
+            dates::describe_days(entry.median_days)
+        ));
+        html.push_str("        <table>\n");
+        html.push_str("            <thead>\n");
+        html.push_str("                <tr>\n");
+        for heading in ["#", "Track Name", "Artist", "Added Date", "On Playlist For"] {
+            html.push_str(&format!("                    <th>{}</th>\n", heading));
+        }
+        html.push_str("                </tr>\n");
+        html.push_str("            </thead>\n");
+        html.push_str("            <tbody>\n");
+        for &(item_idx, days) in &entry.oldest {
+            let item = &playlist.items[item_idx];
+            html.push_str("                <tr>\n");
+            html.push_str(&format!("                    <td>{}</td>\n", item_idx + 1));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                bidi_html(&item.title())
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                bidi_html(&item.creator())
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                escape_html(&item.added_date)
+            ));
+            html.push_str(&format!(
+                "                    <td>{}</td>\n",
+                dates::describe_days(days)
+            ));
+            html.push_str("                </tr>\n");
+        }
+        html.push_str("            </tbody>\n");
+        html.push_str("        </table>\n");
+    }
+
+    html.push_str("    </div>\n");
+    html.push_str("</body>\n</html>");
+
+    html
+}
+
+pub fn generate_tenure_json(
+    tenure: &[PlaylistTenure],
+    playlists: &[Playlist],
+    filenames: &[String],
+    export_date: Date,
+) -> Value {
+    json!({
+        "as_of": export_date.to_string(),
+        "playlists": tenure.iter().map(|entry| {
+            let playlist = &playlists[entry.playlist];
+            json!({
+                "name": playlist.name,
+                "file": filenames[entry.playlist],
+                "median_days": entry.median_days,
+                "oldest": entry.oldest.iter().map(|&(item_idx, days)| {
+                    let mut track = track_json(playlist, item_idx);
+                    track["added_date"] = json!(playlist.items[item_idx].added_date);
+                    track["days"] = json!(days);
+                    track
+                }).collect::<Vec<_>>(),
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
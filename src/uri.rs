@@ -0,0 +1,55 @@
+use crate::links;
+
+/// Spotify URI kinds that have a public page on open.spotify.com
+const WEB_KINDS: &[&str] = &[
+    "track",
+    "album",
+    "artist",
+    "playlist",
+    "episode",
+    "show",
+    "audiobook",
+    "user",
+];
+
+/// Converts a `spotify:<kind>:<id>` URI into its `https://open.spotify.com/<kind>/<id>`
+/// web URL. Returns `None` for URIs without a web page, such as local files.
+pub fn web_url(uri: &str) -> Option<String> {
+    let mut parts = uri.split(':');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("spotify"), Some(kind), Some(id), None)
+            if WEB_KINDS.contains(&kind) && !id.is_empty() =>
+        {
+            Some(format!("https://open.spotify.com/{}/{}", kind, id))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the id of a `spotify:track:<id>` URI
+pub fn track_id(uri: &str) -> Option<&str> {
+    uri.strip_prefix("spotify:track:")
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Artist, album and title of a local file, as its URI gives them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalFile {
+    pub artist: String,
+    pub album: String,
+    pub title: String,
+}
+
+/// Reads a `spotify:local:<artist>:<album>:<title>:<seconds>` URI, whose
+/// parts are URL-encoded with `+` for spaces and may be empty
+pub fn local_file(uri: &str) -> Option<LocalFile> {
+    let mut parts = uri
+        .strip_prefix("spotify:local:")?
+        .split(':')
+        .map(|part| links::percent_decode(&part.replace('+', " ")));
+    Some(LocalFile {
+        artist: parts.next()?,
+        album: parts.next()?,
+        title: parts.next()?,
+    })
+}
@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+use std::fmt;
+
+/// Elements that never have a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is raw text up to the matching end tag
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Named character references the generators emit or users are likely to
+/// paste; anything else is reported as a probable escaping bug.
+const KNOWN_ENTITIES: &[&str] = &[
+    "amp", "lt", "gt", "quot", "apos", "nbsp", "copy", "reg", "hellip", "mdash", "ndash", "larr",
+    "rarr", "uarr", "darr", "middot", "bull",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+struct Checker<'a> {
+    html: &'a str,
+    pos: usize,
+    open: Vec<(String, usize)>,
+    ids: HashSet<String>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Checker<'a> {
+    fn line(&self, pos: usize) -> usize {
+        self.html[..pos].matches('\n').count() + 1
+    }
+
+    fn error(&mut self, pos: usize, message: String) {
+        let line = self.line(pos);
+        self.errors.push(ValidationError { line, message });
+    }
+
+    fn check_entities(&mut self, text: &str, offset: usize) {
+        let mut search = 0;
+        while let Some(found) = text[search..].find('&') {
+            let start = search + found;
+            let rest = &text[start + 1..];
+            let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'));
+            let valid = match end {
+                Some(end) if rest[end..].starts_with(';') => {
+                    let name = &rest[..end];
+                    if let Some(number) = name.strip_prefix('#') {
+                        match number.strip_prefix(['x', 'X']) {
+                            Some(hex) => {
+                                !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit())
+                            }
+                            None => {
+                                !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+                            }
+                        }
+                    } else {
+                        KNOWN_ENTITIES.contains(&name)
+                    }
+                }
+                _ => false,
+            };
+            if !valid {
+                self.error(
+                    offset + start,
+                    "unescaped '&' or unknown character reference".to_string(),
+                );
+            }
+            search = start + 1;
+        }
+    }
+
+    /// Checks the attributes of a start tag, stopping at the first malformed one
+    fn check_attributes(&mut self, attributes: &str, offset: usize) {
+        let mut rest = attributes.trim_end_matches('/');
+        let mut consumed = attributes.len() - attributes.trim_start().len();
+        rest = rest.trim_start();
+
+        while !rest.is_empty() {
+            let name_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=')
+                .unwrap_or(rest.len());
+            let name = &rest[..name_end];
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+            {
+                self.error(
+                    offset + consumed,
+                    format!("malformed attribute name '{}'", name),
+                );
+                return;
+            }
+            let mut after = &rest[name_end..];
+
+            if let Some(value) = after.trim_start().strip_prefix('=') {
+                let value = value.trim_start();
+                let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                    self.error(
+                        offset + consumed,
+                        format!("unquoted value for attribute '{}'", name),
+                    );
+                    return;
+                };
+                let Some(close) = value[1..].find(quote) else {
+                    self.error(
+                        offset + consumed,
+                        format!("unterminated value for attribute '{}'", name),
+                    );
+                    return;
+                };
+                let content = &value[1..1 + close];
+                if content.contains('<') {
+                    self.error(
+                        offset + consumed,
+                        format!("unescaped '<' in attribute '{}'", name),
+                    );
+                }
+                self.check_entities(content, offset + consumed);
+                if name == "id" && !self.ids.insert(content.to_string()) {
+                    self.error(offset + consumed, format!("duplicate id '{}'", content));
+                }
+                after = &value[1 + close + 1..];
+            }
+
+            let trimmed = after.trim_start();
+            consumed += rest.len() - trimmed.len();
+            rest = trimmed;
+        }
+    }
+
+    fn run(mut self) -> Vec<ValidationError> {
+        let html = self.html;
+
+        while self.pos < html.len() {
+            let Some(found) = html[self.pos..].find('<') else {
+                let text = &html[self.pos..];
+                self.check_entities(text, self.pos);
+                break;
+            };
+            let tag_start = self.pos + found;
+            self.check_entities(&html[self.pos..tag_start], self.pos);
+            let rest = &html[tag_start..];
+
+            if rest.starts_with("<!--") {
+                match rest.find("-->") {
+                    Some(end) => self.pos = tag_start + end + 3,
+                    None => {
+                        self.error(tag_start, "unterminated comment".to_string());
+                        break;
+                    }
+                }
+                continue;
+            }
+            if rest.starts_with("<!") {
+                self.pos = tag_start + rest.find('>').map(|end| end + 1).unwrap_or(rest.len());
+                continue;
+            }
+
+            let closing = rest.starts_with("</");
+            let name_start = if closing { 2 } else { 1 };
+            if !rest[name_start..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                self.error(tag_start, "unescaped '<' in text".to_string());
+                self.pos = tag_start + 1;
+                continue;
+            }
+            let Some(tag_len) = rest.find('>') else {
+                self.error(tag_start, "unterminated tag".to_string());
+                break;
+            };
+            let inner = &rest[name_start..tag_len];
+            let name_end = inner
+                .find(|c: char| c.is_whitespace() || c == '/')
+                .unwrap_or(inner.len());
+            let name = inner[..name_end].to_ascii_lowercase();
+            self.pos = tag_start + tag_len + 1;
+
+            if closing {
+                match self.open.pop() {
+                    Some((open, _)) if open == name => {}
+                    Some((open, line)) => {
+                        self.error(
+                            tag_start,
+                            format!("</{}> closes <{}> opened on line {}", name, open, line),
+                        );
+                        self.open.push((open, line));
+                    }
+                    None => {
+                        self.error(tag_start, format!("</{}> without matching start tag", name))
+                    }
+                }
+                continue;
+            }
+
+            self.check_attributes(&inner[name_end..], tag_start + name_start + name_end);
+
+            if VOID_ELEMENTS.contains(&name.as_str()) || inner.ends_with('/') {
+                continue;
+            }
+
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let end_tag = format!("</{}>", name);
+                match html[self.pos..].find(&end_tag) {
+                    Some(end) => {
+                        if name == "title" || name == "textarea" {
+                            let text = &html[self.pos..self.pos + end];
+                            if text.contains('<') {
+                                self.error(self.pos, format!("unescaped '<' in <{}>", name));
+                            }
+                            self.check_entities(text, self.pos);
+                        }
+                        self.pos += end + end_tag.len();
+                    }
+                    None => {
+                        self.error(tag_start, format!("<{}> is never closed", name));
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            let line = self.line(tag_start);
+            self.open.push((name, line));
+        }
+
+        for (name, line) in std::mem::take(&mut self.open) {
+            self.errors.push(ValidationError {
+                line,
+                message: format!("<{}> is never closed", name),
+            });
+        }
+
+        self.errors
+    }
+}
+
+/// Checks generated markup for well-formedness: balanced tags, quoted
+/// attributes, valid character references, unique ids and no stray `<`.
+/// This is deliberately stricter than HTML5 parsing rules, which would silently
+/// repair most of these, because every one of them points at an escaping bug.
+pub fn check_html(html: &str) -> Vec<ValidationError> {
+    Checker {
+        html,
+        pos: 0,
+        open: Vec::new(),
+        ids: HashSet::new(),
+        errors: Vec::new(),
+    }
+    .run()
+}
@@ -0,0 +1,94 @@
+use crate::{Playlist, escape_html, source, uri};
+
+/// A relative file reference as a URI: everything but unreserved characters
+/// and `/` percent-encoded
+fn uri_reference(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn push_element(xspf: &mut String, indent: &str, name: &str, value: &str) {
+    if !value.is_empty() {
+        xspf.push_str(&format!(
+            "{}<{}>{}</{}>\n",
+            indent,
+            name,
+            escape_html(value),
+            name
+        ));
+    }
+}
+
+fn push_header(xspf: &mut String) {
+    xspf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xspf.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+}
+
+/// An XSPF playlist with each track's title, artist as `<creator>`, album and
+/// Spotify URI as `<location>`. Tracks without a URI are kept without a
+/// location, leaving players to find them by their metadata.
+pub fn generate_xspf(playlist: &Playlist) -> String {
+    let mut xspf = String::new();
+    push_header(&mut xspf);
+    push_element(&mut xspf, "    ", "title", &playlist.name);
+    push_element(
+        &mut xspf,
+        "    ",
+        "creator",
+        &source::owner_name(playlist).unwrap_or_default(),
+    );
+    push_element(
+        &mut xspf,
+        "    ",
+        "annotation",
+        playlist.description.as_deref().unwrap_or_default(),
+    );
+    if let Some(web_url) = playlist.uri.as_deref().and_then(uri::web_url) {
+        push_element(&mut xspf, "    ", "info", &web_url);
+    }
+
+    xspf.push_str("    <trackList>\n");
+    for item in &playlist.items {
+        xspf.push_str("        <track>\n");
+        push_element(&mut xspf, "            ", "location", item.uri());
+        push_element(&mut xspf, "            ", "title", &item.title());
+        push_element(&mut xspf, "            ", "creator", &item.creator());
+        if let Some(web_url) = uri::web_url(item.uri()) {
+            push_element(&mut xspf, "            ", "info", &web_url);
+        }
+        push_element(&mut xspf, "            ", "album", &item.album());
+        xspf.push_str("        </track>\n");
+    }
+    xspf.push_str("    </trackList>\n");
+    xspf.push_str("</playlist>\n");
+
+    xspf
+}
+
+/// A playlist of the generated playlist files, one entry per file
+pub fn generate_index_xspf(playlists: &[Playlist], filenames: &[String]) -> String {
+    let mut xspf = String::new();
+    push_header(&mut xspf);
+    push_element(&mut xspf, "    ", "title", "Playlists");
+    xspf.push_str("    <trackList>\n");
+    for (playlist, filename) in playlists.iter().zip(filenames) {
+        xspf.push_str("        <track>\n");
+        push_element(
+            &mut xspf,
+            "            ",
+            "location",
+            &uri_reference(filename),
+        );
+        push_element(&mut xspf, "            ", "title", &playlist.name);
+        xspf.push_str("        </track>\n");
+    }
+    xspf.push_str("    </trackList>\n");
+    xspf.push_str("</playlist>\n");
+    xspf
+}
@@ -0,0 +1,34 @@
+//! `--sort-index name` orders the index by playlist name whether or not the
+//! index is grouped.
+
+use spotify_converter::{Playlist, RenderOptions, generate_index_html};
+
+fn playlist(name: &str) -> Playlist {
+    Playlist {
+        name: name.to_string(),
+        last_modified_date: "2024-01-01".to_string(),
+        ..Playlist::default()
+    }
+}
+
+#[test]
+fn ungrouped_html_index_is_sorted_by_name() {
+    let playlists = [playlist("Zebra"), playlist("apple"), playlist("Mango")];
+    let filenames = [
+        "Zebra.html".to_string(),
+        "apple.html".to_string(),
+        "Mango.html".to_string(),
+    ];
+    let options = RenderOptions {
+        sort_index_by_name: true,
+        ..RenderOptions::default()
+    };
+
+    let html = generate_index_html(&playlists, &filenames, &options);
+    let position = |filename: &str| {
+        html.find(&format!("href=\"{}\"", filename))
+            .unwrap_or_else(|| panic!("no card for {}: {}", filename, html))
+    };
+    assert!(position("apple.html") < position("Mango.html"));
+    assert!(position("Mango.html") < position("Zebra.html"));
+}
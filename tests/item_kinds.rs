@@ -0,0 +1,87 @@
+//! Episodes, audiobooks and local files leave `track` empty in the export;
+//! every writer has to read their names from the item instead.
+
+use spotify_converter::csv::{CsvOptions, generate_csv, generate_flat_csv};
+use spotify_converter::l10n::Localizer;
+use spotify_converter::{Playlist, email, xspf};
+
+fn playlist() -> Playlist {
+    serde_json::from_value(serde_json::json!({
+        "name": "Mixed",
+        "lastModifiedDate": "2024-01-01",
+        "collaborators": [],
+        "items": [
+            {
+                "track": null,
+                "episode": {
+                    "episodeName": "Episode Title",
+                    "showName": "Show Name",
+                    "episodeUri": "spotify:episode:abc"
+                },
+                "audiobook": null,
+                "localTrack": null,
+                "addedDate": "2024-01-01"
+            },
+            {
+                "track": null,
+                "episode": null,
+                "audiobook": null,
+                "localTrack": {
+                    "uri": "spotify:local:Local+Artist:Local+Album:Local+Song:180"
+                },
+                "addedDate": "2024-01-01"
+            }
+        ],
+        "description": null,
+        "numberOfFollowers": 0
+    }))
+    .unwrap()
+}
+
+#[test]
+fn csv_rows_carry_item_names() {
+    let playlist = playlist();
+    let csv = generate_csv(&playlist, &CsvOptions::default());
+    assert!(
+        csv.contains("Episode Title,Show Name,,spotify:episode:abc"),
+        "{}",
+        csv
+    );
+    assert!(
+        csv.contains("Local Song,Local Artist,Local Album"),
+        "{}",
+        csv
+    );
+
+    let flat = generate_flat_csv(std::slice::from_ref(&playlist), &CsvOptions::default());
+    assert!(
+        flat.contains("Mixed,2,Local Song,Local Artist,Local Album"),
+        "{}",
+        flat
+    );
+}
+
+#[test]
+fn xspf_tracks_carry_item_names() {
+    let xspf = xspf::generate_xspf(&playlist());
+    assert!(xspf.contains("<title>Episode Title</title>"), "{}", xspf);
+    assert!(xspf.contains("<creator>Local Artist</creator>"), "{}", xspf);
+    assert!(xspf.contains("<album>Local Album</album>"), "{}", xspf);
+}
+
+#[test]
+fn email_lists_item_names() {
+    let message = email::playlist_email(&playlist(), &Localizer::default());
+    assert!(
+        message.text.contains("1. Episode Title - Show Name"),
+        "{}",
+        message.text
+    );
+    assert!(
+        message
+            .text
+            .contains("2. Local Song - Local Artist (Local Album)"),
+        "{}",
+        message.text
+    );
+}
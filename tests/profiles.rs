@@ -0,0 +1,119 @@
+//! Options from a `--profile` act as defaults: the command line's replace
+//! them, list options included, and a profile may supply `--input` itself.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A fresh directory under the system temp dir, removed when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "spotify-converter-profile-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes an export holding a single playlist called `name`
+fn write_export(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(format!("{}.json", name));
+    let export = serde_json::json!({
+        "playlists": [{
+            "name": name,
+            "lastModifiedDate": "2024-01-01",
+            "collaborators": [],
+            "items": [],
+            "description": null,
+            "numberOfFollowers": 0
+        }]
+    });
+    fs::write(&path, export.to_string()).unwrap();
+    path
+}
+
+/// Writes profile `name` into the config directory under `home`
+fn write_profile(home: &Path, name: &str, settings: serde_json::Value) {
+    let config = if cfg!(target_os = "macos") {
+        home.join("Library/Application Support")
+    } else {
+        home.join(".config")
+    };
+    let dir = config.join("spotify_converter").join("profiles");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(format!("{}.json", name)), settings.to_string()).unwrap();
+}
+
+fn run(home: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_spotify_converter"))
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("APPDATA", home.join(".config"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn command_line_list_replaces_the_profile_list() {
+    let temp = TempDir::new("list");
+    let from_profile = write_export(&temp.0, "FromProfile");
+    let from_command_line = write_export(&temp.0, "FromCommandLine");
+    let output = temp.0.join("out");
+    write_profile(
+        &temp.0,
+        "lists",
+        serde_json::json!({
+            "input": [from_profile.to_str().unwrap()],
+            "output": output.to_str().unwrap()
+        }),
+    );
+
+    run(
+        &temp.0,
+        &[
+            "--profile",
+            "lists",
+            "--input",
+            from_command_line.to_str().unwrap(),
+        ],
+    );
+    assert!(output.join("FromCommandLine.md").exists());
+    assert!(!output.join("FromProfile.md").exists());
+}
+
+#[test]
+fn profile_can_supply_the_input() {
+    let temp = TempDir::new("input");
+    let from_profile = write_export(&temp.0, "FromProfile");
+    let output = temp.0.join("out");
+    write_profile(
+        &temp.0,
+        "inputs",
+        serde_json::json!({
+            "input": [from_profile.to_str().unwrap()],
+            "output": output.to_str().unwrap()
+        }),
+    );
+
+    run(&temp.0, &["--profile", "inputs"]);
+    assert!(output.join("FromProfile.md").exists());
+}
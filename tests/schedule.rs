@@ -0,0 +1,79 @@
+//! Cron expressions for `--schedule` and the times they fire at.
+
+use spotify_converter::dates::Date;
+use spotify_converter::schedule::parse_schedule;
+
+/// Unix seconds of `date` at `hour:minute` UTC
+fn at(date: &str, hour: u64, minute: u64) -> u64 {
+    Date::parse(date).unwrap().days() as u64 * 86_400 + hour * 3600 + minute * 60
+}
+
+fn next(expression: &str, after: u64) -> Option<u64> {
+    parse_schedule(expression).unwrap().next_after(after)
+}
+
+#[test]
+fn steps() {
+    assert_eq!(
+        next("*/15 * * * *", at("2024-01-01", 10, 7)),
+        Some(at("2024-01-01", 10, 15))
+    );
+    assert_eq!(
+        next("5/20 * * * *", at("2024-01-01", 10, 26)),
+        Some(at("2024-01-01", 10, 45))
+    );
+    // Strictly after: a run on the minute doesn't fire again at once
+    assert_eq!(
+        next("*/15 * * * *", at("2024-01-01", 10, 15)),
+        Some(at("2024-01-01", 10, 30))
+    );
+}
+
+#[test]
+fn ranges() {
+    assert_eq!(
+        next("0 9-17 * * *", at("2024-01-01", 17, 30)),
+        Some(at("2024-01-02", 9, 0))
+    );
+    // 2024-01-05 is a Friday; weekdays resume on Monday
+    assert_eq!(
+        next("0 3 * * 1-5", at("2024-01-05", 4, 0)),
+        Some(at("2024-01-08", 3, 0))
+    );
+    // 7 is Sunday as well as 0
+    assert_eq!(
+        next("0 0 * * 7", at("2024-01-01", 0, 0)),
+        Some(at("2024-01-07", 0, 0))
+    );
+}
+
+#[test]
+fn restricted_day_fields_match_either() {
+    // The 13th, or any Friday
+    assert_eq!(
+        next("0 0 13 * 5", at("2024-01-01", 0, 0)),
+        Some(at("2024-01-05", 0, 0))
+    );
+    assert_eq!(
+        next("0 0 13 * 5", at("2024-01-12", 0, 0)),
+        Some(at("2024-01-13", 0, 0))
+    );
+    // With day of week unrestricted, only the 13th
+    assert_eq!(
+        next("0 0 13 * *", at("2024-01-01", 0, 0)),
+        Some(at("2024-01-13", 0, 0))
+    );
+}
+
+#[test]
+fn impossible_dates_never_fire() {
+    assert_eq!(next("0 0 31 2 *", at("2024-01-01", 0, 0)), None);
+}
+
+#[test]
+fn invalid_expressions_are_rejected() {
+    assert!(parse_schedule("60 * * * *").is_err());
+    assert!(parse_schedule("0 3 * *").is_err());
+    assert!(parse_schedule("*/0 * * * *").is_err());
+    assert!(parse_schedule("0 5-3 * * *").is_err());
+}
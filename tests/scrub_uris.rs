@@ -0,0 +1,97 @@
+//! `--scrub-uris` has to keep every Spotify URI out of the output, whatever
+//! kind of item it belongs to.
+
+use spotify_converter::{
+    RenderOptions, Root, TrackAnchors, UriStyle, generate_html, generate_markdown, scrub_uris,
+};
+
+fn export() -> Root {
+    serde_json::from_value(serde_json::json!({
+        "playlists": [{
+            "name": "Mixed",
+            "lastModifiedDate": "2024-01-01",
+            "collaborators": [],
+            "items": [
+                {
+                    "track": null,
+                    "episode": {
+                        "episodeName": "Episode",
+                        "showName": "Show",
+                        "episodeUri": "spotify:episode:abc"
+                    },
+                    "audiobook": null,
+                    "localTrack": null,
+                    "addedDate": "2024-01-01"
+                },
+                {
+                    "track": null,
+                    "episode": null,
+                    "audiobook": {
+                        "audiobookName": "Book",
+                        "authorName": "Author",
+                        "audiobookUri": "spotify:audiobook:def"
+                    },
+                    "localTrack": null,
+                    "addedDate": "2024-01-01"
+                },
+                {
+                    "track": null,
+                    "episode": null,
+                    "audiobook": null,
+                    "localTrack": {
+                        "uri": "spotify:local:Local+Artist:Local+Album:Local+Song:180"
+                    },
+                    "addedDate": "2024-01-01"
+                }
+            ],
+            "description": null,
+            "numberOfFollowers": 0
+        }]
+    }))
+    .unwrap()
+}
+
+fn options() -> RenderOptions {
+    RenderOptions {
+        track_anchors: TrackAnchors::Uri,
+        uri_style: UriStyle::Both,
+        copy_buttons: true,
+        ..RenderOptions::default()
+    }
+}
+
+#[test]
+fn episodes_and_audiobooks_lose_their_uris() {
+    let mut root = export();
+    scrub_uris(&mut root);
+    let playlist = &root.playlists[0];
+
+    let html = generate_html(playlist, &options());
+    assert!(html.contains("Episode"));
+    assert!(!html.contains("spotify:"), "{}", html);
+    assert!(!html.contains("open.spotify.com/episode"), "{}", html);
+
+    let md = generate_markdown(playlist, &options());
+    assert!(md.contains("Episode"));
+    assert!(!md.contains("spotify:"), "{}", md);
+}
+
+#[test]
+fn local_files_keep_their_names_but_lose_their_uris() {
+    let mut root = export();
+    scrub_uris(&mut root);
+    let playlist = &root.playlists[0];
+
+    let html = generate_html(playlist, &options());
+    assert!(html.contains("Local Song"));
+    assert!(html.contains("Local Artist"));
+    assert!(!html.contains("spotify:"), "{}", html);
+}
+
+#[test]
+fn uri_anchors_cover_every_kind_of_item() {
+    let root = export();
+    let html = generate_html(&root.playlists[0], &options());
+    assert!(html.contains("<tr id=\"track-abc\">"), "{}", html);
+    assert!(html.contains("<tr id=\"track-def\">"), "{}", html);
+}
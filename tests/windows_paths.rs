@@ -0,0 +1,179 @@
+//! Paths that Windows refuses or limits: reserved characters, device names
+//! and output directories nested past 260 characters. The checks run on every
+//! platform, since output is often copied to Windows after it's generated.
+
+use spotify_converter::{MAX_BASENAME_BYTES, output, platform, sanitize_filename};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A fresh directory under the system temp dir, removed when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("spotify-converter-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A directory path more than 300 characters long below `base`
+fn nested_dir(base: &Path) -> PathBuf {
+    let mut dir = base.to_path_buf();
+    for level in 0..6 {
+        dir.push(format!("level-{}-{}", level, "d".repeat(50)));
+    }
+    assert!(dir.as_os_str().len() > 300);
+    dir
+}
+
+fn playlist(name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "lastModifiedDate": "2024-01-01",
+        "collaborators": [],
+        "items": [{
+            "track": {
+                "trackName": "Song",
+                "artistName": "Artist",
+                "albumName": "Album",
+                "trackUri": "spotify:track:abc"
+            },
+            "episode": null,
+            "audiobook": null,
+            "localTrack": null,
+            "addedDate": "2024-01-01"
+        }],
+        "description": null,
+        "numberOfFollowers": 0
+    })
+}
+
+#[test]
+fn reserved_characters_are_replaced() {
+    assert_eq!(
+        sanitize_filename(r#"a<b>c:d"e/f\g|h?i*j"#),
+        "a-b-c-d-e-f-g-h-i-j"
+    );
+    assert_eq!(sanitize_filename("tab\there"), "tab-here");
+    assert_eq!(sanitize_filename("..hidden"), "hidden");
+}
+
+#[test]
+fn device_names_are_not_used_as_is() {
+    assert_eq!(sanitize_filename("CON"), "CON-");
+    assert_eq!(sanitize_filename("nul"), "nul-");
+    assert_eq!(sanitize_filename("Com1 "), "Com1-");
+    assert_eq!(sanitize_filename("lpt9.backup"), "lpt9-.backup");
+    assert_eq!(sanitize_filename("aux .mix"), "aux-.mix");
+    assert_eq!(sanitize_filename("Console"), "Console");
+    assert_eq!(sanitize_filename("COM10"), "COM10");
+    assert!(!platform::is_reserved_name("Auxiliary"));
+}
+
+#[test]
+fn trailing_dots_and_spaces_are_dropped() {
+    assert_eq!(sanitize_filename("aux."), "aux-");
+    assert_eq!(sanitize_filename("con . "), "con-");
+    assert_eq!(sanitize_filename("Wait for it..."), "Wait for it");
+    assert_eq!(sanitize_filename("ctrl\u{7}\u{1b}name"), "ctrl--name");
+}
+
+#[test]
+fn long_names_are_cut_at_a_character_boundary() {
+    let name = sanitize_filename(&"é".repeat(100));
+    assert!(name.len() <= MAX_BASENAME_BYTES);
+    assert!(name.starts_with("éé"));
+    assert_ne!(name, sanitize_filename(&"é".repeat(101)));
+}
+
+#[cfg(not(windows))]
+#[test]
+fn long_path_leaves_other_platforms_alone() {
+    let path = Path::new("/tmp/some/output");
+    assert_eq!(platform::long_path(path), path);
+}
+
+#[cfg(windows)]
+#[test]
+fn long_path_adds_the_verbatim_prefix() {
+    assert_eq!(
+        platform::long_path(Path::new(r"C:\Music\out")),
+        Path::new(r"\\?\C:\Music\out")
+    );
+    assert_eq!(
+        platform::long_path(Path::new("C:/Music/out")),
+        Path::new(r"\\?\C:\Music\out")
+    );
+    assert_eq!(
+        platform::long_path(Path::new(r"\\server\share\out")),
+        Path::new(r"\\?\UNC\server\share\out")
+    );
+    assert_eq!(
+        platform::long_path(Path::new(r"\\?\C:\Music")),
+        Path::new(r"\\?\C:\Music")
+    );
+    assert_eq!(platform::long_path(Path::new("out")), Path::new("out"));
+}
+
+#[test]
+fn nested_output_directory_is_writable() {
+    let temp = TempDir::new("nested-dir");
+    let dir = nested_dir(&temp.0);
+
+    let output_dir = output::prepare_output_dir(dir.to_str().unwrap()).unwrap();
+    output::check_writable(&output_dir).unwrap();
+    let file = output::output_file(&output_dir, "CON-.md").unwrap();
+    fs::write(&file, "x").unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "x");
+}
+
+#[test]
+fn conversion_into_a_long_path_opens_every_file() {
+    let temp = TempDir::new("conversion");
+    let dir = nested_dir(&temp.0);
+    let input = temp.0.join("Playlist1.json");
+    let long_name = "Long ".repeat(60);
+    let export = serde_json::json!({
+        "playlists": [playlist("CON"), playlist("Mix: A/B?"), playlist(&long_name)]
+    });
+    fs::write(&input, export.to_string()).unwrap();
+
+    let run = Command::new(env!("CARGO_BIN_EXE_spotify_converter"))
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&dir)
+        .args(["--format", "html", "--embed"])
+        .output()
+        .unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            fs::read(entry.path()).unwrap();
+            entry.file_name().into_string().unwrap()
+        })
+        .collect();
+    names.sort();
+    assert!(names.contains(&"CON-.html".to_string()));
+    assert!(names.contains(&"Mix- A-B-.html".to_string()));
+    assert!(names.contains(&"index.html".to_string()));
+    assert!(names.iter().all(|name| !name.starts_with(".write-check")));
+    assert!(names.iter().all(|name| name.len() <= 143));
+}